@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS products;
+DROP TABLE IF EXISTS phone_verifications;
+DROP VIEW IF EXISTS farm;
+DROP TABLE IF EXISTS farms;
+DROP TABLE IF EXISTS farmers;
+DROP FUNCTION IF EXISTS set_updated_at();
@@ -0,0 +1,106 @@
+-- Core schema for farmers, farms, phone verification and products.
+
+CREATE EXTENSION IF NOT EXISTS postgis;
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE farmers (
+    id                   UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    phone_number         TEXT NOT NULL UNIQUE,
+    email                TEXT,
+    first_name           TEXT NOT NULL,
+    last_name            TEXT NOT NULL,
+    registration_channel TEXT NOT NULL DEFAULT 'Web',
+    verification_status  TEXT NOT NULL DEFAULT 'pending',
+    profile_completed    BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER farmers_set_updated_at
+    BEFORE UPDATE ON farmers
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE farms (
+    id                 UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id          UUID NOT NULL REFERENCES farmers(id) ON DELETE CASCADE,
+    farm_name          TEXT,
+    location           GEOMETRY(Point, 4326),
+    address_text       TEXT,
+    farm_size_hectares NUMERIC(12, 4),
+    farm_type          TEXT NOT NULL DEFAULT 'subsistence',
+    primary_crops      JSONB NOT NULL DEFAULT '[]'::jsonb,
+    created_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at         TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX farms_farmer_id_idx ON farms (farmer_id);
+CREATE INDEX farms_location_idx ON farms USING GIST (location);
+
+CREATE TRIGGER farms_set_updated_at
+    BEFORE UPDATE ON farms
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- The phone verification handler looks a farm up by the owner's phone number
+-- and reads its id as text. Expose that shape as a view over `farms`.
+CREATE VIEW farm AS
+SELECT fm.id::text AS id,
+       f.phone_number,
+       fm.farmer_id,
+       fm.farm_name,
+       fm.created_at
+FROM farms fm
+JOIN farmers f ON f.id = fm.farmer_id;
+
+CREATE TABLE phone_verifications (
+    id           UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    phone_number TEXT NOT NULL,
+    otp_code     TEXT NOT NULL,
+    expires_at   TIMESTAMPTZ NOT NULL,
+    verified     BOOLEAN NOT NULL DEFAULT FALSE,
+    attempts     INTEGER NOT NULL DEFAULT 0,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX phone_verifications_phone_created_idx
+    ON phone_verifications (phone_number, created_at DESC);
+
+CREATE TABLE products (
+    id                    UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id             UUID NOT NULL REFERENCES farmers(id) ON DELETE CASCADE,
+    farm_id               UUID REFERENCES farms(id) ON DELETE SET NULL,
+    name                  TEXT NOT NULL,
+    slug                  TEXT NOT NULL UNIQUE,
+    description           TEXT,
+    category              TEXT NOT NULL,
+    unit                  TEXT NOT NULL DEFAULT 'kg',
+    tags                  TEXT[] NOT NULL DEFAULT '{}',
+    price_cents           BIGINT NOT NULL CHECK (price_cents >= 0),
+    currency_code         TEXT NOT NULL DEFAULT 'NGN',
+    min_order_qty         INTEGER NOT NULL DEFAULT 1 CHECK (min_order_qty > 0),
+    quantity_available    INTEGER NOT NULL DEFAULT 0 CHECK (quantity_available >= 0),
+    organic               BOOLEAN NOT NULL DEFAULT FALSE,
+    perishable            BOOLEAN NOT NULL DEFAULT FALSE,
+    expected_harvest_date DATE,
+    expiry_date           DATE,
+    status                TEXT NOT NULL DEFAULT 'draft'
+                          CHECK (status IN ('draft', 'published', 'archived')),
+    visibility            TEXT NOT NULL DEFAULT 'both'
+                          CHECK (visibility IN ('local_only', 'public', 'both')),
+    images                TEXT[] NOT NULL DEFAULT '{}',
+    created_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX products_farmer_id_idx ON products (farmer_id);
+CREATE INDEX products_farm_id_idx ON products (farm_id);
+
+CREATE TRIGGER products_set_updated_at
+    BEFORE UPDATE ON products
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
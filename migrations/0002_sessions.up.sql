@@ -0,0 +1,15 @@
+-- Server-side session store used when SESSION_STORE=postgres.
+
+CREATE TABLE sessions (
+    session_key  TEXT PRIMARY KEY,
+    session_id   UUID UNIQUE,
+    farmer_id    UUID REFERENCES farmers(id) ON DELETE CASCADE,
+    user_agent   TEXT,
+    state        JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at   TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX sessions_farmer_id_idx ON sessions (farmer_id) WHERE farmer_id IS NOT NULL;
+CREATE INDEX sessions_expires_at_idx ON sessions (expires_at);
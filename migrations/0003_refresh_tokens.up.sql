@@ -0,0 +1,17 @@
+-- Rotating refresh tokens for bearer-token clients. Only a SHA-256 hash of
+-- each token is stored. Tokens descending from one login share a family_id so
+-- a replayed token can revoke the whole chain.
+
+CREATE TABLE refresh_tokens (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id   UUID NOT NULL REFERENCES farmers(id) ON DELETE CASCADE,
+    family_id   UUID NOT NULL,
+    token_hash  TEXT NOT NULL UNIQUE,
+    expires_at  TIMESTAMPTZ NOT NULL,
+    revoked_at  TIMESTAMPTZ,
+    replaced_by UUID REFERENCES refresh_tokens(id) ON DELETE SET NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens (family_id);
+CREATE INDEX refresh_tokens_farmer_id_idx ON refresh_tokens (farmer_id);
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS rate_limit_events;
+
+DELETE FROM phone_verifications;
+ALTER TABLE phone_verifications DROP COLUMN IF EXISTS request_ip;
+ALTER TABLE phone_verifications RENAME COLUMN otp_hash TO otp_code;
@@ -0,0 +1,15 @@
+-- OTP codes are now stored as a keyed HMAC (OTP_PEPPER); existing plaintext codes are
+-- short-lived and simply dropped.
+DELETE FROM phone_verifications;
+ALTER TABLE phone_verifications RENAME COLUMN otp_code TO otp_hash;
+ALTER TABLE phone_verifications ADD COLUMN request_ip TEXT;
+
+CREATE TABLE rate_limit_events (
+    id         BIGSERIAL PRIMARY KEY,
+    action     TEXT NOT NULL,
+    subject    TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX rate_limit_events_lookup_idx
+    ON rate_limit_events (action, subject, created_at DESC);
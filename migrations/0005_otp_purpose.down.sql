@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS phone_verifications_phone_purpose_created_idx;
+ALTER TABLE phone_verifications DROP COLUMN IF EXISTS purpose;
+CREATE INDEX phone_verifications_phone_created_idx
+    ON phone_verifications (phone_number, created_at DESC);
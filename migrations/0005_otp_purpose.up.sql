@@ -0,0 +1,9 @@
+-- Codes are issued for a specific purpose so a login code can't complete a
+-- registration and vice versa.
+ALTER TABLE phone_verifications
+    ADD COLUMN purpose TEXT NOT NULL DEFAULT 'login'
+    CHECK (purpose IN ('registration', 'login'));
+
+DROP INDEX IF EXISTS phone_verifications_phone_created_idx;
+CREATE INDEX phone_verifications_phone_purpose_created_idx
+    ON phone_verifications (phone_number, purpose, created_at DESC);
@@ -0,0 +1 @@
+ALTER TABLE farmers DROP CONSTRAINT IF EXISTS farmers_phone_number_e164;
@@ -0,0 +1,28 @@
+-- Phone numbers are stored in E.164 (+2348031234567). Rewrite the Nigerian
+-- local (0803...) and unprefixed (234803...) forms written before that, unless
+-- the canonical form already exists for another row.
+UPDATE farmers f
+SET phone_number = '+234' || substr(f.phone_number, 2)
+WHERE f.phone_number ~ '^0[789][0-9]{9}$'
+  AND NOT EXISTS (SELECT 1 FROM farmers o WHERE o.phone_number = '+234' || substr(f.phone_number, 2));
+
+UPDATE farmers f
+SET phone_number = '+' || f.phone_number
+WHERE f.phone_number ~ '^234[789][0-9]{9}$'
+  AND NOT EXISTS (SELECT 1 FROM farmers o WHERE o.phone_number = '+' || f.phone_number);
+
+UPDATE phone_verifications
+SET phone_number = '+234' || substr(phone_number, 2)
+WHERE phone_number ~ '^0[789][0-9]{9}$';
+
+UPDATE phone_verifications
+SET phone_number = '+' || phone_number
+WHERE phone_number ~ '^234[789][0-9]{9}$';
+
+-- Old hashes were computed over the un-normalised number and can't verify.
+DELETE FROM phone_verifications WHERE NOT verified;
+
+-- NOT VALID so rows that could not be rewritten don't block the migration;
+-- every new write is checked.
+ALTER TABLE farmers
+    ADD CONSTRAINT farmers_phone_number_e164 CHECK (phone_number ~ '^\+[1-9][0-9]{7,14}$') NOT VALID;
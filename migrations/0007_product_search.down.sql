@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS products_published_harvest_idx;
+DROP INDEX IF EXISTS products_published_price_idx;
+DROP INDEX IF EXISTS products_published_newest_idx;
+DROP INDEX IF EXISTS products_category_idx;
+DROP INDEX IF EXISTS products_tags_idx;
+DROP INDEX IF EXISTS products_search_vector_idx;
+DROP TRIGGER IF EXISTS products_search_vector ON products;
+DROP FUNCTION IF EXISTS products_search_vector();
+ALTER TABLE products DROP COLUMN IF EXISTS search_vector;
@@ -0,0 +1,29 @@
+-- Full-text search document for the catalogue. Maintained by a trigger
+-- rather than a generated column because array_to_string is not immutable.
+ALTER TABLE products ADD COLUMN search_vector TSVECTOR;
+
+CREATE OR REPLACE FUNCTION products_search_vector() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', coalesce(NEW.name, '')), 'A') ||
+        setweight(to_tsvector('english', array_to_string(NEW.tags, ' ')), 'A') ||
+        setweight(to_tsvector('english', coalesce(NEW.description, '')), 'B');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER products_search_vector
+    BEFORE INSERT OR UPDATE OF name, description, tags ON products
+    FOR EACH ROW EXECUTE FUNCTION products_search_vector();
+
+UPDATE products SET name = name;
+
+CREATE INDEX products_search_vector_idx ON products USING GIN (search_vector);
+CREATE INDEX products_tags_idx ON products USING GIN (tags);
+CREATE INDEX products_category_idx ON products (category);
+
+-- Keyset pagination indexes, one per sort order; listings are almost always
+-- restricted to published products.
+CREATE INDEX products_published_newest_idx ON products (created_at DESC, id DESC) WHERE status = 'published';
+CREATE INDEX products_published_price_idx ON products (price_cents, id) WHERE status = 'published';
+CREATE INDEX products_published_harvest_idx ON products (expected_harvest_date, id) WHERE status = 'published';
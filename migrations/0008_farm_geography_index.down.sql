@@ -0,0 +1 @@
+DROP INDEX IF EXISTS farms_location_geography_idx;
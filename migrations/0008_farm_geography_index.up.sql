@@ -0,0 +1,3 @@
+-- Distance searches run on geography (metres on the spheroid), which can't
+-- use the planar index on the geometry column.
+CREATE INDEX farms_location_geography_idx ON farms USING GIST ((location::geography));
@@ -0,0 +1,8 @@
+CREATE VIEW farm AS
+SELECT fm.id::text AS id,
+       f.phone_number,
+       fm.farmer_id,
+       fm.farm_name,
+       fm.created_at
+FROM farms fm
+JOIN farmers f ON f.id = fm.farmer_id;
@@ -0,0 +1,2 @@
+-- Sessions now read the farmer's farms directly.
+DROP VIEW IF EXISTS farm;
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS farm_plots;
+DROP INDEX IF EXISTS farms_boundary_idx;
+ALTER TABLE farms DROP COLUMN IF EXISTS boundary_area_hectares;
+ALTER TABLE farms DROP COLUMN IF EXISTS boundary;
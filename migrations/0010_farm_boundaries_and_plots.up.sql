@@ -0,0 +1,26 @@
+-- Mapped farm outline. Polygons are stored as MultiPolygon so farms split
+-- across several parcels fit the same column.
+ALTER TABLE farms ADD COLUMN boundary GEOMETRY(MultiPolygon, 4326);
+ALTER TABLE farms ADD COLUMN boundary_area_hectares NUMERIC(12, 4);
+
+CREATE INDEX farms_boundary_idx ON farms USING GIST (boundary);
+
+-- Named plots within a farm. Plot names are what farm activities record in
+-- `field_plot`.
+CREATE TABLE farm_plots (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farm_id       UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    name          TEXT NOT NULL CHECK (btrim(name) <> ''),
+    boundary      GEOMETRY(MultiPolygon, 4326),
+    area_hectares NUMERIC(12, 4) CHECK (area_hectares > 0),
+    notes         TEXT,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX farm_plots_farm_name_idx ON farm_plots (farm_id, lower(name));
+CREATE INDEX farm_plots_boundary_idx ON farm_plots USING GIST (boundary);
+
+CREATE TRIGGER farm_plots_set_updated_at
+    BEFORE UPDATE ON farm_plots
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,29 @@
+CREATE TABLE farm_activities (
+    id                    UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id             UUID NOT NULL REFERENCES farmers(id) ON DELETE CASCADE,
+    farm_id               UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    activity_type         TEXT NOT NULL
+                          CHECK (activity_type IN ('land_preparation', 'planting', 'fertilizing', 'irrigation',
+                                                   'weeding', 'pest_control', 'harvesting', 'post_harvest', 'other')),
+    description           TEXT NOT NULL,
+    activity_date         DATE NOT NULL,
+    status                TEXT NOT NULL DEFAULT 'completed'
+                          CHECK (status IN ('planned', 'in_progress', 'completed', 'cancelled')),
+    crop_name             TEXT,
+    -- Name of a row in farm_plots for the same farm.
+    field_plot            TEXT,
+    inputs_used           JSONB,
+    quantity_measured     DOUBLE PRECISION CHECK (quantity_measured >= 0),
+    unit_measured         TEXT,
+    expected_harvest_date DATE,
+    notes                 TEXT,
+    created_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX farm_activities_farm_date_idx ON farm_activities (farm_id, activity_date DESC);
+CREATE INDEX farm_activities_farm_crop_idx ON farm_activities (farm_id, lower(crop_name));
+
+CREATE TRIGGER farm_activities_set_updated_at
+    BEFORE UPDATE ON farm_activities
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,3 @@
+ALTER TABLE farm_activities DROP COLUMN IF EXISTS crop_cycle_id;
+DROP TABLE IF EXISTS crop_cycles;
+DROP TABLE IF EXISTS crop_templates;
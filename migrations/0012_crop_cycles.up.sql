@@ -0,0 +1,91 @@
+-- Reference schedules used to plan a crop cycle. Task offsets are days from
+-- the planting date; harvesting tasks are placed on the cycle's expected
+-- harvest date instead.
+CREATE TABLE crop_templates (
+    id           UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    crop_name    TEXT NOT NULL,
+    growing_days INTEGER NOT NULL CHECK (growing_days > 0),
+    tasks        JSONB NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX crop_templates_crop_name_key ON crop_templates (lower(crop_name));
+
+CREATE TRIGGER crop_templates_set_updated_at
+    BEFORE UPDATE ON crop_templates
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+INSERT INTO crop_templates (crop_name, growing_days, tasks) VALUES
+    ('Maize', 120, '[
+        {"activity_type": "land_preparation", "description": "Clear and till the field", "offset_days": -14},
+        {"activity_type": "planting", "description": "Sow maize", "offset_days": 0},
+        {"activity_type": "weeding", "description": "First weeding", "offset_days": 21},
+        {"activity_type": "fertilizing", "description": "Apply NPK", "offset_days": 21},
+        {"activity_type": "weeding", "description": "Second weeding", "offset_days": 42},
+        {"activity_type": "fertilizing", "description": "Top dress with urea", "offset_days": 42},
+        {"activity_type": "harvesting", "description": "Harvest maize", "offset_days": 120}
+    ]'),
+    ('Cassava', 300, '[
+        {"activity_type": "land_preparation", "description": "Clear land and make ridges", "offset_days": -14},
+        {"activity_type": "planting", "description": "Plant cassava stems", "offset_days": 0},
+        {"activity_type": "weeding", "description": "First weeding", "offset_days": 28},
+        {"activity_type": "fertilizing", "description": "Apply NPK", "offset_days": 56},
+        {"activity_type": "weeding", "description": "Second weeding", "offset_days": 84},
+        {"activity_type": "harvesting", "description": "Harvest cassava roots", "offset_days": 300}
+    ]'),
+    ('Rice', 120, '[
+        {"activity_type": "land_preparation", "description": "Plough, puddle and level the field", "offset_days": -21},
+        {"activity_type": "planting", "description": "Transplant seedlings", "offset_days": 0},
+        {"activity_type": "fertilizing", "description": "Basal fertilizer", "offset_days": 14},
+        {"activity_type": "weeding", "description": "First weeding", "offset_days": 21},
+        {"activity_type": "fertilizing", "description": "Top dress with urea", "offset_days": 45},
+        {"activity_type": "weeding", "description": "Second weeding", "offset_days": 45},
+        {"activity_type": "harvesting", "description": "Harvest paddy", "offset_days": 120}
+    ]'),
+    ('Tomato', 90, '[
+        {"activity_type": "land_preparation", "description": "Prepare beds", "offset_days": -7},
+        {"activity_type": "planting", "description": "Transplant seedlings", "offset_days": 0},
+        {"activity_type": "fertilizing", "description": "Apply NPK", "offset_days": 14},
+        {"activity_type": "weeding", "description": "Weed and stake plants", "offset_days": 21},
+        {"activity_type": "pest_control", "description": "Scout and spray for pests", "offset_days": 35},
+        {"activity_type": "harvesting", "description": "Harvest tomatoes", "offset_days": 90}
+    ]'),
+    ('Yam', 240, '[
+        {"activity_type": "land_preparation", "description": "Clear land and make mounds", "offset_days": -21},
+        {"activity_type": "planting", "description": "Plant seed yams", "offset_days": 0},
+        {"activity_type": "other", "description": "Stake vines", "offset_days": 30},
+        {"activity_type": "weeding", "description": "First weeding", "offset_days": 45},
+        {"activity_type": "weeding", "description": "Second weeding", "offset_days": 90},
+        {"activity_type": "harvesting", "description": "Harvest yams", "offset_days": 240}
+    ]');
+
+-- One planting of one crop, usually on one plot. Activities logged for the
+-- season point at it through farm_activities.crop_cycle_id.
+CREATE TABLE crop_cycles (
+    id                    UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id             UUID NOT NULL REFERENCES farmers(id) ON DELETE CASCADE,
+    farm_id               UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    crop_name             TEXT NOT NULL,
+    -- Name of a row in farm_plots for the same farm.
+    field_plot            TEXT,
+    planting_date         DATE NOT NULL,
+    expected_harvest_date DATE,
+    status                TEXT NOT NULL DEFAULT 'active'
+                          CHECK (status IN ('active', 'completed', 'abandoned')),
+    notes                 TEXT,
+    created_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (expected_harvest_date IS NULL OR expected_harvest_date >= planting_date)
+);
+
+CREATE INDEX crop_cycles_farm_planting_idx ON crop_cycles (farm_id, planting_date DESC);
+
+CREATE TRIGGER crop_cycles_set_updated_at
+    BEFORE UPDATE ON crop_cycles
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+ALTER TABLE farm_activities
+    ADD COLUMN crop_cycle_id UUID REFERENCES crop_cycles(id) ON DELETE SET NULL;
+
+CREATE INDEX farm_activities_crop_cycle_idx ON farm_activities (crop_cycle_id, activity_date);
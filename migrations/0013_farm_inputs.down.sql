@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS input_transactions;
+DROP TABLE IF EXISTS farm_inputs;
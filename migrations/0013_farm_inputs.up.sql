@@ -0,0 +1,46 @@
+-- Stock of a purchased input on one farm. stock_value_cents is what the
+-- quantity on hand cost, so usage can be costed at the average price paid.
+CREATE TABLE farm_inputs (
+    id                UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farm_id           UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    name              TEXT NOT NULL,
+    category          TEXT NOT NULL DEFAULT 'other'
+                      CHECK (category IN ('seed', 'fertilizer', 'agrochemical', 'other')),
+    unit              TEXT NOT NULL,
+    currency_code     TEXT NOT NULL DEFAULT 'NGN',
+    quantity_on_hand  DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (quantity_on_hand >= 0),
+    stock_value_cents BIGINT NOT NULL DEFAULT 0 CHECK (stock_value_cents >= 0),
+    -- Stock at or below this level is reported as low.
+    reorder_level     DOUBLE PRECISION CHECK (reorder_level >= 0),
+    notes             TEXT,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX farm_inputs_farm_name_key ON farm_inputs (farm_id, lower(name));
+
+CREATE TRIGGER farm_inputs_set_updated_at
+    BEFORE UPDATE ON farm_inputs
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Every stock movement. Purchases add stock at their cost; usage rows are
+-- written for activities that consume inputs and removed again if the
+-- activity is edited or deleted.
+CREATE TABLE input_transactions (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    input_id    UUID NOT NULL REFERENCES farm_inputs(id) ON DELETE CASCADE,
+    farm_id     UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    activity_id UUID REFERENCES farm_activities(id) ON DELETE CASCADE,
+    kind        TEXT NOT NULL CHECK (kind IN ('purchase', 'usage')),
+    quantity    DOUBLE PRECISION NOT NULL CHECK (quantity > 0),
+    cost_cents  BIGINT NOT NULL CHECK (cost_cents >= 0),
+    occurred_on DATE NOT NULL,
+    supplier    TEXT,
+    notes       TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((kind = 'usage') = (activity_id IS NOT NULL))
+);
+
+CREATE INDEX input_transactions_farm_date_idx ON input_transactions (farm_id, occurred_on DESC);
+CREATE INDEX input_transactions_input_idx ON input_transactions (input_id, occurred_on DESC);
+CREATE INDEX input_transactions_activity_idx ON input_transactions (activity_id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS product_sales;
+DROP TABLE IF EXISTS labour_entries;
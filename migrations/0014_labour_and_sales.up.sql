@@ -0,0 +1,38 @@
+-- Paid work on a farm. crop_name is copied from the crop cycle or activity
+-- when the entry is tied to one, so reports can group by crop.
+CREATE TABLE labour_entries (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farm_id       UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    crop_cycle_id UUID REFERENCES crop_cycles(id) ON DELETE SET NULL,
+    activity_id   UUID REFERENCES farm_activities(id) ON DELETE SET NULL,
+    crop_name     TEXT,
+    work_date     DATE NOT NULL,
+    description   TEXT NOT NULL,
+    workers       INTEGER NOT NULL DEFAULT 1 CHECK (workers > 0),
+    hours         DOUBLE PRECISION CHECK (hours > 0),
+    cost_cents    BIGINT NOT NULL CHECK (cost_cents >= 0),
+    currency_code TEXT NOT NULL DEFAULT 'NGN',
+    notes         TEXT,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX labour_entries_farm_date_idx ON labour_entries (farm_id, work_date DESC);
+
+-- Produce sold from a farm, whether through a listing or off the platform.
+CREATE TABLE product_sales (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farm_id       UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    product_id    UUID REFERENCES products(id) ON DELETE SET NULL,
+    crop_cycle_id UUID REFERENCES crop_cycles(id) ON DELETE SET NULL,
+    crop_name     TEXT,
+    quantity      DOUBLE PRECISION NOT NULL CHECK (quantity > 0),
+    unit          TEXT NOT NULL,
+    amount_cents  BIGINT NOT NULL CHECK (amount_cents >= 0),
+    currency_code TEXT NOT NULL DEFAULT 'NGN',
+    sold_on       DATE NOT NULL,
+    buyer_name    TEXT,
+    notes         TEXT,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX product_sales_farm_date_idx ON product_sales (farm_id, sold_on DESC);
@@ -0,0 +1,2 @@
+-- Canonicalised unit names are left as they are.
+DROP TABLE IF EXISTS units;
@@ -0,0 +1,77 @@
+-- Canonical units of measure. Units in the same dimension convert through
+-- to_base (kilograms, litres or pieces). Packaging units such as crates
+-- hold different amounts of different produce, so they have no factor and
+-- only convert to themselves.
+CREATE TABLE units (
+    code      TEXT PRIMARY KEY CHECK (code = lower(code)),
+    name      TEXT NOT NULL,
+    dimension TEXT NOT NULL CHECK (dimension IN ('mass', 'volume', 'count')),
+    to_base   DOUBLE PRECISION CHECK (to_base > 0),
+    -- Other spellings accepted on input, lower-case.
+    aliases   TEXT[] NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO units (code, name, dimension, to_base, aliases) VALUES
+    ('g',          'Gram',              'mass',   0.001,      '{gram,grams,gm,gms}'),
+    ('kg',         'Kilogram',          'mass',   1,          '{kgs,kilo,kilos,kilogram,kilograms}'),
+    ('tonne',      'Tonne',             'mass',   1000,       '{t,ton,tons,tonnes,mt}'),
+    ('lb',         'Pound',             'mass',   0.45359237, '{lbs,pound,pounds}'),
+    ('bag-25kg',   'Bag (25 kg)',       'mass',   25,         '{}'),
+    ('bag-50kg',   'Bag (50 kg)',       'mass',   50,         '{}'),
+    ('bag-100kg',  'Bag (100 kg)',      'mass',   100,        '{}'),
+    ('ml',         'Millilitre',        'volume', 0.001,      '{millilitre,millilitres,milliliter,milliliters}'),
+    ('litre',      'Litre',             'volume', 1,          '{l,liter,liters,litres,ltr}'),
+    ('jerrycan-25l', 'Jerrycan (25 L)', 'volume', 25,         '{jerrycan,jerrycans}'),
+    ('piece',      'Piece',             'count',  1,          '{pc,pcs,pieces,each,unit,units}'),
+    ('dozen',      'Dozen',             'count',  12,         '{dozens,doz}'),
+    ('tuber',      'Tuber',             'count',  1,          '{tubers}'),
+    ('bunch',      'Bunch',             'count',  NULL,       '{bunches}'),
+    ('crate',      'Crate',             'count',  NULL,       '{crates}'),
+    ('basket',     'Basket',            'count',  NULL,       '{baskets}'),
+    ('bag',        'Bag',               'count',  NULL,       '{bags}');
+
+-- Keep every unit already in use resolvable. Ones the registry doesn't
+-- recognise become packaging units of their own rather than being guessed at.
+CREATE OR REPLACE FUNCTION pg_temp.known_unit(value TEXT) RETURNS BOOLEAN AS $$
+    SELECT EXISTS (SELECT 1 FROM units WHERE code = value OR value = ANY(aliases))
+$$ LANGUAGE SQL STABLE;
+
+INSERT INTO units (code, name, dimension, to_base)
+SELECT code, min(name), 'count', NULL
+FROM (
+    SELECT lower(trim(unit)) AS code, trim(unit) AS name FROM products
+    UNION ALL SELECT lower(trim(unit)), trim(unit) FROM farm_inputs
+    UNION ALL SELECT lower(trim(unit)), trim(unit) FROM product_sales
+    UNION ALL SELECT lower(trim(unit_measured)), trim(unit_measured) FROM farm_activities
+    UNION ALL
+    SELECT lower(trim(e->>'unit')), trim(e->>'unit')
+    FROM farm_activities,
+         jsonb_array_elements(CASE WHEN jsonb_typeof(inputs_used) = 'array' THEN inputs_used ELSE '[]' END) AS e
+) legacy
+WHERE code <> '' AND NOT pg_temp.known_unit(code)
+GROUP BY code;
+
+DROP FUNCTION pg_temp.known_unit(TEXT);
+
+-- Rewrite existing free-text units that the registry recognises.
+CREATE OR REPLACE FUNCTION pg_temp.canonical_unit(value TEXT) RETURNS TEXT AS $$
+    SELECT COALESCE(
+        (SELECT code FROM units
+         WHERE code = lower(trim(value)) OR lower(trim(value)) = ANY(aliases)),
+        value)
+$$ LANGUAGE SQL STABLE;
+
+UPDATE products SET unit = pg_temp.canonical_unit(unit);
+UPDATE farm_inputs SET unit = pg_temp.canonical_unit(unit);
+UPDATE product_sales SET unit = pg_temp.canonical_unit(unit);
+UPDATE farm_activities SET unit_measured = pg_temp.canonical_unit(unit_measured)
+WHERE unit_measured IS NOT NULL;
+UPDATE farm_activities SET inputs_used = (
+    SELECT jsonb_agg(
+        CASE WHEN e ? 'unit' THEN jsonb_set(e, '{unit}', to_jsonb(pg_temp.canonical_unit(e->>'unit'))) ELSE e END
+        ORDER BY ord)
+    FROM jsonb_array_elements(inputs_used) WITH ORDINALITY AS x(e, ord)
+)
+WHERE jsonb_typeof(inputs_used) = 'array' AND inputs_used <> '[]';
+
+DROP FUNCTION pg_temp.canonical_unit(TEXT);
@@ -0,0 +1,53 @@
+-- A buyer's cart. buyer_id is the id of whichever account is buying, so it
+-- has no foreign key.
+CREATE TABLE cart_items (
+    buyer_id   UUID NOT NULL,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    quantity   INTEGER NOT NULL CHECK (quantity > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (buyer_id, product_id)
+);
+
+CREATE TRIGGER cart_items_set_updated_at
+    BEFORE UPDATE ON cart_items
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- One farmer's share of a checkout. Orders placed together share a
+-- checkout_id.
+CREATE TABLE orders (
+    id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    checkout_id    UUID NOT NULL,
+    buyer_id       UUID NOT NULL,
+    farmer_id      UUID NOT NULL REFERENCES farmers(id),
+    status         TEXT NOT NULL DEFAULT 'pending',
+    currency_code  TEXT NOT NULL,
+    subtotal_cents BIGINT NOT NULL CHECK (subtotal_cents >= 0),
+    delivery_notes TEXT,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX orders_buyer_idx ON orders (buyer_id, created_at DESC);
+CREATE INDEX orders_farmer_idx ON orders (farmer_id, created_at DESC);
+CREATE INDEX orders_checkout_idx ON orders (checkout_id);
+
+CREATE TRIGGER orders_set_updated_at
+    BEFORE UPDATE ON orders
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Line items keep the product's name, unit and price at checkout so later
+-- edits to the listing don't change the order. quantity is also the stock
+-- reserved from the product.
+CREATE TABLE order_items (
+    id               UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id         UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    product_id       UUID REFERENCES products(id) ON DELETE SET NULL,
+    product_name     TEXT NOT NULL,
+    unit             TEXT NOT NULL,
+    quantity         INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price_cents BIGINT NOT NULL CHECK (unit_price_cents >= 0),
+    line_total_cents BIGINT NOT NULL CHECK (line_total_cents >= 0)
+);
+
+CREATE INDEX order_items_order_idx ON order_items (order_id);
@@ -0,0 +1,30 @@
+ALTER TABLE orders
+    ADD CONSTRAINT orders_status_check CHECK (status IN (
+        'pending', 'confirmed', 'packed', 'shipped', 'ready_for_pickup',
+        'delivered', 'completed', 'cancelled', 'rejected'
+    )),
+    -- Pending orders the farmer hasn't accepted by then are cancelled and
+    -- their stock released.
+    ADD COLUMN confirm_by TIMESTAMPTZ;
+
+UPDATE orders SET confirm_by = created_at + INTERVAL '48 hours' WHERE status = 'pending';
+
+CREATE INDEX orders_confirm_by_idx ON orders (confirm_by) WHERE status = 'pending';
+
+-- Every status change of an order. actor_id is null for changes made by the
+-- system, such as timeouts.
+CREATE TABLE order_events (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id    UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    from_status TEXT,
+    to_status   TEXT NOT NULL,
+    actor_id    UUID,
+    actor_role  TEXT NOT NULL CHECK (actor_role IN ('buyer', 'farmer', 'system')),
+    note        TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX order_events_order_idx ON order_events (order_id, created_at);
+
+INSERT INTO order_events (order_id, from_status, to_status, actor_id, actor_role, created_at)
+SELECT id, NULL, 'pending', buyer_id, 'buyer', created_at FROM orders;
@@ -0,0 +1,11 @@
+DELETE FROM refresh_tokens WHERE buyer_id IS NOT NULL;
+ALTER TABLE refresh_tokens
+    DROP CONSTRAINT IF EXISTS refresh_tokens_account_check,
+    DROP COLUMN IF EXISTS buyer_id,
+    ALTER COLUMN farmer_id SET NOT NULL;
+
+DELETE FROM sessions WHERE buyer_id IS NOT NULL;
+ALTER TABLE sessions DROP COLUMN IF EXISTS buyer_id;
+
+DROP TABLE IF EXISTS buyer_addresses;
+DROP TABLE IF EXISTS buyers;
@@ -0,0 +1,54 @@
+-- Buyer accounts: individuals, restaurants and aggregators. They log in with
+-- the same phone OTP as farmers but are separate accounts, so one phone can
+-- hold both.
+CREATE TABLE buyers (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    phone_number        TEXT NOT NULL UNIQUE,
+    email               TEXT,
+    first_name          TEXT NOT NULL,
+    last_name           TEXT NOT NULL,
+    buyer_type          TEXT NOT NULL DEFAULT 'individual'
+                        CHECK (buyer_type IN ('individual', 'restaurant', 'aggregator')),
+    business_name       TEXT,
+    verification_status TEXT NOT NULL DEFAULT 'pending',
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER buyers_set_updated_at
+    BEFORE UPDATE ON buyers
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE buyer_addresses (
+    id                    UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    buyer_id              UUID NOT NULL REFERENCES buyers(id) ON DELETE CASCADE,
+    label                 TEXT,
+    recipient_name        TEXT,
+    phone_number          TEXT,
+    address_text          TEXT NOT NULL,
+    city                  TEXT,
+    state                 TEXT,
+    latitude              DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90),
+    longitude             DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180),
+    delivery_instructions TEXT,
+    is_default            BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX buyer_addresses_buyer_idx ON buyer_addresses (buyer_id);
+CREATE UNIQUE INDEX buyer_addresses_default_idx ON buyer_addresses (buyer_id) WHERE is_default;
+
+CREATE TRIGGER buyer_addresses_set_updated_at
+    BEFORE UPDATE ON buyer_addresses
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Sessions and refresh tokens belong to either a farmer or a buyer.
+ALTER TABLE sessions ADD COLUMN buyer_id UUID REFERENCES buyers(id) ON DELETE CASCADE;
+CREATE INDEX sessions_buyer_id_idx ON sessions (buyer_id) WHERE buyer_id IS NOT NULL;
+
+ALTER TABLE refresh_tokens
+    ALTER COLUMN farmer_id DROP NOT NULL,
+    ADD COLUMN buyer_id UUID REFERENCES buyers(id) ON DELETE CASCADE,
+    ADD CONSTRAINT refresh_tokens_account_check CHECK ((farmer_id IS NULL) <> (buyer_id IS NULL));
+CREATE INDEX refresh_tokens_buyer_id_idx ON refresh_tokens (buyer_id);
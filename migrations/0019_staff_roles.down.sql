@@ -0,0 +1,18 @@
+DELETE FROM refresh_tokens WHERE staff_id IS NOT NULL;
+ALTER TABLE refresh_tokens
+    DROP CONSTRAINT IF EXISTS refresh_tokens_account_check,
+    DROP COLUMN IF EXISTS staff_id,
+    ADD CONSTRAINT refresh_tokens_account_check CHECK ((farmer_id IS NULL) <> (buyer_id IS NULL));
+
+DELETE FROM sessions WHERE staff_id IS NOT NULL;
+ALTER TABLE sessions DROP COLUMN IF EXISTS staff_id;
+
+DROP TABLE IF EXISTS product_moderation_events;
+
+UPDATE products SET status = 'draft' WHERE status = 'suspended';
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_status_check;
+ALTER TABLE products ADD CONSTRAINT products_status_check
+    CHECK (status IN ('draft', 'published', 'archived'));
+
+DROP TABLE IF EXISTS staff_farm_assignments;
+DROP TABLE IF EXISTS staff;
@@ -0,0 +1,58 @@
+-- Staff accounts: extension officers, cooperative managers and admins. They
+-- are created by an admin and log in with the same phone OTP as farmers and
+-- buyers.
+CREATE TABLE staff (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    phone_number        TEXT NOT NULL UNIQUE,
+    first_name          TEXT NOT NULL,
+    last_name           TEXT NOT NULL,
+    role                TEXT NOT NULL
+                        CHECK (role IN ('extension_officer', 'coop_manager', 'admin')),
+    is_active           BOOLEAN NOT NULL DEFAULT TRUE,
+    verification_status TEXT NOT NULL DEFAULT 'pending',
+    created_by          UUID REFERENCES staff(id) ON DELETE SET NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER staff_set_updated_at
+    BEFORE UPDATE ON staff
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Farms an extension officer or cooperative manager may read.
+CREATE TABLE staff_farm_assignments (
+    staff_id    UUID NOT NULL REFERENCES staff(id) ON DELETE CASCADE,
+    farm_id     UUID NOT NULL REFERENCES farms(id) ON DELETE CASCADE,
+    assigned_by UUID REFERENCES staff(id) ON DELETE SET NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (staff_id, farm_id)
+);
+
+CREATE INDEX staff_farm_assignments_farm_idx ON staff_farm_assignments (farm_id);
+
+-- Admins can take a product off the marketplace. Suspended products can only
+-- be reinstated (back to draft) by an admin.
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_status_check;
+ALTER TABLE products ADD CONSTRAINT products_status_check
+    CHECK (status IN ('draft', 'published', 'archived', 'suspended'));
+
+CREATE TABLE product_moderation_events (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id  UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    staff_id    UUID REFERENCES staff(id) ON DELETE SET NULL,
+    action      TEXT NOT NULL CHECK (action IN ('suspend', 'reinstate')),
+    reason      TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX product_moderation_events_product_idx ON product_moderation_events (product_id, created_at);
+
+-- Sessions and refresh tokens can also belong to a staff member.
+ALTER TABLE sessions ADD COLUMN staff_id UUID REFERENCES staff(id) ON DELETE CASCADE;
+CREATE INDEX sessions_staff_id_idx ON sessions (staff_id) WHERE staff_id IS NOT NULL;
+
+ALTER TABLE refresh_tokens
+    DROP CONSTRAINT refresh_tokens_account_check,
+    ADD COLUMN staff_id UUID REFERENCES staff(id) ON DELETE CASCADE,
+    ADD CONSTRAINT refresh_tokens_account_check CHECK (num_nonnulls(farmer_id, buyer_id, staff_id) = 1);
+CREATE INDEX refresh_tokens_staff_id_idx ON refresh_tokens (staff_id);
@@ -0,0 +1,9 @@
+ALTER TABLE orders
+    DROP COLUMN IF EXISTS paid_at,
+    DROP COLUMN IF EXISTS payment_id,
+    DROP COLUMN IF EXISTS payment_status;
+
+DROP TABLE IF EXISTS payment_webhook_events;
+DROP TABLE IF EXISTS payment_refunds;
+DROP TABLE IF EXISTS payment_orders;
+DROP TABLE IF EXISTS payments;
@@ -0,0 +1,79 @@
+-- Card and bank payments through a gateway. A payment covers the unpaid
+-- orders of one checkout in one currency.
+CREATE TABLE payments (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    checkout_id             UUID NOT NULL,
+    buyer_id                UUID NOT NULL,
+    provider                TEXT NOT NULL,
+    reference               TEXT NOT NULL UNIQUE,
+    amount_cents            BIGINT NOT NULL CHECK (amount_cents > 0),
+    currency_code           TEXT NOT NULL,
+    status                  TEXT NOT NULL DEFAULT 'pending'
+                            CHECK (status IN ('pending', 'succeeded', 'failed', 'partially_refunded', 'refunded')),
+    authorization_url       TEXT,
+    provider_transaction_id TEXT,
+    failure_reason          TEXT,
+    refunded_cents          BIGINT NOT NULL DEFAULT 0 CHECK (refunded_cents >= 0),
+    paid_at                 TIMESTAMPTZ,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX payments_buyer_idx ON payments (buyer_id, created_at DESC);
+CREATE INDEX payments_checkout_idx ON payments (checkout_id);
+
+CREATE TRIGGER payments_set_updated_at
+    BEFORE UPDATE ON payments
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- The orders a payment was started for. An order can appear under several
+-- payments if the buyer retried; only one of them pays it.
+CREATE TABLE payment_orders (
+    payment_id   UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    order_id     UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    amount_cents BIGINT NOT NULL,
+    PRIMARY KEY (payment_id, order_id)
+);
+
+CREATE INDEX payment_orders_order_idx ON payment_orders (order_id);
+
+CREATE TABLE payment_refunds (
+    id                 UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id         UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    order_id           UUID REFERENCES orders(id) ON DELETE SET NULL,
+    amount_cents       BIGINT NOT NULL CHECK (amount_cents > 0),
+    reason             TEXT,
+    status             TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processed', 'failed')),
+    provider_refund_id TEXT,
+    failure_reason     TEXT,
+    created_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at         TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX payment_refunds_payment_idx ON payment_refunds (payment_id);
+-- An order is refunded from a given payment at most once.
+CREATE UNIQUE INDEX payment_refunds_order_idx ON payment_refunds (payment_id, order_id)
+    WHERE order_id IS NOT NULL AND status <> 'failed';
+
+CREATE TRIGGER payment_refunds_set_updated_at
+    BEFORE UPDATE ON payment_refunds
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Every webhook delivery accepted, keyed by a hash of its body so redelivered
+-- events are only processed once.
+CREATE TABLE payment_webhook_events (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider    TEXT NOT NULL,
+    event_key   TEXT NOT NULL,
+    event_type  TEXT NOT NULL,
+    reference   TEXT,
+    payload     JSONB NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, event_key)
+);
+
+ALTER TABLE orders
+    ADD COLUMN payment_status TEXT NOT NULL DEFAULT 'unpaid'
+        CHECK (payment_status IN ('unpaid', 'paid', 'refunded')),
+    ADD COLUMN payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    ADD COLUMN paid_at TIMESTAMPTZ;
@@ -0,0 +1,132 @@
+-- Where farmers are paid out to.
+CREATE TABLE farmer_bank_accounts (
+    id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id      UUID NOT NULL REFERENCES farmers(id) ON DELETE CASCADE,
+    bank_name      TEXT NOT NULL,
+    bank_code      TEXT,
+    account_number TEXT NOT NULL,
+    account_name   TEXT NOT NULL,
+    currency_code  TEXT NOT NULL,
+    -- Removed accounts are kept for the payouts that went to them.
+    is_active      BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX farmer_bank_accounts_farmer_idx ON farmer_bank_accounts (farmer_id);
+
+CREATE TRIGGER farmer_bank_accounts_set_updated_at
+    BEFORE UPDATE ON farmer_bank_accounts
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- A farmer's request to withdraw from their wallet, paid by hand by an admin.
+CREATE TABLE payouts (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    farmer_id       UUID NOT NULL REFERENCES farmers(id),
+    bank_account_id UUID NOT NULL REFERENCES farmer_bank_accounts(id),
+    amount_cents    BIGINT NOT NULL CHECK (amount_cents > 0),
+    currency_code   TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'requested' CHECK (status IN ('requested', 'paid', 'failed')),
+    -- The bank transfer's reference once paid.
+    transfer_reference TEXT,
+    failure_reason  TEXT,
+    processed_by    UUID REFERENCES staff(id),
+    processed_at    TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX payouts_farmer_idx ON payouts (farmer_id, created_at DESC);
+CREATE INDEX payouts_status_idx ON payouts (status, created_at);
+
+CREATE TRIGGER payouts_set_updated_at
+    BEFORE UPDATE ON payouts
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Double-entry ledger of the money the platform holds. The gateway account
+-- is what has been collected; escrow, farmer payable and payouts in transit
+-- are owed to farmers; platform fees are ours. Entries are signed, debits
+-- positive and credits negative, and each transaction's entries sum to zero.
+CREATE TABLE ledger_accounts (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind          TEXT NOT NULL
+                  CHECK (kind IN ('gateway', 'escrow', 'farmer_payable', 'payouts_in_transit', 'platform_fees')),
+    farmer_id     UUID REFERENCES farmers(id),
+    currency_code TEXT NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((farmer_id IS NULL) = (kind IN ('gateway', 'platform_fees')))
+);
+
+-- One account per kind, owner and currency. The platform's accounts have no
+-- owner, so it is coalesced for the comparison (NULLS NOT DISTINCT would
+-- need PostgreSQL 15).
+CREATE UNIQUE INDEX ledger_accounts_key_idx ON ledger_accounts (
+    kind, COALESCE(farmer_id, '00000000-0000-0000-0000-000000000000'), currency_code
+);
+
+CREATE TABLE ledger_transactions (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind          TEXT NOT NULL CHECK (kind IN (
+                      'payment', 'refund', 'refund_failed', 'release', 'payout', 'payout_paid', 'payout_failed'
+                  )),
+    currency_code TEXT NOT NULL,
+    order_id      UUID REFERENCES orders(id),
+    payment_id    UUID REFERENCES payments(id),
+    refund_id     UUID REFERENCES payment_refunds(id),
+    payout_id     UUID REFERENCES payouts(id),
+    memo          TEXT,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ledger_transactions_order_idx ON ledger_transactions (order_id);
+-- An order's escrow is released once.
+CREATE UNIQUE INDEX ledger_transactions_release_idx ON ledger_transactions (order_id) WHERE kind = 'release';
+
+CREATE TABLE ledger_entries (
+    id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL REFERENCES ledger_transactions(id),
+    account_id     UUID NOT NULL REFERENCES ledger_accounts(id),
+    amount_cents   BIGINT NOT NULL CHECK (amount_cents <> 0),
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ledger_entries_account_idx ON ledger_entries (account_id, created_at);
+CREATE INDEX ledger_entries_transaction_idx ON ledger_entries (transaction_id);
+
+-- Checked at commit, once all of a transaction's entries are in.
+CREATE FUNCTION ledger_check_transaction() RETURNS trigger AS $$
+BEGIN
+    IF (SELECT SUM(amount_cents) FROM ledger_entries WHERE transaction_id = NEW.transaction_id) <> 0 THEN
+        RAISE EXCEPTION 'Ledger transaction % does not balance', NEW.transaction_id;
+    END IF;
+    IF EXISTS (
+        SELECT 1 FROM ledger_entries e
+        JOIN ledger_accounts a ON a.id = e.account_id
+        JOIN ledger_transactions t ON t.id = e.transaction_id
+        WHERE e.transaction_id = NEW.transaction_id AND a.currency_code <> t.currency_code
+    ) THEN
+        RAISE EXCEPTION 'Ledger transaction % mixes currencies', NEW.transaction_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER ledger_entries_balanced
+    AFTER INSERT ON ledger_entries
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION ledger_check_transaction();
+
+-- Mistakes are corrected with new transactions, never by editing history.
+CREATE FUNCTION ledger_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION '% is append-only', TG_TABLE_NAME;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER ledger_entries_append_only
+    BEFORE UPDATE OR DELETE ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION ledger_append_only();
+
+CREATE TRIGGER ledger_transactions_append_only
+    BEFORE UPDATE OR DELETE ON ledger_transactions
+    FOR EACH ROW EXECUTE FUNCTION ledger_append_only();
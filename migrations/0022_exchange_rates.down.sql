@@ -0,0 +1,2 @@
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_currency_code_format;
+DROP TABLE IF EXISTS exchange_rates;
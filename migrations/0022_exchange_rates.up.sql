@@ -0,0 +1,36 @@
+-- Admin-maintained exchange rates, used to show prices in a viewer's
+-- currency. Orders always settle in the product's own currency.
+CREATE TABLE exchange_rates (
+    id             UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    base_currency  TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    -- Major units of the quote currency bought by one of the base currency.
+    rate           NUMERIC(24, 12) NOT NULL CHECK (rate > 0),
+    -- The rate applies from this day until a later one for the pair.
+    effective_date DATE NOT NULL,
+    created_by     UUID REFERENCES staff(id) ON DELETE SET NULL,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (base_currency <> quote_currency),
+    UNIQUE (base_currency, quote_currency, effective_date)
+);
+
+-- Product currencies used to be stored as sent. Anything that isn't a
+-- supported ISO-4217 code once tidied falls back to NGN, the old default.
+UPDATE products SET currency_code = UPPER(TRIM(currency_code));
+UPDATE products SET currency_code = 'NGN' WHERE currency_code NOT IN (
+    'AED', 'AFN', 'ALL', 'AMD', 'ANG', 'AOA', 'ARS', 'AUD', 'AWG', 'AZN', 'BAM', 'BBD', 'BDT',
+    'BGN', 'BHD', 'BIF', 'BMD', 'BND', 'BOB', 'BRL', 'BSD', 'BTN', 'BWP', 'BYN', 'BZD', 'CAD',
+    'CDF', 'CHF', 'CLP', 'CNY', 'COP', 'CRC', 'CUP', 'CVE', 'CZK', 'DJF', 'DKK', 'DOP', 'DZD',
+    'EGP', 'ERN', 'ETB', 'EUR', 'FJD', 'FKP', 'GBP', 'GEL', 'GHS', 'GIP', 'GMD', 'GNF', 'GTQ',
+    'GYD', 'HKD', 'HNL', 'HTG', 'HUF', 'IDR', 'ILS', 'INR', 'IQD', 'IRR', 'ISK', 'JMD', 'JOD',
+    'JPY', 'KES', 'KGS', 'KHR', 'KMF', 'KPW', 'KRW', 'KWD', 'KYD', 'KZT', 'LAK', 'LBP', 'LKR',
+    'LRD', 'LSL', 'LYD', 'MAD', 'MDL', 'MGA', 'MKD', 'MMK', 'MNT', 'MOP', 'MRU', 'MUR', 'MVR',
+    'MWK', 'MXN', 'MYR', 'MZN', 'NAD', 'NGN', 'NIO', 'NOK', 'NPR', 'NZD', 'OMR', 'PAB', 'PEN',
+    'PGK', 'PHP', 'PKR', 'PLN', 'PYG', 'QAR', 'RON', 'RSD', 'RUB', 'RWF', 'SAR', 'SBD', 'SCR',
+    'SDG', 'SEK', 'SGD', 'SHP', 'SLE', 'SOS', 'SRD', 'SSP', 'STN', 'SVC', 'SYP', 'SZL', 'THB',
+    'TJS', 'TMT', 'TND', 'TOP', 'TRY', 'TTD', 'TWD', 'TZS', 'UAH', 'UGX', 'USD', 'UYU', 'UZS',
+    'VES', 'VND', 'VUV', 'WST', 'XAF', 'XCD', 'XOF', 'XPF', 'YER', 'ZAR', 'ZMW', 'ZWG'
+);
+
+ALTER TABLE products
+    ADD CONSTRAINT products_currency_code_format CHECK (currency_code ~ '^[A-Z]{3}$');
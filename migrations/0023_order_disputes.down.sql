@@ -0,0 +1,8 @@
+UPDATE orders SET status = 'delivered' WHERE status = 'disputed';
+
+ALTER TABLE orders
+    DROP CONSTRAINT IF EXISTS orders_status_check,
+    ADD CONSTRAINT orders_status_check CHECK (status IN (
+        'pending', 'confirmed', 'packed', 'shipped', 'ready_for_pickup',
+        'delivered', 'completed', 'cancelled', 'rejected'
+    ));
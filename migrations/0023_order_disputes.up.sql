@@ -0,0 +1,8 @@
+-- Buyers can dispute a delivered order they didn't receive, which holds its
+-- escrow until they confirm receipt.
+ALTER TABLE orders
+    DROP CONSTRAINT orders_status_check,
+    ADD CONSTRAINT orders_status_check CHECK (status IN (
+        'pending', 'confirmed', 'packed', 'shipped', 'ready_for_pickup',
+        'delivered', 'disputed', 'completed', 'cancelled', 'rejected'
+    ));
@@ -1,25 +1,246 @@
 use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool, Row};
 use std::env;
 
+/// A single schema change. `up` and `down` are plain SQL scripts embedded at
+/// compile time so the binary always carries the schema the server expects.
+struct Migration {
+    version: i64,
+    name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+macro_rules! migration {
+    ($version:expr, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("../../migrations/", $name, ".up.sql")),
+            down: include_str!(concat!("../../migrations/", $name, ".down.sql")),
+        }
+    };
+}
+
+/// Ordered list of migrations. Append new entries at the end; never reorder or
+/// edit a migration that has already shipped.
+const MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_initial_schema"),
+    migration!(2, "0002_sessions"),
+    migration!(3, "0003_refresh_tokens"),
+    migration!(4, "0004_otp_hardening"),
+    migration!(5, "0005_otp_purpose"),
+    migration!(6, "0006_normalize_phone_numbers"),
+    migration!(7, "0007_product_search"),
+    migration!(8, "0008_farm_geography_index"),
+    migration!(9, "0009_drop_farm_view"),
+    migration!(10, "0010_farm_boundaries_and_plots"),
+    migration!(11, "0011_farm_activities"),
+    migration!(12, "0012_crop_cycles"),
+    migration!(13, "0013_farm_inputs"),
+    migration!(14, "0014_labour_and_sales"),
+    migration!(15, "0015_units"),
+    migration!(16, "0016_carts_and_orders"),
+    migration!(17, "0017_order_lifecycle"),
+    migration!(18, "0018_buyers"),
+    migration!(19, "0019_staff_roles"),
+    migration!(20, "0020_payments"),
+    migration!(21, "0021_ledger"),
+    migration!(22, "0022_exchange_rates"),
+    migration!(23, "0023_order_disputes"),
+];
+
+// Arbitrary key so two `migrate` processes never run against the same database at once.
+const MIGRATION_LOCK_KEY: i64 = 0x6167_726f_6d69_6772;
+
+const USAGE: &str = "Usage: migrate <command>
+
+Commands:
+  up [N]      Apply pending migrations (all, or the next N)
+  down [N]    Revert the last N applied migrations (default 1)
+  status      List migrations and whether they are applied
+  redo        Revert and re-apply the last applied migration";
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv::dotenv().ok();
-    
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = args.first().map(String::as_str).unwrap_or("up");
+    let steps = match args.get(1) {
+        Some(n) => Some(n.parse::<usize>().map_err(|_| format!("invalid step count: {}", n))?),
+        None => None,
+    };
+
+    if !matches!(command, "up" | "down" | "status" | "redo") {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    }
+
     let database_url = env::var("DATABASE_URL")
         .expect("DATABASE_URL must be set");
-    
+
     let pool = PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(1)
         .connect(&database_url)
         .await?;
-    
-    println!("Running database migrations...");
-    
-    // You can add manual migrations here or use sqlx migrate
-    // For now, let's just test the connection
-    sqlx::query("SELECT 1").fetch_one(&pool).await?;
-    
-    println!("Database connection successful!");
-    
+
+    ensure_tracking_table(&pool).await?;
+
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&pool)
+        .await?;
+
+    let result = match command {
+        "up" => migrate_up(&pool, steps).await,
+        "down" => migrate_down(&pool, steps.unwrap_or(1)).await,
+        "status" => status(&pool).await,
+        "redo" => redo(&pool).await,
+        _ => unreachable!(),
+    };
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&pool)
+        .await?;
+
+    result
+}
+
+async fn ensure_tracking_table(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version    BIGINT PRIMARY KEY,
+            name       TEXT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn applied_versions(pool: &PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    let rows = sqlx::query("SELECT version FROM schema_migrations ORDER BY version")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(|row| row.get("version")).collect())
+}
+
+async fn apply(pool: &PgPool, migration: &Migration) -> Result<(), sqlx::Error> {
+    println!("Applying {} ...", migration.name);
+
+    let mut tx = pool.begin().await?;
+    (&mut *tx).execute(migration.up).await?;
+    sqlx::query("INSERT INTO schema_migrations (version, name) VALUES ($1, $2)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn revert(pool: &PgPool, migration: &Migration) -> Result<(), sqlx::Error> {
+    println!("Reverting {} ...", migration.name);
+
+    let mut tx = pool.begin().await?;
+    (&mut *tx).execute(migration.down).await?;
+    sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+        .bind(migration.version)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+fn find(version: i64) -> Result<&'static Migration, String> {
+    MIGRATIONS
+        .iter()
+        .find(|m| m.version == version)
+        .ok_or_else(|| format!("database has version {} applied but this binary does not know it", version))
+}
+
+async fn migrate_up(pool: &PgPool, steps: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let applied = applied_versions(pool).await?;
+    let pending: Vec<&Migration> = MIGRATIONS
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .take(steps.unwrap_or(usize::MAX))
+        .collect();
+
+    if pending.is_empty() {
+        println!("Database is up to date.");
+        return Ok(());
+    }
+
+    for migration in pending {
+        apply(pool, migration).await?;
+    }
+
+    println!("Migrations applied successfully!");
+    Ok(())
+}
+
+async fn migrate_down(pool: &PgPool, steps: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let applied = applied_versions(pool).await?;
+
+    if applied.is_empty() {
+        println!("No migrations to revert.");
+        return Ok(());
+    }
+
+    for version in applied.iter().rev().take(steps) {
+        revert(pool, find(*version)?).await?;
+    }
+
+    Ok(())
+}
+
+async fn redo(pool: &PgPool) -> Result<(), Box<dyn std::error::Error>> {
+    let applied = applied_versions(pool).await?;
+
+    let Some(last) = applied.last() else {
+        println!("No migrations to redo.");
+        return Ok(());
+    };
+
+    let migration = find(*last)?;
+    revert(pool, migration).await?;
+    apply(pool, migration).await?;
+
+    Ok(())
+}
+
+async fn status(pool: &PgPool) -> Result<(), Box<dyn std::error::Error>> {
+    let rows = sqlx::query("SELECT version, applied_at::text AS applied_at FROM schema_migrations ORDER BY version")
+        .fetch_all(pool)
+        .await?;
+
+    let applied: Vec<(i64, String)> = rows
+        .iter()
+        .map(|row| (row.get("version"), row.get("applied_at")))
+        .collect();
+
+    for migration in MIGRATIONS {
+        match applied.iter().find(|(version, _)| *version == migration.version) {
+            Some((_, applied_at)) => println!("[x] {}  (applied {})", migration.name, applied_at),
+            None => println!("[ ] {}", migration.name),
+        }
+    }
+
+    for (version, _) in &applied {
+        if find(*version).is_err() {
+            println!("[?] version {} is applied but unknown to this binary", version);
+        }
+    }
+
     Ok(())
 }
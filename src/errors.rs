@@ -1,4 +1,4 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{web, HttpResponse, ResponseError};
 use serde_json::json;
 use std::fmt;
 
@@ -8,7 +8,20 @@ pub enum AppError {
     ValidationError(String),
     NotFound(String),
     Unauthorized(String),
+    /// Logged in, but the account's role doesn't allow the request.
+    Forbidden(String),
     InternalError(String),
+    RateLimited { message: String, retry_after_secs: i64 },
+    Otp(OtpError),
+}
+
+/// Why an OTP was rejected. Each maps to its own error code so clients can
+/// tell "ask for a new code" apart from "try again".
+#[derive(Debug)]
+pub enum OtpError {
+    Expired,
+    TooManyAttempts,
+    Invalid { attempts_remaining: i32 },
 }
 
 impl fmt::Display for AppError {
@@ -18,7 +31,10 @@ impl fmt::Display for AppError {
             AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
+            AppError::RateLimited { message, .. } => write!(f, "Rate limited: {}", message),
+            AppError::Otp(err) => write!(f, "OTP error: {:?}", err),
         }
     }
 }
@@ -50,12 +66,46 @@ impl ResponseError for AppError {
                     "code": "UNAUTHORIZED"
                 }))
             }
+            AppError::Forbidden(msg) => {
+                HttpResponse::Forbidden().json(json!({
+                    "error": msg,
+                    "code": "FORBIDDEN"
+                }))
+            }
             AppError::InternalError(msg) => {
                 HttpResponse::InternalServerError().json(json!({
                     "error": msg,
                     "code": "INTERNAL_ERROR"
                 }))
             }
+            AppError::RateLimited { message, retry_after_secs } => {
+                HttpResponse::TooManyRequests()
+                    .insert_header(("Retry-After", retry_after_secs.to_string()))
+                    .json(json!({
+                        "error": message,
+                        "code": "RATE_LIMITED",
+                        "retry_after_secs": retry_after_secs
+                    }))
+            }
+            AppError::Otp(OtpError::Expired) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "OTP has expired, request a new code",
+                    "code": "OTP_EXPIRED"
+                }))
+            }
+            AppError::Otp(OtpError::TooManyAttempts) => {
+                HttpResponse::TooManyRequests().json(json!({
+                    "error": "Too many incorrect attempts, request a new code",
+                    "code": "OTP_TOO_MANY_ATTEMPTS"
+                }))
+            }
+            AppError::Otp(OtpError::Invalid { attempts_remaining }) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "Invalid OTP code",
+                    "code": "OTP_INVALID",
+                    "attempts_remaining": attempts_remaining
+                }))
+            }
         }
     }
 }
@@ -73,3 +123,20 @@ impl From<anyhow::Error> for AppError {
 }
 
 pub type AppResult<T> = Result<T, AppError>;
+
+/// Extractor configs so malformed bodies, queries and paths come back in the
+/// same JSON envelope as every other error.
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default()
+        .error_handler(|err, _req| AppError::ValidationError(err.to_string()).into())
+}
+
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default()
+        .error_handler(|err, _req| AppError::ValidationError(err.to_string()).into())
+}
+
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default()
+        .error_handler(|err, _req| AppError::ValidationError(err.to_string()).into())
+}
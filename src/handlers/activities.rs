@@ -0,0 +1,70 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{AuthenticatedFarmer, FarmReader},
+    models::{ActivityQuery, CreateFarmActivityRequest, UpdateFarmActivityRequest},
+    services,
+};
+
+pub async fn list_activities(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<ActivityQuery>,
+) -> ActixResult<HttpResponse> {
+    let activities =
+        services::activity_service::list_activities(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(activities))
+}
+
+pub async fn create_activity(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateFarmActivityRequest>,
+) -> ActixResult<HttpResponse> {
+    let activity =
+        services::activity_service::create_activity(&db, farmer.farmer_id, path.into_inner(), payload.into_inner())
+            .await?;
+    Ok(HttpResponse::Created().json(activity))
+}
+
+pub async fn get_activity(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, activity_id) = path.into_inner();
+    let activity = services::activity_service::get_activity(&db, reader.farmer_id, farm_id, activity_id).await?;
+    Ok(HttpResponse::Ok().json(activity))
+}
+
+pub async fn update_activity(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+    payload: web::Json<UpdateFarmActivityRequest>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, activity_id) = path.into_inner();
+    let activity = services::activity_service::update_activity(
+        &db,
+        farmer.farmer_id,
+        farm_id,
+        activity_id,
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(activity))
+}
+
+pub async fn delete_activity(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, activity_id) = path.into_inner();
+    services::activity_service::delete_activity(&db, farmer.farmer_id, farm_id, activity_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
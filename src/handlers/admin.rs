@@ -0,0 +1,190 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::AuthenticatedStaff,
+    models::{
+        AssignFarmRequest, CreateExchangeRateRequest, CreateStaffRequest, ExchangeRateQuery, ModerationRequest,
+        PayoutFailedRequest, PayoutPaidRequest, PayoutQuery, RefundQuery, StaffQuery, UpdateStaffRequest,
+    },
+    services::{self, payment_provider::PaymentProvider, sms_service::SmsProvider},
+};
+
+/// Creates a staff account. The new staff member logs in with
+/// `POST /api/auth/request-otp` using their role.
+pub async fn create_staff(
+    db: web::Data<Database>,
+    admin: AuthenticatedStaff,
+    payload: web::Json<CreateStaffRequest>,
+) -> ActixResult<HttpResponse> {
+    let staff = services::staff_service::create_staff(&db, admin.staff_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(staff))
+}
+
+pub async fn list_staff(db: web::Data<Database>, query: web::Query<StaffQuery>) -> ActixResult<HttpResponse> {
+    let staff = services::staff_service::list_staff(&db, &query).await?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+pub async fn get_staff(db: web::Data<Database>, path: web::Path<Uuid>) -> ActixResult<HttpResponse> {
+    let staff = services::staff_service::get_staff(&db, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+pub async fn update_staff(
+    db: web::Data<Database>,
+    admin: AuthenticatedStaff,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateStaffRequest>,
+) -> ActixResult<HttpResponse> {
+    let staff =
+        services::staff_service::update_staff(&db, admin.staff_id, path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+pub async fn list_staff_farms(db: web::Data<Database>, path: web::Path<Uuid>) -> ActixResult<HttpResponse> {
+    let staff_id = path.into_inner();
+    services::staff_service::get_staff(&db, staff_id).await?;
+    let farms = services::staff_service::assigned_farms(&db, staff_id).await?;
+    Ok(HttpResponse::Ok().json(farms))
+}
+
+pub async fn assign_farm(
+    db: web::Data<Database>,
+    admin: AuthenticatedStaff,
+    path: web::Path<Uuid>,
+    payload: web::Json<AssignFarmRequest>,
+) -> ActixResult<HttpResponse> {
+    let farms =
+        services::staff_service::assign_farm(&db, admin.staff_id, path.into_inner(), payload.farm_id).await?;
+    Ok(HttpResponse::Ok().json(farms))
+}
+
+pub async fn unassign_farm(db: web::Data<Database>, path: web::Path<(Uuid, Uuid)>) -> ActixResult<HttpResponse> {
+    let (staff_id, farm_id) = path.into_inner();
+    services::staff_service::unassign_farm(&db, staff_id, farm_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn suspend_product(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    admin: AuthenticatedStaff,
+    path: web::Path<Uuid>,
+    payload: web::Json<ModerationRequest>,
+) -> ActixResult<HttpResponse> {
+    let product = services::product_service::suspend_product(
+        &db,
+        sms.get_ref(),
+        admin.staff_id,
+        path.into_inner(),
+        payload.into_inner().reason,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(product))
+}
+
+pub async fn reinstate_product(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    admin: AuthenticatedStaff,
+    path: web::Path<Uuid>,
+    payload: Option<web::Json<ModerationRequest>>,
+) -> ActixResult<HttpResponse> {
+    let reason = payload.and_then(|p| p.into_inner().reason);
+    let product =
+        services::product_service::reinstate_product(&db, sms.get_ref(), admin.staff_id, path.into_inner(), reason)
+            .await?;
+    Ok(HttpResponse::Ok().json(product))
+}
+
+pub async fn list_moderation_events(db: web::Data<Database>, path: web::Path<Uuid>) -> ActixResult<HttpResponse> {
+    let events = services::product_service::list_moderation_events(&db, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(events))
+}
+
+pub async fn list_refunds(db: web::Data<Database>, query: web::Query<RefundQuery>) -> ActixResult<HttpResponse> {
+    let refunds = services::payment_service::list_refunds(&db, &query).await?;
+    Ok(HttpResponse::Ok().json(refunds))
+}
+
+/// Retries a refund the gateway turned down.
+pub async fn retry_refund(
+    db: web::Data<Database>,
+    payments: web::Data<dyn PaymentProvider>,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let refund = services::payment_service::retry_refund(&db, payments.get_ref(), path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(refund))
+}
+
+pub async fn list_payouts(db: web::Data<Database>, query: web::Query<PayoutQuery>) -> ActixResult<HttpResponse> {
+    let payouts = services::ledger_service::list_payouts(&db, None, &query).await?;
+    Ok(HttpResponse::Ok().json(payouts))
+}
+
+/// Records a payout as sent once the bank transfer has been made.
+pub async fn mark_payout_paid(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    admin: AuthenticatedStaff,
+    path: web::Path<Uuid>,
+    payload: web::Json<PayoutPaidRequest>,
+) -> ActixResult<HttpResponse> {
+    let payout = services::ledger_service::mark_payout_paid(
+        &db,
+        sms.get_ref(),
+        admin.staff_id,
+        path.into_inner(),
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(payout))
+}
+
+pub async fn mark_payout_failed(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    admin: AuthenticatedStaff,
+    path: web::Path<Uuid>,
+    payload: web::Json<PayoutFailedRequest>,
+) -> ActixResult<HttpResponse> {
+    let payout = services::ledger_service::mark_payout_failed(
+        &db,
+        sms.get_ref(),
+        admin.staff_id,
+        path.into_inner(),
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(payout))
+}
+
+pub async fn ledger_reconciliation(db: web::Data<Database>) -> ActixResult<HttpResponse> {
+    let report = services::ledger_service::reconciliation(&db).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+pub async fn list_exchange_rates(
+    db: web::Data<Database>,
+    query: web::Query<ExchangeRateQuery>,
+) -> ActixResult<HttpResponse> {
+    let rates = services::exchange_rate_service::list_exchange_rates(&db, &query).await?;
+    Ok(HttpResponse::Ok().json(rates))
+}
+
+/// Sets a pair's rate from a given day, replacing any rate set for that day.
+pub async fn set_exchange_rate(
+    db: web::Data<Database>,
+    admin: AuthenticatedStaff,
+    payload: web::Json<CreateExchangeRateRequest>,
+) -> ActixResult<HttpResponse> {
+    let rate = services::exchange_rate_service::set_exchange_rate(&db, admin.staff_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(rate))
+}
+
+pub async fn delete_exchange_rate(db: web::Data<Database>, path: web::Path<Uuid>) -> ActixResult<HttpResponse> {
+    services::exchange_rate_service::delete_exchange_rate(&db, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
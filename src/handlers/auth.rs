@@ -0,0 +1,265 @@
+use actix_session::Session;
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Result as ActixResult};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    database::Database,
+    errors::AppResult,
+    middleware::auth::{store_account_session, AuthenticatedAccount},
+    models::{
+        AccountRole, LogoutRequest, OtpPurpose, RefreshTokenRequest, SendOtpRequest, SessionAccount,
+        VerifyPhoneRequest,
+    },
+    services::{
+        self,
+        sms_service::SmsProvider,
+        token_service::{TokenConfig, TokenPair},
+    },
+    utils::client_ip,
+};
+
+/// Finishes a login once the OTP has been accepted: bearer-token clients get
+/// a token pair, everyone else gets the account stored in the cookie session.
+pub async fn start_login(
+    db: &Database,
+    tokens: &TokenConfig,
+    session: &Session,
+    req: &HttpRequest,
+    account: SessionAccount,
+    issue_tokens: bool,
+) -> AppResult<Option<TokenPair>> {
+    if issue_tokens {
+        let pair = services::token_service::issue_tokens(db, tokens, &account).await?;
+        return Ok(Some(pair));
+    }
+
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    store_account_session(session, &account, user_agent)?;
+
+    Ok(None)
+}
+
+pub async fn request_otp(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    payload: web::Json<SendOtpRequest>,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    let purpose = payload.purpose.unwrap_or(OtpPurpose::Login);
+    let client_ip = client_ip(&req);
+    match payload.role {
+        AccountRole::Farmer => {
+            services::farmer_service::request_otp(
+                &db,
+                sms.get_ref(),
+                &payload.phone_number,
+                purpose,
+                client_ip.as_deref(),
+            )
+            .await?
+        }
+        AccountRole::Buyer => {
+            services::buyer_service::request_otp(
+                &db,
+                sms.get_ref(),
+                &payload.phone_number,
+                purpose,
+                client_ip.as_deref(),
+            )
+            .await?
+        }
+        role => {
+            services::staff_service::request_otp(
+                &db,
+                sms.get_ref(),
+                &payload.phone_number,
+                role,
+                purpose,
+                client_ip.as_deref(),
+            )
+            .await?
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "message": "OTP sent successfully",
+        "purpose": purpose
+    })))
+}
+
+pub async fn resend_otp(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    payload: web::Json<SendOtpRequest>,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    log::info!("Resending otp ");
+    let client_ip = client_ip(&req);
+    match payload.role {
+        AccountRole::Farmer => {
+            services::farmer_service::resend_otp(
+                &db,
+                sms.get_ref(),
+                &payload.phone_number,
+                payload.purpose,
+                client_ip.as_deref(),
+            )
+            .await?
+        }
+        AccountRole::Buyer => {
+            services::buyer_service::resend_otp(
+                &db,
+                sms.get_ref(),
+                &payload.phone_number,
+                payload.purpose,
+                client_ip.as_deref(),
+            )
+            .await?
+        }
+        role => {
+            services::staff_service::resend_otp(
+                &db,
+                sms.get_ref(),
+                &payload.phone_number,
+                role,
+                payload.purpose,
+                client_ip.as_deref(),
+            )
+            .await?
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "message": "OTP sent successfully"
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyOtpRequest {
+    pub phone_number: String,
+    pub otp_code: String,
+    pub purpose: OtpPurpose,
+    #[serde(default)]
+    pub issue_tokens: bool,
+    /// Which kind of account to log in to.
+    #[serde(default)]
+    pub role: AccountRole,
+}
+
+pub async fn verify(
+    db: web::Data<Database>,
+    tokens: web::Data<TokenConfig>,
+    payload: web::Json<VerifyOtpRequest>,
+    session: Session,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    let payload = payload.into_inner();
+    let request = VerifyPhoneRequest {
+        phone_number: payload.phone_number.clone(),
+        otp_code: payload.otp_code,
+        purpose: Some(payload.purpose),
+        issue_tokens: payload.issue_tokens,
+    };
+
+    if payload.role == AccountRole::Buyer {
+        let buyer = services::buyer_service::verify_buyer(&db, request, client_ip(&req).as_deref()).await?;
+        let account = SessionAccount::Buyer(services::buyer_service::buyer_session(&db, buyer.id).await?);
+        let tokens = start_login(&db, &tokens, &session, &req, account, payload.issue_tokens).await?;
+
+        return Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "purpose": payload.purpose,
+            "role": payload.role,
+            "buyer": buyer,
+            "tokens": tokens
+        })));
+    }
+
+    if payload.role.is_staff() {
+        let staff =
+            services::staff_service::verify_staff(&db, payload.role, request, client_ip(&req).as_deref()).await?;
+        let account = SessionAccount::Staff(services::staff_service::staff_session(&db, staff.id).await?);
+        let tokens = start_login(&db, &tokens, &session, &req, account, payload.issue_tokens).await?;
+
+        return Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "purpose": payload.purpose,
+            "role": payload.role,
+            "staff": staff,
+            "tokens": tokens
+        })));
+    }
+
+    let farmer = services::farmer_service::login_farmer_after_otp(&db, request, client_ip(&req).as_deref()).await?;
+    let account =
+        SessionAccount::Farmer(services::farmer_service::farmer_session_for_phone(&db, &payload.phone_number).await?);
+    let tokens = start_login(&db, &tokens, &session, &req, account, payload.issue_tokens).await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "purpose": payload.purpose,
+        "role": payload.role,
+        "farmer": farmer,
+        "tokens": tokens
+    })))
+}
+
+/// The logged-in account's profile, whatever its kind.
+pub async fn me(
+    db: web::Data<Database>,
+    account: AuthenticatedAccount,
+) -> ActixResult<HttpResponse> {
+    match &account.0 {
+        SessionAccount::Farmer(farmer) => {
+            let profile = services::farmer_service::farmer_profile(&db, farmer.farmer_id).await?;
+            Ok(HttpResponse::Ok().json(profile))
+        }
+        SessionAccount::Buyer(buyer) => {
+            let profile = services::buyer_service::get_buyer(&db, buyer.buyer_id).await?;
+            Ok(HttpResponse::Ok().json(profile))
+        }
+        SessionAccount::Staff(staff) => {
+            let profile = services::staff_service::get_staff(&db, staff.staff_id).await?;
+            Ok(HttpResponse::Ok().json(profile))
+        }
+    }
+}
+
+pub async fn logout(
+    db: web::Data<Database>,
+    session: Session,
+    payload: Option<web::Json<LogoutRequest>>,
+) -> ActixResult<HttpResponse> {
+    if let Some(refresh_token) = payload.and_then(|p| p.into_inner().refresh_token) {
+        services::token_service::revoke_refresh_token(&db, &refresh_token).await?;
+    }
+
+    session.purge();
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "message": "Logged out"
+    })))
+}
+
+pub async fn refresh(
+    db: web::Data<Database>,
+    tokens: web::Data<TokenConfig>,
+    payload: web::Json<RefreshTokenRequest>,
+) -> ActixResult<HttpResponse> {
+    let pair = services::token_service::refresh_tokens(&db, &tokens, &payload.refresh_token).await?;
+    Ok(HttpResponse::Ok().json(pair))
+}
+
+pub async fn revoke(
+    db: web::Data<Database>,
+    payload: web::Json<RefreshTokenRequest>,
+) -> ActixResult<HttpResponse> {
+    services::token_service::revoke_refresh_token(&db, &payload.refresh_token).await?;
+    Ok(HttpResponse::Ok().json(json!({ "success": true })))
+}
@@ -0,0 +1,83 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::AuthenticatedAccount,
+    models::{CreateBuyerAddressRequest, CreateBuyerRequest, UpdateBuyerAddressRequest, UpdateBuyerRequest},
+    services::{self, sms_service::SmsProvider},
+    utils::client_ip,
+};
+
+/// Registers a buyer and texts them a registration code. They finish with
+/// `POST /api/auth/verify` using `"role": "buyer"`.
+pub async fn register_buyer(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    payload: web::Json<CreateBuyerRequest>,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    let buyer = services::buyer_service::create_buyer(
+        &db,
+        sms.get_ref(),
+        payload.into_inner(),
+        client_ip(&req).as_deref(),
+    )
+    .await?;
+    Ok(HttpResponse::Created().json(buyer))
+}
+
+pub async fn get_profile(db: web::Data<Database>, account: AuthenticatedAccount) -> ActixResult<HttpResponse> {
+    let buyer = services::buyer_service::get_buyer(&db, account.buyer()?.buyer_id).await?;
+    Ok(HttpResponse::Ok().json(buyer))
+}
+
+pub async fn update_profile(
+    db: web::Data<Database>,
+    account: AuthenticatedAccount,
+    payload: web::Json<UpdateBuyerRequest>,
+) -> ActixResult<HttpResponse> {
+    let buyer =
+        services::buyer_service::update_buyer(&db, account.buyer()?.buyer_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(buyer))
+}
+
+pub async fn list_addresses(db: web::Data<Database>, account: AuthenticatedAccount) -> ActixResult<HttpResponse> {
+    let addresses = services::buyer_service::list_addresses(&db, account.buyer()?.buyer_id).await?;
+    Ok(HttpResponse::Ok().json(addresses))
+}
+
+pub async fn create_address(
+    db: web::Data<Database>,
+    account: AuthenticatedAccount,
+    payload: web::Json<CreateBuyerAddressRequest>,
+) -> ActixResult<HttpResponse> {
+    let address =
+        services::buyer_service::create_address(&db, account.buyer()?.buyer_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(address))
+}
+
+pub async fn update_address(
+    db: web::Data<Database>,
+    account: AuthenticatedAccount,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateBuyerAddressRequest>,
+) -> ActixResult<HttpResponse> {
+    let address = services::buyer_service::update_address(
+        &db,
+        account.buyer()?.buyer_id,
+        path.into_inner(),
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(address))
+}
+
+pub async fn delete_address(
+    db: web::Data<Database>,
+    account: AuthenticatedAccount,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    services::buyer_service::delete_address(&db, account.buyer()?.buyer_id, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,84 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{AuthenticatedFarmer, FarmReader},
+    models::{CreateCropCycleRequest, CropCycleQuery, UpdateCropCycleRequest},
+    services,
+};
+
+pub async fn list_templates(db: web::Data<Database>) -> ActixResult<HttpResponse> {
+    let templates = services::crop_cycle_service::list_templates(&db).await?;
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+pub async fn list_cycles(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<CropCycleQuery>,
+) -> ActixResult<HttpResponse> {
+    let cycles = services::crop_cycle_service::list_cycles(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(cycles))
+}
+
+pub async fn create_cycle(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateCropCycleRequest>,
+) -> ActixResult<HttpResponse> {
+    let schedule =
+        services::crop_cycle_service::create_cycle(&db, farmer.farmer_id, path.into_inner(), payload.into_inner())
+            .await?;
+    Ok(HttpResponse::Created().json(schedule))
+}
+
+pub async fn get_cycle(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, cycle_id) = path.into_inner();
+    let cycle = services::crop_cycle_service::get_cycle(&db, reader.farmer_id, farm_id, cycle_id).await?;
+    Ok(HttpResponse::Ok().json(cycle))
+}
+
+pub async fn cycle_schedule(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, cycle_id) = path.into_inner();
+    let schedule = services::crop_cycle_service::cycle_schedule(&db, reader.farmer_id, farm_id, cycle_id).await?;
+    Ok(HttpResponse::Ok().json(schedule))
+}
+
+pub async fn update_cycle(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+    payload: web::Json<UpdateCropCycleRequest>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, cycle_id) = path.into_inner();
+    let cycle = services::crop_cycle_service::update_cycle(
+        &db,
+        farmer.farmer_id,
+        farm_id,
+        cycle_id,
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(cycle))
+}
+
+pub async fn delete_cycle(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, cycle_id) = path.into_inner();
+    services::crop_cycle_service::delete_cycle(&db, farmer.farmer_id, farm_id, cycle_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,16 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+
+use crate::{database::Database, models::CurrentRatesQuery, services, utils::currency::CURRENCIES};
+
+pub async fn list_currencies() -> ActixResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(CURRENCIES))
+}
+
+/// The latest rate for each pair, as used to convert displayed prices.
+pub async fn current_rates(
+    db: web::Data<Database>,
+    query: web::Query<CurrentRatesQuery>,
+) -> ActixResult<HttpResponse> {
+    let rates = services::exchange_rate_service::current_rates(&db, query.as_of).await?;
+    Ok(HttpResponse::Ok().json(rates))
+}
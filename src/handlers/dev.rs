@@ -0,0 +1,117 @@
+use std::env;
+
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest, HttpResponse, Result as ActixResult};
+use futures_util::future::{ready, Ready};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::MockPaymentOutcome,
+    services::{payment_provider::PaymentProvider, payment_service, sms_service::SmsProvider},
+    utils::{one_of, phone},
+};
+
+/// Header carrying `DEV_API_TOKEN` on dev route requests.
+const DEV_TOKEN_HEADER: &str = "x-dev-token";
+
+/// Settings for the `/api/dev` routes, which are only mounted when
+/// `ENABLE_DEV_ROUTES=true`.
+#[derive(Clone)]
+pub struct DevConfig {
+    token: String,
+}
+
+impl DevConfig {
+    /// `None` unless `ENABLE_DEV_ROUTES=true`, in which case `DEV_API_TOKEN`
+    /// must be set to at least 32 characters.
+    pub fn from_env() -> AppResult<Option<Self>> {
+        if env::var("ENABLE_DEV_ROUTES").map_or(true, |value| value != "true") {
+            return Ok(None);
+        }
+        let token = env::var("DEV_API_TOKEN")
+            .ok()
+            .filter(|token| token.len() >= 32)
+            .ok_or_else(|| {
+                AppError::InternalError("DEV_API_TOKEN must be at least 32 characters long".to_string())
+            })?;
+
+        log::warn!("Dev routes are enabled under /api/dev");
+        Ok(Some(DevConfig { token }))
+    }
+}
+
+/// Mounts the dev routes.
+pub fn configure(cfg: &mut web::ServiceConfig, config: DevConfig) {
+    cfg.service(
+        web::scope("/api/dev")
+            .app_data(web::Data::new(config))
+            .route("/sms-outbox", web::get().to(sms_outbox))
+            .route("/payments/{reference}", web::post().to(complete_mock_payment)),
+    );
+}
+
+/// Extractor admitting callers that send `DEV_API_TOKEN` in `X-Dev-Token`.
+pub struct DevCaller;
+
+impl FromRequest for DevCaller {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let expected = req.app_data::<web::Data<DevConfig>>().map(|config| Sha256::digest(&config.token));
+        let given = req.headers().get(DEV_TOKEN_HEADER).map(|value| Sha256::digest(value.as_bytes()));
+
+        // Comparing digests keeps the comparison from leaking the token.
+        ready(match (expected, given) {
+            (Some(expected), Some(given)) if expected == given => Ok(DevCaller),
+            _ => Err(AppError::Unauthorized("A valid dev token is required".to_string())),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    pub phone_number: Option<String>,
+}
+
+/// Lists messages captured by the local SMS outbox so dev setups can read back
+/// OTP codes. Returns 404 unless `SMS_PROVIDER=outbox`.
+pub async fn sms_outbox(
+    _caller: DevCaller,
+    sms: web::Data<dyn SmsProvider>,
+    query: web::Query<OutboxQuery>,
+) -> ActixResult<HttpResponse> {
+    let outbox = sms
+        .outbox()
+        .ok_or_else(|| AppError::NotFound("SMS outbox is not enabled".to_string()))?;
+
+    let mut messages = outbox.messages()?;
+    if let Some(phone_number) = &query.phone_number {
+        let phone_number = phone::normalize(phone_number).unwrap_or_else(|_| phone_number.clone());
+        messages.retain(|m| m.phone_number == phone_number);
+    }
+
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+/// Plays the buyer's side of the mock payment page: settles the transaction
+/// and delivers the signed webhook the gateway would send. Returns 404 unless
+/// `PAYMENT_PROVIDER=mock`.
+pub async fn complete_mock_payment(
+    _caller: DevCaller,
+    db: web::Data<Database>,
+    payments: web::Data<dyn PaymentProvider>,
+    path: web::Path<String>,
+    payload: web::Json<MockPaymentOutcome>,
+) -> ActixResult<HttpResponse> {
+    let mock = payments
+        .mock()
+        .ok_or_else(|| AppError::NotFound("Mock payments are not enabled".to_string()))?;
+    one_of("status", &payload.status, &["success", "failed"])?;
+
+    let (body, signature) = mock.complete(&path, payload.status == "success")?;
+    payment_service::handle_webhook(&db, payments.get_ref(), &body, &signature).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "reference": path.into_inner(), "status": payload.status })))
+}
@@ -1,138 +1,76 @@
-use actix_web::{web, HttpResponse, Responder, Result as ActixResult};
+use actix_web::{web, HttpRequest, HttpResponse, Responder, Result as ActixResult};
 use serde_json::json;
 use actix_session::Session;
 
 use crate::{
     database::Database,
     errors::AppError,
-    models::{CreateFarmRequest, CreateFarmerRequest, FarmResponse, Farmer, FarmerLogin, FarmerSession, SendOtpRequest, VerifyPhoneRequest},
-    services,
+    handlers::auth::start_login,
+    middleware::auth::AuthenticatedFarmer,
+    models::{CreateFarmerRequest, FarmerLogin, SessionAccount, VerifyPhoneRequest},
+    services::{self, sms_service::SmsProvider, token_service::TokenConfig},
+    utils::client_ip,
 };
 
 pub async fn register_farmer(
     db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
     payload: web::Json<CreateFarmerRequest>,
+    req: HttpRequest,
 ) -> ActixResult<HttpResponse> {
     log::info!("Registering farmer with payload : {:?}", payload);
 
-    match services::farmer_service::create_farmer(&db, payload.into_inner()).await {
+    match services::farmer_service::create_farmer(&db, sms.get_ref(), payload.into_inner(), client_ip(&req).as_deref()).await {
         Ok(farmer) => {
             log::info!("Registered successfully");
-            return Ok(HttpResponse::Created().json(farmer));
+            Ok(HttpResponse::Created().json(farmer))
         }
         Err(AppError::ValidationError(msg)) => {
             log::warn!("Validation Error occured during registration :{}", msg);
-            return Ok(HttpResponse::BadRequest().json(json!(
+            Ok(HttpResponse::BadRequest().json(json!(
             {"error" : msg , "code" : "VALIDATION_ERROR"}
-            )));
+            )))
         }
+        Err(err @ AppError::RateLimited { .. }) => Err(err.into()),
         Err(err) => {
             log::error!("Error registering farmer {:?}", err);
-            return Ok(HttpResponse::InternalServerError().json(json!({
+            Ok(HttpResponse::InternalServerError().json(json!({
                 "error" : "Registration failed" , "code" : "INTERNAL_ERROR"
-            })));
+            })))
         }
     }
 }
 
 pub async fn verify_phone(
     db: web::Data<Database>,
+    tokens: web::Data<TokenConfig>,
     payload: web::Json<VerifyPhoneRequest>,
-    session : Session
+    session : Session,
+    req : HttpRequest
 ) -> ActixResult<HttpResponse> {
 
     let phone_number = payload.clone().phone_number;
+    let issue_tokens = payload.issue_tokens;
     
-    match services::farmer_service::verify_phone_number(&db, payload.into_inner()).await {
-        Ok(success) => {
-            if success == true {
-                log::info!("Farmer verification completed");
-
-                // get the farmer and the farm from the id . The farmer is gotten from the phone number
-
-                let farmer : Farmer = sqlx::query_as::<_, Farmer>(
-                    r#"
-                        SELECT * FROM farmers WHERE phone_number = $1
-                        Limit 1
-                    "#
-                )
-                .bind(&phone_number)
-                .fetch_one(&db.pool)
-                .await
-                .map_err(|e| {
-                log::error!("Failed to fetch farmer: {:?}", e);
-                actix_web::error::ErrorInternalServerError("Error fetching farmer")
-            })?;
+    if let Err(err) = services::farmer_service::verify_phone_number(&db, payload.into_inner(), client_ip(&req).as_deref()).await {
+        log::warn!("Farmer verification failed: {}", err);
+        return Err(err.into());
+    }
 
-                let farm : FarmResponse = sqlx::query_as::<_, FarmResponse>(
-                    r#"
-                        SELECT * FROM farm WHERE phone_number = $1
-                        Limit 1
-                    "#
-                )
-                .bind(&phone_number)
-                .fetch_one(&db.pool)
-                .await
-                .map_err(|e| {
-                log::error!("Failed to fetch farm: {:?}", e);
-                actix_web::error::ErrorInternalServerError("Error fetching farm")
-            })?;
-                
-                let farmer = FarmerSession{
-                    farmer_id : farmer.id , 
-                    farm_id : farm.id,
-                    name : farmer.first_name
-                };
+    log::info!("Farmer verification completed");
 
-               let json = serde_json::to_string(&farmer).unwrap();
-               session.insert("farmer", json).unwrap();
-                Ok(HttpResponse::Ok().json(json!(
-                    {
-                        "success" : true , "message" : "Phone verified successfully"
-                    }
-                )))
-            } else {
-                log::warn!("Farmer verification failed");
-                Ok(HttpResponse::BadRequest().json(json!(
-                    {
-                        "success" : false , "message" : "Phone verified failed"
-                    }
-                )))
+    let farmer = services::farmer_service::farmer_session_for_phone(&db, &phone_number).await?;
+    match start_login(&db, &tokens, &session, &req, SessionAccount::Farmer(farmer), issue_tokens).await? {
+        Some(tokens) => Ok(HttpResponse::Ok().json(json!(
+            {
+                "success" : true , "message" : "Phone verified successfully" , "tokens" : tokens
             }
-        }
-        Err(e) => {
-            log::error!("Server Error {:?}", e);
-            Ok(HttpResponse::InternalServerError().json(json!(
-                {
-
-                        "error" : false , "code" : "Server Error"
-
-                }
-            )))
-        }
-    }
-}
-
-pub async fn resend_otp(
-    db: &Database,
-    payload: web::Json<SendOtpRequest>,
-) -> ActixResult<HttpResponse> {
-    log::info!("Resending otp ");
-    match services::farmer_service::send_otp(db, &payload.phone_number).await {
-        Ok(_) => {
-            log::info!("Otp sent successfully");
-            Ok(HttpResponse::Ok().json(json!({
-                "success": true,
-                "message": "OTP sent successfully"
-            })))
-        }
-        Err(_e) => {
-            log::info!("Otp sent successfully");
-             Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to send OTP",
-                "code": "INTERNAL_ERROR"
-            })))
-        }
+        ))),
+        None => Ok(HttpResponse::Ok().json(json!(
+            {
+                "success" : true , "message" : "Phone verified successfully"
+            }
+        ))),
     }
 }
 
@@ -140,59 +78,50 @@ pub async fn resend_otp(
 
 pub async fn farmer_login (
     db: web::Data<Database>,
-    payload: web::Json<FarmerLogin>
+    sms: web::Data<dyn SmsProvider>,
+    payload: web::Json<FarmerLogin>,
+    req: HttpRequest
 ) -> ActixResult<HttpResponse>{
     
     log::info!("Logging in farmer");
 
-    match services::farmer_service::send_login_otp(&db, payload.into_inner()).await{
+    match services::farmer_service::send_login_otp(&db, sms.get_ref(), payload.into_inner(), client_ip(&req).as_deref()).await{
         Ok(_) =>{
             log::info!("Otp sent");
-            return Ok(HttpResponse::Ok().json(
+            Ok(HttpResponse::Ok().json(
                 json!(
                     {
                         "success": true 
                     }
                 )
-            ));
+            ))
         },
-        Err (AppError::ValidationError(msg)) => {
+        Err (AppError::ValidationError(_)) => {
             log::info!("Validation Error");
-            return Ok(HttpResponse::BadRequest().json(
+            Ok(HttpResponse::BadRequest().json(
                 json!(
                     {
                         "success": false
                     }
                 )
-            ));
+            ))
         }
+        Err(err @ AppError::RateLimited { .. }) => Err(err.into()),
         Err(_) => {
             log::info!("Server error");
-            return Ok(HttpResponse::InternalServerError().json(
+            Ok(HttpResponse::InternalServerError().json(
                 json!(
                     {
                         "error" : "Server Error", 
                         "message" : "Server temporarily unavailable"
                     }
                 )
-            ));
+            ))
         }
     }
     
 }
 
-pub async fn dashboard(session : Session) -> impl Responder{
-    if let Some(sesh) = session.get::<String>("farmer").unwrap(){
-        let farmer : FarmerSession = serde_json::from_str(&sesh).unwrap();
-        HttpResponse::Ok().body(format!("Welcome back {}" , farmer.name))
-    }
-    else{
-        HttpResponse::Unauthorized().body("Not logged in")
-    }
+pub async fn dashboard(farmer : AuthenticatedFarmer) -> impl Responder{
+    HttpResponse::Ok().body(format!("Welcome back {}" , farmer.name))
 }
-
-pub async fn logout(session : Session) -> impl Responder{
-    session.purge();
-    HttpResponse::Ok().body("Logged out")
-}
-
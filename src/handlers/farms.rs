@@ -0,0 +1,189 @@
+use actix_session::Session;
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{set_active_farm, uses_bearer_token, AuthenticatedFarmer, FarmReader},
+    models::{
+        CreateFarmPlotRequest, CreateFarmRequest, FarmBoundaryRequest, FarmQuery, FarmerFarms, SessionAccount,
+        UpdateFarmPlotRequest, UpdateFarmRequest,
+    },
+    services::{self, token_service::TokenConfig},
+};
+
+pub async fn list_farms(
+    db: web::Data<Database>,
+    query: web::Query<FarmQuery>,
+) -> ActixResult<HttpResponse> {
+    let farms = services::farm_service::search_farms(&db, &query).await?;
+    Ok(HttpResponse::Ok().json(farms))
+}
+
+pub async fn my_farms(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+) -> ActixResult<HttpResponse> {
+    let farms = services::farm_service::list_farmer_farms(&db, farmer.farmer_id).await?;
+    Ok(HttpResponse::Ok().json(FarmerFarms {
+        active_farm_id: farmer.farm_id,
+        farms,
+    }))
+}
+
+pub async fn create_farm(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    session: Session,
+    payload: web::Json<CreateFarmRequest>,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    let farm = services::farm_service::create_farm(&db, farmer.farmer_id, payload.into_inner()).await?;
+
+    // A farmer's first farm becomes active straight away.
+    if farmer.farm_id.is_none() && !uses_bearer_token(&req) {
+        set_active_farm(&session, Some(farm.id))?;
+    }
+
+    Ok(HttpResponse::Created().json(farm))
+}
+
+/// The owner and staff who may read the farm get all of it; anyone else gets
+/// the public listing, without the owner, address or exact location.
+pub async fn get_farm(
+    db: web::Data<Database>,
+    reader: Option<FarmReader>,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let farm = services::farm_service::get_farm(&db, path.into_inner()).await?;
+    if reader.is_some_and(|reader| reader.farmer_id == farm.farmer_id) {
+        return Ok(HttpResponse::Ok().json(farm));
+    }
+    Ok(HttpResponse::Ok().json(services::farm_service::public_farm(farm)))
+}
+
+pub async fn update_farm(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateFarmRequest>,
+) -> ActixResult<HttpResponse> {
+    let farm = services::farm_service::update_farm(&db, farmer.farmer_id, path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(farm))
+}
+
+pub async fn delete_farm(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    session: Session,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    let farm_id = path.into_inner();
+    services::farm_service::delete_farm(&db, farmer.farmer_id, farm_id).await?;
+
+    if farmer.farm_id == Some(farm_id) && !uses_bearer_token(&req) {
+        let fallback = services::farm_service::default_farm_id(&db, farmer.farmer_id).await?;
+        set_active_farm(&session, fallback)?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Makes a farm the active one. Cookie sessions are updated in place; bearer
+/// clients get a new access token carrying the farm.
+pub async fn activate_farm(
+    db: web::Data<Database>,
+    tokens: web::Data<TokenConfig>,
+    farmer: AuthenticatedFarmer,
+    session: Session,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> ActixResult<HttpResponse> {
+    let farm = services::farm_service::owned_farm(&db, farmer.farmer_id, path.into_inner()).await?;
+
+    if uses_bearer_token(&req) {
+        let mut updated = farmer.0.clone();
+        updated.farm_id = Some(farm.id);
+        let access_token = services::token_service::issue_access_token(&tokens, &SessionAccount::Farmer(updated))?;
+        return Ok(HttpResponse::Ok().json(json!({
+            "active_farm_id": farm.id,
+            "access_token": access_token
+        })));
+    }
+
+    set_active_farm(&session, Some(farm.id))?;
+    Ok(HttpResponse::Ok().json(json!({ "active_farm_id": farm.id })))
+}
+
+pub async fn get_boundary(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let boundary = services::farm_service::farm_boundary(&db, reader.farmer_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(boundary))
+}
+
+pub async fn set_boundary(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<FarmBoundaryRequest>,
+) -> ActixResult<HttpResponse> {
+    let boundary =
+        services::farm_service::set_farm_boundary(&db, farmer.farmer_id, path.into_inner(), &payload.boundary).await?;
+    Ok(HttpResponse::Ok().json(boundary))
+}
+
+pub async fn clear_boundary(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    services::farm_service::clear_farm_boundary(&db, farmer.farmer_id, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_plots(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let plots = services::plot_service::list_plots(&db, reader.farmer_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(plots))
+}
+
+pub async fn create_plot(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateFarmPlotRequest>,
+) -> ActixResult<HttpResponse> {
+    let plot =
+        services::plot_service::create_plot(&db, farmer.farmer_id, path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(plot))
+}
+
+pub async fn update_plot(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+    payload: web::Json<UpdateFarmPlotRequest>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, plot_id) = path.into_inner();
+    let plot =
+        services::plot_service::update_plot(&db, farmer.farmer_id, farm_id, plot_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(plot))
+}
+
+pub async fn delete_plot(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, plot_id) = path.into_inner();
+    services::plot_service::delete_plot(&db, farmer.farmer_id, farm_id, plot_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
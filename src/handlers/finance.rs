@@ -0,0 +1,82 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{AuthenticatedFarmer, FarmReader},
+    models::{CreateLabourEntryRequest, CreateProductSaleRequest, FinanceQuery, PnlQuery},
+    services,
+};
+
+pub async fn list_labour(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<FinanceQuery>,
+) -> ActixResult<HttpResponse> {
+    let entries = services::finance_service::list_labour(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub async fn create_labour(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateLabourEntryRequest>,
+) -> ActixResult<HttpResponse> {
+    let entry =
+        services::finance_service::create_labour(&db, farmer.farmer_id, path.into_inner(), payload.into_inner())
+            .await?;
+    Ok(HttpResponse::Created().json(entry))
+}
+
+pub async fn delete_labour(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, entry_id) = path.into_inner();
+    services::finance_service::delete_labour(&db, farmer.farmer_id, farm_id, entry_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_sales(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<FinanceQuery>,
+) -> ActixResult<HttpResponse> {
+    let sales = services::finance_service::list_sales(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(sales))
+}
+
+pub async fn create_sale(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateProductSaleRequest>,
+) -> ActixResult<HttpResponse> {
+    let sale =
+        services::finance_service::create_sale(&db, farmer.farmer_id, path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(sale))
+}
+
+pub async fn delete_sale(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, sale_id) = path.into_inner();
+    services::finance_service::delete_sale(&db, farmer.farmer_id, farm_id, sale_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn season_pnl(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<PnlQuery>,
+) -> ActixResult<HttpResponse> {
+    let report = services::finance_service::season_pnl(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
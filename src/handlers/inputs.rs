@@ -0,0 +1,85 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{AuthenticatedFarmer, FarmReader},
+    models::{CreateFarmInputRequest, FarmInputQuery, InputLedgerQuery, RecordPurchaseRequest, UpdateFarmInputRequest},
+    services,
+};
+
+pub async fn list_inputs(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<FarmInputQuery>,
+) -> ActixResult<HttpResponse> {
+    let inputs = services::input_service::list_inputs(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(inputs))
+}
+
+pub async fn create_input(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateFarmInputRequest>,
+) -> ActixResult<HttpResponse> {
+    let input =
+        services::input_service::create_input(&db, farmer.farmer_id, path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(input))
+}
+
+pub async fn get_input(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, input_id) = path.into_inner();
+    let input = services::input_service::get_input(&db, reader.farmer_id, farm_id, input_id).await?;
+    Ok(HttpResponse::Ok().json(input))
+}
+
+pub async fn update_input(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+    payload: web::Json<UpdateFarmInputRequest>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, input_id) = path.into_inner();
+    let input =
+        services::input_service::update_input(&db, farmer.farmer_id, farm_id, input_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(input))
+}
+
+pub async fn delete_input(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, input_id) = path.into_inner();
+    services::input_service::delete_input(&db, farmer.farmer_id, farm_id, input_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn record_purchase(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<(Uuid, Uuid)>,
+    payload: web::Json<RecordPurchaseRequest>,
+) -> ActixResult<HttpResponse> {
+    let (farm_id, input_id) = path.into_inner();
+    let input =
+        services::input_service::record_purchase(&db, farmer.farmer_id, farm_id, input_id, payload.into_inner())
+            .await?;
+    Ok(HttpResponse::Created().json(input))
+}
+
+pub async fn ledger(
+    db: web::Data<Database>,
+    reader: FarmReader,
+    path: web::Path<Uuid>,
+    query: web::Query<InputLedgerQuery>,
+) -> ActixResult<HttpResponse> {
+    let entries = services::input_service::list_transactions(&db, reader.farmer_id, path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
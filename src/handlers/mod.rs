@@ -1,2 +1,18 @@
+pub mod activities;
+pub mod admin;
+pub mod auth;
+pub mod buyers;
+pub mod crop_cycles;
+pub mod currencies;
+pub mod dev;
+pub mod farms;
 pub mod farmers;
+pub mod finance;
+pub mod inputs;
+pub mod orders;
+pub mod payments;
 pub mod products;
+pub mod sessions;
+pub mod staff;
+pub mod units;
+pub mod wallet;
@@ -0,0 +1,133 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{AuthenticatedBuyer, AuthenticatedFarmer},
+    models::{
+        AddCartItemRequest, CheckoutRequest, DisplayCurrencyQuery, OrderQuery, OrderStatusChange, UpdateCartItemRequest,
+    },
+    services::{
+        self, exchange_rate_service::display_currency, payment_provider::PaymentProvider, sms_service::SmsProvider,
+    },
+};
+
+pub async fn get_cart(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    query: web::Query<DisplayCurrencyQuery>,
+) -> ActixResult<HttpResponse> {
+    let display = display_currency(&query)?;
+    let cart = services::cart_service::get_cart(&db, buyer.buyer_id, display).await?;
+    Ok(HttpResponse::Ok().json(cart))
+}
+
+pub async fn add_cart_item(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    payload: web::Json<AddCartItemRequest>,
+    query: web::Query<DisplayCurrencyQuery>,
+) -> ActixResult<HttpResponse> {
+    let display = display_currency(&query)?;
+    let cart = services::cart_service::add_item(&db, buyer.buyer_id, payload.into_inner(), display).await?;
+    Ok(HttpResponse::Ok().json(cart))
+}
+
+pub async fn update_cart_item(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateCartItemRequest>,
+    query: web::Query<DisplayCurrencyQuery>,
+) -> ActixResult<HttpResponse> {
+    let display = display_currency(&query)?;
+    let cart =
+        services::cart_service::update_item(&db, buyer.buyer_id, path.into_inner(), payload.into_inner(), display)
+            .await?;
+    Ok(HttpResponse::Ok().json(cart))
+}
+
+pub async fn remove_cart_item(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+    query: web::Query<DisplayCurrencyQuery>,
+) -> ActixResult<HttpResponse> {
+    let display = display_currency(&query)?;
+    let cart = services::cart_service::remove_item(&db, buyer.buyer_id, path.into_inner(), display).await?;
+    Ok(HttpResponse::Ok().json(cart))
+}
+
+pub async fn clear_cart(db: web::Data<Database>, buyer: AuthenticatedBuyer) -> ActixResult<HttpResponse> {
+    services::cart_service::clear_cart(&db, buyer.buyer_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn checkout(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    buyer: AuthenticatedBuyer,
+    payload: Option<web::Json<CheckoutRequest>>,
+) -> ActixResult<HttpResponse> {
+    let payload = payload.map(web::Json::into_inner).unwrap_or_default();
+    let checkout = services::order_service::checkout(&db, sms.get_ref(), buyer.buyer_id, payload).await?;
+    Ok(HttpResponse::Created().json(checkout))
+}
+
+pub async fn list_orders(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    query: web::Query<OrderQuery>,
+) -> ActixResult<HttpResponse> {
+    let orders = services::order_service::list_orders(&db, buyer.buyer_id, &query).await?;
+    Ok(HttpResponse::Ok().json(orders))
+}
+
+pub async fn list_received_orders(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    query: web::Query<OrderQuery>,
+) -> ActixResult<HttpResponse> {
+    let orders = services::order_service::list_received_orders(&db, farmer.farmer_id, &query).await?;
+    Ok(HttpResponse::Ok().json(orders))
+}
+
+pub async fn get_order(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let order = services::order_service::get_order(&db, buyer.buyer_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(order))
+}
+
+/// Moves an order along its lifecycle: the farmer accepts (`confirmed`),
+/// rejects and fulfils it, the buyer cancels or confirms receipt.
+pub async fn change_order_status(
+    db: web::Data<Database>,
+    sms: web::Data<dyn SmsProvider>,
+    payments: web::Data<dyn PaymentProvider>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+    payload: web::Json<OrderStatusChange>,
+) -> ActixResult<HttpResponse> {
+    let order = services::order_service::change_status(
+        &db,
+        sms.get_ref(),
+        payments.get_ref(),
+        buyer.buyer_id,
+        path.into_inner(),
+        payload.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(order))
+}
+
+pub async fn list_order_events(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let events = services::order_service::list_events(&db, buyer.buyer_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(events))
+}
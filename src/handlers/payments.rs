@@ -0,0 +1,71 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::AppError,
+    middleware::auth::AuthenticatedBuyer,
+    models::CreatePaymentRequest,
+    services::{self, payment_provider::PaymentProvider},
+};
+
+/// Starts paying for a checkout. The response's `authorization_url` is where
+/// the buyer completes the payment.
+pub async fn create_payment(
+    db: web::Data<Database>,
+    payments: web::Data<dyn PaymentProvider>,
+    buyer: AuthenticatedBuyer,
+    payload: web::Json<CreatePaymentRequest>,
+) -> ActixResult<HttpResponse> {
+    let payment =
+        services::payment_service::create_payment(&db, payments.get_ref(), buyer.buyer_id, payload.into_inner())
+            .await?;
+    Ok(HttpResponse::Created().json(payment))
+}
+
+pub async fn list_payments(db: web::Data<Database>, buyer: AuthenticatedBuyer) -> ActixResult<HttpResponse> {
+    let payments = services::payment_service::list_payments(&db, buyer.buyer_id).await?;
+    Ok(HttpResponse::Ok().json(payments))
+}
+
+pub async fn get_payment(
+    db: web::Data<Database>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let payment = services::payment_service::get_payment(&db, buyer.buyer_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(payment))
+}
+
+/// Checks the payment with the gateway, for when the buyer is back from the
+/// payment page before the webhook.
+pub async fn verify_payment(
+    db: web::Data<Database>,
+    payments: web::Data<dyn PaymentProvider>,
+    buyer: AuthenticatedBuyer,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let payment =
+        services::payment_service::verify_payment(&db, payments.get_ref(), buyer.buyer_id, path.into_inner())
+            .await?;
+    Ok(HttpResponse::Ok().json(payment))
+}
+
+/// Gateway webhook. Unauthenticated; the body must carry a valid HMAC
+/// signature in the provider's signature header.
+pub async fn webhook(
+    db: web::Data<Database>,
+    payments: web::Data<dyn PaymentProvider>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> ActixResult<HttpResponse> {
+    let signature = req
+        .headers()
+        .get(payments.signature_header())
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing webhook signature".to_string()))?;
+
+    services::payment_service::handle_webhook(&db, payments.get_ref(), &body, signature).await?;
+    Ok(HttpResponse::Ok().json(json!({ "received": true })))
+}
@@ -1,9 +1,17 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder, Result as ActixResult};
 use sqlx::{Error as SqlxError};
 use uuid::Uuid;
 
 use crate::database::Database;
-use crate::models::product::{NewProduct, Product, slugify};
+use crate::errors::AppError;
+use crate::services;
+use crate::utils::currency;
+use crate::middleware::auth::AuthenticatedFarmer;
+use crate::models::{
+    can_transition_status, slugify, NewProduct, Product, ProductQuery, ProductStatusChange, ProductUpdate,
+    PRODUCT_COLUMNS, PRODUCT_STATUSES, PRODUCT_VISIBILITIES,
+};
+
 
 #[derive(Debug, serde::Serialize)]
 struct ApiError {
@@ -25,11 +33,90 @@ fn validate_new_product(p : &NewProduct ) -> Result<() , String>{
         return Err("Price_cents must be >= 0".into());
     }
     if p.min_order_qty <= 0{
+        return Err("Min_order_qty must be > 0".into());
+    }
+    if p.quantity_available < 0 {
         return Err("Quantity available must be >= 0".into());
     }
+    if let Some(slug) = &p.slug {
+        if slug.trim().is_empty() {
+            return Err("Slug cannot be empty".into());
+        }
+    }
+    if let Some(status) = &p.status {
+        if !PRODUCT_STATUSES.contains(&status.as_str()) {
+            return Err(format!("Status must be one of: {}", PRODUCT_STATUSES.join(", ")));
+        }
+    }
+    if let Some(visibility) = &p.visibility {
+        if !PRODUCT_VISIBILITIES.contains(&visibility.as_str()) {
+            return Err(format!("Visibility must be one of: {}", PRODUCT_VISIBILITIES.join(", ")));
+        }
+    }
     Ok(())
 }
 
+async fn check_farm_owner(db: &Database, farm_id: Uuid, farmer_id: Uuid) -> Result<(), HttpResponse> {
+    let owned = sqlx::query("SELECT 1 FROM farms WHERE id = $1 AND farmer_id = $2")
+        .bind(farm_id)
+        .bind(farmer_id)
+        .fetch_optional(&db.pool)
+        .await;
+
+    match owned {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::BadRequest().json(ApiError { error: "Farm not found".into() })),
+        Err(e) => Err(HttpResponse::InternalServerError()
+            .json(ApiError { error: format!("Failed to look up farm: {}", e) })),
+    }
+}
+
+/// The farm a new listing goes on when the body doesn't name one: the
+/// session's active farm if the farmer still owns it, else their oldest farm.
+/// The session copy can be stale, e.g. after the farm is deleted elsewhere.
+async fn default_farm(db: &Database, farmer_id: Uuid, active: Option<Uuid>) -> Result<Option<Uuid>, HttpResponse> {
+    let farm_id = sqlx::query_scalar(
+        "SELECT id FROM farms WHERE farmer_id = $1 ORDER BY (id = $2) IS TRUE DESC, created_at, id LIMIT 1",
+    )
+    .bind(farmer_id)
+    .bind(active)
+    .fetch_optional(&db.pool)
+    .await;
+
+    farm_id.map_err(|e| {
+        HttpResponse::InternalServerError().json(ApiError { error: format!("Failed to look up farm: {}", e) })
+    })
+}
+
+/// Replaces the payload's unit with its registry code.
+async fn canonical_unit(db: &Database, payload: &mut NewProduct) -> Result<(), HttpResponse> {
+    let canonical = match services::unit_service::registry(db).await {
+        Ok(units) => units.canonical(&payload.unit),
+        Err(e) => Err(e),
+    };
+
+    match canonical {
+        Ok(unit) => {
+            payload.unit = unit;
+            Ok(())
+        }
+        Err(AppError::ValidationError(msg)) => Err(HttpResponse::BadRequest().json(ApiError { error: msg })),
+        Err(e) => Err(HttpResponse::InternalServerError()
+            .json(ApiError { error: format!("Failed to look up units: {}", e) })),
+    }
+}
+
+fn canonical_currency(payload: &mut NewProduct) -> Result<(), HttpResponse> {
+    let code = payload.currency_code.as_deref().unwrap_or("NGN");
+    match currency::find(code) {
+        Some(currency) => {
+            payload.currency_code = Some(currency.code.to_string());
+            Ok(())
+        }
+        None => Err(HttpResponse::BadRequest().json(ApiError { error: format!("Unknown currency '{}'", code.trim()) })),
+    }
+}
+
 async fn insert_product(
     db : &Database , id : Uuid , payload : &NewProduct , slug: &str
 ) -> Result<Product , SqlxError>{
@@ -96,16 +183,40 @@ async fn insert_product(
 
 pub async fn add_products(
     db : web::Data<Database>, 
+    farmer : AuthenticatedFarmer,
     json : web::Json<NewProduct>
 ) -> impl Responder
 {
-    let payload = json.into_inner();
+    let mut payload = json.into_inner();
+    payload.farmer_id = farmer.farmer_id;
+    if payload.farm_id.is_none() {
+        payload.farm_id = match default_farm(&db, farmer.farmer_id, farmer.farm_id).await {
+            Ok(farm_id) => farm_id,
+            Err(resp) => return resp,
+        };
+    }
 
     if let Err(msg) = validate_new_product(&payload){
         return HttpResponse::BadRequest().json(ApiError{
             error : msg
         })
     }
+    if !matches!(payload.status.as_deref(), None | Some("draft") | Some("published")) {
+        return HttpResponse::BadRequest().json(ApiError { error: "A new product must be draft or published".into() });
+    }
+
+    if let Err(resp) = canonical_unit(&db, &mut payload).await {
+        return resp;
+    }
+    if let Err(resp) = canonical_currency(&mut payload) {
+        return resp;
+    }
+
+    if let Some(farm_id) = payload.farm_id {
+        if let Err(resp) = check_farm_owner(&db, farm_id, payload.farmer_id).await {
+            return resp;
+        }
+    }
 
     let base_slug = payload.slug.clone().unwrap_or_else(||
     slugify(&payload.name)
@@ -143,4 +254,249 @@ pub async fn add_products(
     HttpResponse::InternalServerError().json(
         ApiError{error : "Failed to create product after retries".into()}
     )
-}
\ No newline at end of file
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(ApiError { error: "Product not found".into() })
+}
+
+fn lookup_failed(e: SqlxError) -> HttpResponse {
+    HttpResponse::InternalServerError().json(ApiError { error: format!("Failed to look up product: {}", e) })
+}
+
+async fn find_product(db: &Database, id: Uuid) -> Result<Option<Product>, SqlxError> {
+    sqlx::query_as::<_, Product>(&format!("SELECT {} FROM products WHERE id = $1", PRODUCT_COLUMNS))
+        .bind(id)
+        .fetch_optional(&db.pool)
+        .await
+}
+
+/// Loads a product for a write by `farmer`. Other farmers' products are
+/// reported as forbidden rather than hidden, since the id was already public.
+async fn find_owned_product(db: &Database, id: Uuid, farmer: &AuthenticatedFarmer) -> Result<Product, HttpResponse> {
+    match find_product(db, id).await {
+        Ok(Some(product)) if product.farmer_id == farmer.farmer_id => Ok(product),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden()
+            .json(ApiError { error: "You can only modify your own products".into() })),
+        Ok(None) => Err(not_found()),
+        Err(e) => Err(lookup_failed(e)),
+    }
+}
+
+/// Published products are public; drafts and archived products are only
+/// visible to the farmer who owns them.
+fn visible_to(product: &Product, farmer: &Option<AuthenticatedFarmer>) -> bool {
+    product.status == "published" || farmer.as_ref().is_some_and(|f| f.farmer_id == product.farmer_id)
+}
+
+fn product_response(product: Result<Option<Product>, SqlxError>, farmer: &Option<AuthenticatedFarmer>) -> HttpResponse {
+    match product {
+        Ok(Some(product)) if visible_to(&product, farmer) => HttpResponse::Ok().json(product),
+        Ok(_) => not_found(),
+        Err(e) => lookup_failed(e),
+    }
+}
+
+pub async fn list_products(
+    db: web::Data<Database>,
+    farmer: Option<AuthenticatedFarmer>,
+    query: web::Query<ProductQuery>,
+) -> ActixResult<HttpResponse> {
+    let viewer = farmer.map(|f| f.farmer_id);
+    let page = services::product_service::search_products(&db, &query, viewer).await?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+pub async fn get_product(
+    db: web::Data<Database>,
+    farmer: Option<AuthenticatedFarmer>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    product_response(find_product(&db, path.into_inner()).await, &farmer)
+}
+
+pub async fn get_product_by_slug(
+    db: web::Data<Database>,
+    farmer: Option<AuthenticatedFarmer>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let product = sqlx::query_as::<_, Product>(&format!("SELECT {} FROM products WHERE slug = $1", PRODUCT_COLUMNS))
+        .bind(path.into_inner())
+        .fetch_optional(&db.pool)
+        .await;
+
+    product_response(product, &farmer)
+}
+
+/// Writes the update unless the product's status moved away from
+/// `current_status` since it was read, e.g. an admin suspended it; `None`
+/// then. The status itself is only written when the client sent one.
+async fn save_product(
+    db: &Database,
+    id: Uuid,
+    payload: &NewProduct,
+    status: Option<&str>,
+    current_status: &str,
+) -> Result<Option<Product>, SqlxError> {
+    sqlx::query_as::<_, Product>(&format!(
+        r#"
+        UPDATE products SET
+            farm_id = $3, name = $4, slug = $5, description = $6,
+            category = $7, unit = $8, tags = $9,
+            price_cents = $10, currency_code = $11,
+            min_order_qty = $12, quantity_available = $13,
+            organic = $14, perishable = $15,
+            expected_harvest_date = $16, expiry_date = $17,
+            status = COALESCE($18, status), visibility = $19, images = $20
+        WHERE id = $1 AND farmer_id = $2 AND status = $21
+        RETURNING {}
+        "#,
+        PRODUCT_COLUMNS
+    ))
+    .bind(id)
+    .bind(payload.farmer_id)
+    .bind(payload.farm_id)
+    .bind(&payload.name)
+    .bind(&payload.slug)
+    .bind(&payload.description)
+    .bind(&payload.category)
+    .bind(&payload.unit)
+    .bind(&payload.tags)
+    .bind(payload.price_cents)
+    .bind(&payload.currency_code)
+    .bind(payload.min_order_qty)
+    .bind(payload.quantity_available)
+    .bind(payload.organic)
+    .bind(payload.perishable)
+    .bind(payload.expected_harvest_date)
+    .bind(payload.expiry_date)
+    .bind(status)
+    .bind(&payload.visibility)
+    .bind(&payload.images)
+    .bind(current_status)
+    .fetch_optional(&db.pool)
+    .await
+}
+
+fn status_change_error(from: &str, to: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiError {
+        error: format!("Cannot change product status from {} to {}", from, to),
+    })
+}
+
+fn status_changed() -> HttpResponse {
+    HttpResponse::Conflict().json(ApiError {
+        error: "The product's status changed while you were editing it; reload and try again".into(),
+    })
+}
+
+pub async fn update_product(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    json: web::Json<ProductUpdate>,
+) -> impl Responder {
+    let current = match find_owned_product(&db, path.into_inner(), &farmer).await {
+        Ok(product) => product,
+        Err(resp) => return resp,
+    };
+
+    // Only a unit or currency sent with the update is checked; the stored
+    // ones may predate the registry.
+    let update = json.into_inner();
+    let (unit_given, currency_given) = (update.unit.is_some(), update.currency_code.is_some());
+    let status_given = update.status.is_some();
+    let mut payload = update.apply_to(&current);
+
+    if let Err(msg) = validate_new_product(&payload) {
+        return HttpResponse::BadRequest().json(ApiError { error: msg });
+    }
+
+    if unit_given {
+        if let Err(resp) = canonical_unit(&db, &mut payload).await {
+            return resp;
+        }
+    }
+    if currency_given {
+        if let Err(resp) = canonical_currency(&mut payload) {
+            return resp;
+        }
+    }
+
+    let status = payload.status.as_deref().unwrap_or(&current.status);
+    if !can_transition_status(&current.status, status) {
+        return status_change_error(&current.status, status);
+    }
+
+    if let Some(farm_id) = payload.farm_id.filter(|id| Some(*id) != current.farm_id) {
+        if let Err(resp) = check_farm_owner(&db, farm_id, farmer.farmer_id).await {
+            return resp;
+        }
+    }
+
+    let status = payload.status.as_deref().filter(|_| status_given);
+    match save_product(&db, current.id, &payload, status, &current.status).await {
+        Ok(Some(product)) => HttpResponse::Ok().json(product),
+        Ok(None) => status_changed(),
+        Err(SqlxError::Database(db_err)) if db_err.code().as_deref() == Some("23505") => {
+            HttpResponse::Conflict().json(ApiError { error: "Slug is already in use".into() })
+        }
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiError { error: format!("Failed to update product: {}", e) }),
+    }
+}
+
+async fn set_status(db: &Database, farmer: &AuthenticatedFarmer, id: Uuid, status: &str) -> HttpResponse {
+    let current = match find_owned_product(db, id, farmer).await {
+        Ok(product) => product,
+        Err(resp) => return resp,
+    };
+
+    if !PRODUCT_STATUSES.contains(&status) {
+        return HttpResponse::BadRequest().json(ApiError {
+            error: format!("Status must be one of: {}", PRODUCT_STATUSES.join(", ")),
+        });
+    }
+    if !can_transition_status(&current.status, status) {
+        return status_change_error(&current.status, status);
+    }
+
+    // Only from the status checked above, so a suspension that lands in
+    // between isn't overwritten.
+    let updated = sqlx::query_as::<_, Product>(&format!(
+        "UPDATE products SET status = $3 WHERE id = $1 AND farmer_id = $2 AND status = $4 RETURNING {}",
+        PRODUCT_COLUMNS
+    ))
+    .bind(id)
+    .bind(farmer.farmer_id)
+    .bind(status)
+    .bind(&current.status)
+    .fetch_optional(&db.pool)
+    .await;
+
+    match updated {
+        Ok(Some(product)) => HttpResponse::Ok().json(product),
+        Ok(None) => status_changed(),
+        Err(e) => HttpResponse::InternalServerError()
+            .json(ApiError { error: format!("Failed to update product: {}", e) }),
+    }
+}
+
+pub async fn change_product_status(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+    json: web::Json<ProductStatusChange>,
+) -> impl Responder {
+    set_status(&db, &farmer, path.into_inner(), &json.status).await
+}
+
+/// Products are never hard-deleted so past orders keep their references;
+/// deleting archives the product instead.
+pub async fn archive_product(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    set_status(&db, &farmer, path.into_inner(), "archived").await
+}
@@ -0,0 +1,62 @@
+use actix_session::Session;
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::{current_session_id, AuthenticatedAccount},
+    services,
+    session_store::SessionStoreKind,
+};
+
+pub async fn list_sessions(
+    db: web::Data<Database>,
+    store: web::Data<SessionStoreKind>,
+    account: AuthenticatedAccount,
+    session: Session,
+) -> ActixResult<HttpResponse> {
+    let sessions = services::session_service::list_sessions(
+        &db,
+        **store,
+        account.id(),
+        current_session_id(&session),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+pub async fn revoke_session(
+    db: web::Data<Database>,
+    store: web::Data<SessionStoreKind>,
+    account: AuthenticatedAccount,
+    session: Session,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let session_id = path.into_inner();
+    services::session_service::revoke_session(&db, **store, account.id(), session_id).await?;
+
+    if current_session_id(&session) == Some(session_id) {
+        session.purge();
+    }
+
+    Ok(HttpResponse::Ok().json(json!({ "success": true })))
+}
+
+pub async fn revoke_other_sessions(
+    db: web::Data<Database>,
+    store: web::Data<SessionStoreKind>,
+    account: AuthenticatedAccount,
+    session: Session,
+) -> ActixResult<HttpResponse> {
+    let revoked = services::session_service::revoke_other_sessions(
+        &db,
+        **store,
+        account.id(),
+        current_session_id(&session),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({ "success": true, "revoked": revoked })))
+}
@@ -0,0 +1,10 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+
+use crate::{database::Database, middleware::auth::AuthenticatedStaff, services};
+
+/// Farms assigned to the logged-in extension officer or cooperative manager.
+/// Their records are read through the usual `/api/farms/{id}/...` routes.
+pub async fn assigned_farms(db: web::Data<Database>, staff: AuthenticatedStaff) -> ActixResult<HttpResponse> {
+    let farms = services::staff_service::assigned_farms(&db, staff.staff_id).await?;
+    Ok(HttpResponse::Ok().json(farms))
+}
@@ -0,0 +1,8 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+
+use crate::{database::Database, services};
+
+pub async fn list_units(db: web::Data<Database>) -> ActixResult<HttpResponse> {
+    let units = services::unit_service::list_units(&db).await?;
+    Ok(HttpResponse::Ok().json(units))
+}
@@ -0,0 +1,67 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    middleware::auth::AuthenticatedFarmer,
+    models::{CreateBankAccountRequest, PayoutQuery, PayoutRequest, WalletQuery},
+    services,
+};
+
+/// Held, available and in-payout balances per currency.
+pub async fn get_balances(db: web::Data<Database>, farmer: AuthenticatedFarmer) -> ActixResult<HttpResponse> {
+    let balances = services::ledger_service::wallet_balances(&db, farmer.farmer_id).await?;
+    Ok(HttpResponse::Ok().json(balances))
+}
+
+pub async fn list_entries(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    query: web::Query<WalletQuery>,
+) -> ActixResult<HttpResponse> {
+    let entries = services::ledger_service::wallet_entries(&db, farmer.farmer_id, &query).await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub async fn list_bank_accounts(db: web::Data<Database>, farmer: AuthenticatedFarmer) -> ActixResult<HttpResponse> {
+    let accounts = services::ledger_service::list_bank_accounts(&db, farmer.farmer_id).await?;
+    Ok(HttpResponse::Ok().json(accounts))
+}
+
+pub async fn create_bank_account(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    payload: web::Json<CreateBankAccountRequest>,
+) -> ActixResult<HttpResponse> {
+    let account = services::ledger_service::create_bank_account(&db, farmer.farmer_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(account))
+}
+
+pub async fn remove_bank_account(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    path: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    services::ledger_service::remove_bank_account(&db, farmer.farmer_id, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn list_payouts(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    query: web::Query<PayoutQuery>,
+) -> ActixResult<HttpResponse> {
+    let payouts = services::ledger_service::list_payouts(&db, Some(farmer.farmer_id), &query).await?;
+    Ok(HttpResponse::Ok().json(payouts))
+}
+
+/// Withdraws from the wallet to one of the farmer's bank accounts. The
+/// money is set aside until an admin sends it.
+pub async fn request_payout(
+    db: web::Data<Database>,
+    farmer: AuthenticatedFarmer,
+    payload: web::Json<PayoutRequest>,
+) -> ActixResult<HttpResponse> {
+    let payout = services::ledger_service::request_payout(&db, farmer.farmer_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(payout))
+}
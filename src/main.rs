@@ -1,10 +1,14 @@
-use actix_session::{storage::CookieSessionStore, Session, SessionMiddleware};
-use actix_web::{cookie::Key, middleware::Logger, web, App, HttpServer, Result as ActixResult};
+use actix_session::SessionMiddleware;
+use actix_web::{middleware::Logger, web, App, HttpServer, Result as ActixResult};
 use actix_cors::Cors;
 use std::env;
 use database::Database;
+use models::Permission;
+use services::{order_service, payment_provider, sms_service, staff_service, token_service::TokenConfig};
+use session_store::SessionConfig;
 
-use crate::handlers::farmers::{farmer_login, register_farmer, verify_phone};
+use crate::handlers::farmers::{dashboard, farmer_login, register_farmer, verify_phone};
+use crate::middleware::auth::{RequireFarmer, RequirePermission};
 
 mod models;
 mod handlers;
@@ -12,6 +16,8 @@ mod database;
 mod services;
 mod utils;
 mod errors;
+mod middleware;
+mod session_store;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -20,6 +26,15 @@ async fn main() -> std::io::Result<()> {
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let db = Database::new(&database_url).await.expect("Failed to connect to the database");
+    utils::init_trusted_proxies().expect("Failed to configure trusted proxies");
+    utils::init_otp_pepper().expect("Failed to configure OTP hashing");
+    utils::phone::init().expect("Failed to configure phone numbers");
+    utils::geo::init().expect("Failed to configure geo settings");
+    let sms = sms_service::provider_from_env().expect("Failed to configure SMS provider");
+    let payments = payment_provider::provider_from_env().expect("Failed to configure payment provider");
+    let dev_config = handlers::dev::DevConfig::from_env().expect("Failed to configure dev routes");
+    staff_service::bootstrap_admin(&db).await.expect("Failed to create the bootstrap admin");
+    actix_web::rt::spawn(order_service::run_order_expiry(db.clone(), sms.clone(), payments.clone()));
 
     // Get the port from the environment variable (Render provides this)
     let port = env::var("PORT")
@@ -28,7 +43,8 @@ async fn main() -> std::io::Result<()> {
         .expect("PORT must be a number");
 
     println!("Starting server on port: {}", port);
-    let secret_key = Key::generate();
+    let session_config = SessionConfig::from_env();
+    let token_config = TokenConfig::from_env();
     HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
@@ -38,21 +54,206 @@ async fn main() -> std::io::Result<()> {
 
         App::new()
             .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::from(sms.clone()))
+            .app_data(web::Data::from(payments.clone()))
+            .app_data(web::Data::new(session_config.store))
+            .app_data(web::Data::new(token_config.clone()))
+            .app_data(errors::json_config())
+            .app_data(errors::query_config())
+            .app_data(errors::path_config())
             .wrap(cors)
-            .wrap(SessionMiddleware::new(
-                CookieSessionStore::default(), secret_key.clone()
-            ))
+            .wrap(
+                SessionMiddleware::builder(
+                    session_config.build_store(&db.pool), session_config.key.clone()
+                )
+                .session_lifecycle(session_config.lifecycle())
+                .build()
+            )
             .wrap(Logger::default())
             .route("/api/health_check", web::get().to(health_check))
+            .configure(|cfg| {
+                if let Some(dev_config) = &dev_config {
+                    handlers::dev::configure(cfg, dev_config.clone());
+                }
+            })
             .service(
                 web::scope("/api/farmers")
                     .route("/register", web::post().to(register_farmer))
                     .route("/login", web::post().to(farmer_login))
-                    .route("/verify-phone", web::post().to(verify_phone)),
+                    .route("/verify-phone", web::post().to(verify_phone))
+                    .service(
+                        web::resource("/dashboard")
+                            .wrap(RequireFarmer)
+                            .route(web::get().to(dashboard)),
+                    ),
+            )
+            .service(
+                web::scope("/api/auth")
+                    .route("/request-otp", web::post().to(handlers::auth::request_otp))
+                    .route("/resend-otp", web::post().to(handlers::auth::resend_otp))
+                    .route("/verify", web::post().to(handlers::auth::verify))
+                    .route("/logout", web::post().to(handlers::auth::logout))
+                    .route("/refresh", web::post().to(handlers::auth::refresh))
+                    .route("/revoke", web::post().to(handlers::auth::revoke))
+                    .route("/me", web::get().to(handlers::auth::me))
+                    .service(
+                        web::scope("/sessions")
+                            .route("", web::get().to(handlers::sessions::list_sessions))
+                            .route("/revoke-others", web::post().to(handlers::sessions::revoke_other_sessions))
+                            .route("/{session_id}", web::delete().to(handlers::sessions::revoke_session)),
+                    ),
+            )
+            .service(
+                web::scope("/api/buyers")
+                    .route("/register", web::post().to(handlers::buyers::register_buyer))
+                    .route("/me", web::get().to(handlers::buyers::get_profile))
+                    .route("/me", web::patch().to(handlers::buyers::update_profile))
+                    .route("/me/addresses", web::get().to(handlers::buyers::list_addresses))
+                    .route("/me/addresses", web::post().to(handlers::buyers::create_address))
+                    .route("/me/addresses/{address_id}", web::patch().to(handlers::buyers::update_address))
+                    .route("/me/addresses/{address_id}", web::delete().to(handlers::buyers::delete_address)),
+            )
+            .service(
+                web::scope("/api/admin")
+                    .service(
+                        web::scope("/staff")
+                            .wrap(RequirePermission(Permission::ManageStaff))
+                            .route("", web::get().to(handlers::admin::list_staff))
+                            .route("", web::post().to(handlers::admin::create_staff))
+                            .route("/{id}", web::get().to(handlers::admin::get_staff))
+                            .route("/{id}", web::patch().to(handlers::admin::update_staff))
+                            .route("/{id}/farms", web::get().to(handlers::admin::list_staff_farms))
+                            .route("/{id}/farms", web::post().to(handlers::admin::assign_farm))
+                            .route("/{id}/farms/{farm_id}", web::delete().to(handlers::admin::unassign_farm)),
+                    )
+                    .service(
+                        web::scope("/products")
+                            .wrap(RequirePermission(Permission::ModerateProducts))
+                            .route("/{id}/suspend", web::post().to(handlers::admin::suspend_product))
+                            .route("/{id}/reinstate", web::post().to(handlers::admin::reinstate_product))
+                            .route("/{id}/moderation", web::get().to(handlers::admin::list_moderation_events)),
+                    )
+                    .service(
+                        web::scope("/payouts")
+                            .wrap(RequirePermission(Permission::ManagePayouts))
+                            .route("", web::get().to(handlers::admin::list_payouts))
+                            .route("/{id}/paid", web::post().to(handlers::admin::mark_payout_paid))
+                            .route("/{id}/failed", web::post().to(handlers::admin::mark_payout_failed)),
+                    )
+                    .service(
+                        web::scope("/refunds")
+                            .wrap(RequirePermission(Permission::ManagePayouts))
+                            .route("", web::get().to(handlers::admin::list_refunds))
+                            .route("/{id}/retry", web::post().to(handlers::admin::retry_refund)),
+                    )
+                    .service(
+                        web::resource("/ledger/reconciliation")
+                            .wrap(RequirePermission(Permission::ManagePayouts))
+                            .route(web::get().to(handlers::admin::ledger_reconciliation)),
+                    )
+                    .service(
+                        web::scope("/exchange-rates")
+                            .wrap(RequirePermission(Permission::ManageExchangeRates))
+                            .route("", web::get().to(handlers::admin::list_exchange_rates))
+                            .route("", web::post().to(handlers::admin::set_exchange_rate))
+                            .route("/{id}", web::delete().to(handlers::admin::delete_exchange_rate)),
+                    ),
+            )
+            .service(
+                web::resource("/api/staff/farms")
+                    .wrap(RequirePermission(Permission::ReadAssignedFarms))
+                    .route(web::get().to(handlers::staff::assigned_farms)),
+            )
+            .service(
+                web::scope("/api/farms")
+                    .route("", web::get().to(handlers::farms::list_farms))
+                    .route("", web::post().to(handlers::farms::create_farm))
+                    .route("/mine", web::get().to(handlers::farms::my_farms))
+                    .route("/{id}", web::get().to(handlers::farms::get_farm))
+                    .route("/{id}", web::patch().to(handlers::farms::update_farm))
+                    .route("/{id}", web::delete().to(handlers::farms::delete_farm))
+                    .route("/{id}/activate", web::post().to(handlers::farms::activate_farm))
+                    .route("/{id}/boundary", web::get().to(handlers::farms::get_boundary))
+                    .route("/{id}/boundary", web::put().to(handlers::farms::set_boundary))
+                    .route("/{id}/boundary", web::delete().to(handlers::farms::clear_boundary))
+                    .route("/{id}/plots", web::get().to(handlers::farms::list_plots))
+                    .route("/{id}/plots", web::post().to(handlers::farms::create_plot))
+                    .route("/{id}/plots/{plot_id}", web::patch().to(handlers::farms::update_plot))
+                    .route("/{id}/plots/{plot_id}", web::delete().to(handlers::farms::delete_plot))
+                    .route("/{id}/activities", web::get().to(handlers::activities::list_activities))
+                    .route("/{id}/activities", web::post().to(handlers::activities::create_activity))
+                    .route("/{id}/activities/{activity_id}", web::get().to(handlers::activities::get_activity))
+                    .route("/{id}/activities/{activity_id}", web::patch().to(handlers::activities::update_activity))
+                    .route("/{id}/activities/{activity_id}", web::delete().to(handlers::activities::delete_activity))
+                    .route("/{id}/cycles", web::get().to(handlers::crop_cycles::list_cycles))
+                    .route("/{id}/cycles", web::post().to(handlers::crop_cycles::create_cycle))
+                    .route("/{id}/cycles/{cycle_id}", web::get().to(handlers::crop_cycles::get_cycle))
+                    .route("/{id}/cycles/{cycle_id}", web::patch().to(handlers::crop_cycles::update_cycle))
+                    .route("/{id}/cycles/{cycle_id}", web::delete().to(handlers::crop_cycles::delete_cycle))
+                    .route("/{id}/cycles/{cycle_id}/schedule", web::get().to(handlers::crop_cycles::cycle_schedule))
+                    .route("/{id}/inputs", web::get().to(handlers::inputs::list_inputs))
+                    .route("/{id}/inputs", web::post().to(handlers::inputs::create_input))
+                    .route("/{id}/inputs/ledger", web::get().to(handlers::inputs::ledger))
+                    .route("/{id}/inputs/{input_id}", web::get().to(handlers::inputs::get_input))
+                    .route("/{id}/inputs/{input_id}", web::patch().to(handlers::inputs::update_input))
+                    .route("/{id}/inputs/{input_id}", web::delete().to(handlers::inputs::delete_input))
+                    .route("/{id}/inputs/{input_id}/purchases", web::post().to(handlers::inputs::record_purchase))
+                    .route("/{id}/labour", web::get().to(handlers::finance::list_labour))
+                    .route("/{id}/labour", web::post().to(handlers::finance::create_labour))
+                    .route("/{id}/labour/{entry_id}", web::delete().to(handlers::finance::delete_labour))
+                    .route("/{id}/sales", web::get().to(handlers::finance::list_sales))
+                    .route("/{id}/sales", web::post().to(handlers::finance::create_sale))
+                    .route("/{id}/sales/{sale_id}", web::delete().to(handlers::finance::delete_sale))
+                    .route("/{id}/reports/pnl", web::get().to(handlers::finance::season_pnl)),
+            )
+            .route("/api/crop-templates", web::get().to(handlers::crop_cycles::list_templates))
+            .route("/api/units", web::get().to(handlers::units::list_units))
+            .route("/api/currencies", web::get().to(handlers::currencies::list_currencies))
+            .route("/api/exchange-rates", web::get().to(handlers::currencies::current_rates))
+            .service(
+                web::scope("/api/cart")
+                    .route("", web::get().to(handlers::orders::get_cart))
+                    .route("", web::delete().to(handlers::orders::clear_cart))
+                    .route("/items", web::post().to(handlers::orders::add_cart_item))
+                    .route("/items/{product_id}", web::patch().to(handlers::orders::update_cart_item))
+                    .route("/items/{product_id}", web::delete().to(handlers::orders::remove_cart_item))
+                    .route("/checkout", web::post().to(handlers::orders::checkout)),
+            )
+            .service(
+                web::scope("/api/orders")
+                    .route("", web::get().to(handlers::orders::list_orders))
+                    .route("/received", web::get().to(handlers::orders::list_received_orders))
+                    .route("/{id}", web::get().to(handlers::orders::get_order))
+                    .route("/{id}/status", web::post().to(handlers::orders::change_order_status))
+                    .route("/{id}/events", web::get().to(handlers::orders::list_order_events)),
+            )
+            .service(
+                web::scope("/api/wallet")
+                    .route("", web::get().to(handlers::wallet::get_balances))
+                    .route("/entries", web::get().to(handlers::wallet::list_entries))
+                    .route("/bank-accounts", web::get().to(handlers::wallet::list_bank_accounts))
+                    .route("/bank-accounts", web::post().to(handlers::wallet::create_bank_account))
+                    .route("/bank-accounts/{id}", web::delete().to(handlers::wallet::remove_bank_account))
+                    .route("/payouts", web::get().to(handlers::wallet::list_payouts))
+                    .route("/payouts", web::post().to(handlers::wallet::request_payout)),
+            )
+            .service(
+                web::scope("/api/payments")
+                    .route("", web::get().to(handlers::payments::list_payments))
+                    .route("", web::post().to(handlers::payments::create_payment))
+                    .route("/webhook", web::post().to(handlers::payments::webhook))
+                    .route("/{id}", web::get().to(handlers::payments::get_payment))
+                    .route("/{id}/verify", web::post().to(handlers::payments::verify_payment)),
             )
             .service(
                 web::scope("/api/products")
+                .route("", web::get().to(handlers::products::list_products))
                 .route("" , web::post().to(handlers::products::add_products))
+                .route("/by-slug/{slug}", web::get().to(handlers::products::get_product_by_slug))
+                .route("/{id}", web::get().to(handlers::products::get_product))
+                .route("/{id}", web::patch().to(handlers::products::update_product))
+                .route("/{id}", web::delete().to(handlers::products::archive_product))
+                .route("/{id}/status", web::post().to(handlers::products::change_product_status))
             )
     })
     .bind(("0.0.0.0", port))? // Bind to all interfaces
@@ -0,0 +1,417 @@
+use actix_session::{Session, SessionExt};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{http::header, web, Error, FromRequest, HttpRequest};
+use futures_util::future::{ok, ready, LocalBoxFuture, Ready};
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{BuyerSession, FarmerSession, Permission, SessionAccount, StaffSession},
+    services::{
+        staff_service,
+        token_service::{verify_access_token, TokenConfig},
+    },
+};
+
+/// Session key holding the logged-in farmer.
+pub const FARMER_SESSION_KEY: &str = "farmer";
+/// Session key holding the logged-in buyer.
+pub const BUYER_SESSION_KEY: &str = "buyer";
+/// Session key holding the logged-in staff member.
+pub const STAFF_SESSION_KEY: &str = "staff";
+/// A session holds at most one of these.
+const ACCOUNT_KEYS: &[&str] = &[FARMER_SESSION_KEY, BUYER_SESSION_KEY, STAFF_SESSION_KEY];
+/// Public id of the login, used to list and revoke sessions without exposing the cookie key.
+pub const SESSION_ID_KEY: &str = "session_id";
+/// User agent of the device that logged in.
+pub const USER_AGENT_KEY: &str = "user_agent";
+
+/// Stores a freshly logged-in account under `key`, dropping any other
+/// account the session held.
+fn store_login<T: Serialize>(session: &Session, key: &str, account: &T, user_agent: Option<&str>) -> AppResult<()> {
+    session.renew();
+    let to_err = |e: actix_session::SessionInsertError| {
+        AppError::InternalError(format!("Failed to store session: {}", e))
+    };
+
+    for other in ACCOUNT_KEYS.iter().filter(|other| **other != key) {
+        session.remove(other);
+    }
+    session.insert(SESSION_ID_KEY, Uuid::new_v4()).map_err(to_err)?;
+    if let Some(user_agent) = user_agent {
+        session.insert(USER_AGENT_KEY, user_agent).map_err(to_err)?;
+    }
+    session.insert(key, account).map_err(to_err)
+}
+
+/// Stores the farmer in the session after a successful login.
+pub fn store_farmer_session(session: &Session, farmer: &FarmerSession, user_agent: Option<&str>) -> AppResult<()> {
+    store_login(session, FARMER_SESSION_KEY, farmer, user_agent)
+}
+
+/// Stores whichever kind of account logged in.
+pub fn store_account_session(session: &Session, account: &SessionAccount, user_agent: Option<&str>) -> AppResult<()> {
+    match account {
+        SessionAccount::Farmer(farmer) => store_farmer_session(session, farmer, user_agent),
+        SessionAccount::Buyer(buyer) => store_login(session, BUYER_SESSION_KEY, buyer, user_agent),
+        SessionAccount::Staff(staff) => store_login(session, STAFF_SESSION_KEY, staff, user_agent),
+    }
+}
+
+/// Switches the active farm of the farmer stored in the session.
+pub fn set_active_farm(session: &Session, farm_id: Option<Uuid>) -> AppResult<()> {
+    let mut farmer = farmer_from_session(session)?;
+    farmer.farm_id = farm_id;
+    session
+        .insert(FARMER_SESSION_KEY, farmer)
+        .map_err(|e| AppError::InternalError(format!("Failed to store session: {}", e)))
+}
+
+/// Public id of the current login, if the session has one.
+pub fn current_session_id(session: &Session) -> Option<Uuid> {
+    session.get::<Uuid>(SESSION_ID_KEY).ok().flatten()
+}
+
+/// Reads the account stored under `key`. An unreadable entry is discarded
+/// and treated like a missing one.
+fn session_value<T: DeserializeOwned>(session: &Session, key: &str) -> Option<T> {
+    match session.get::<T>(key) {
+        Ok(value) => value,
+        Err(e) => {
+            log::warn!("Discarding unreadable {} session: {}", key, e);
+            session.remove(key);
+            None
+        }
+    }
+}
+
+/// Reads the farmer out of the session. A missing or unreadable entry is
+/// treated as "not logged in".
+pub fn farmer_from_session(session: &Session) -> AppResult<FarmerSession> {
+    session_value(session, FARMER_SESSION_KEY).ok_or_else(|| AppError::Unauthorized("Not logged in".to_string()))
+}
+
+/// Reads the logged-in account out of the session, whatever its kind.
+pub fn account_from_session(session: &Session) -> AppResult<SessionAccount> {
+    if let Some(farmer) = session_value(session, FARMER_SESSION_KEY) {
+        return Ok(SessionAccount::Farmer(farmer));
+    }
+    if let Some(buyer) = session_value(session, BUYER_SESSION_KEY) {
+        return Ok(SessionAccount::Buyer(buyer));
+    }
+    if let Some(staff) = session_value(session, STAFF_SESSION_KEY) {
+        return Ok(SessionAccount::Staff(staff));
+    }
+    Err(AppError::Unauthorized("Not logged in".to_string()))
+}
+
+/// Whether the request authenticates with a bearer token rather than the
+/// cookie session.
+pub fn uses_bearer_token(req: &HttpRequest) -> bool {
+    req.headers().contains_key(header::AUTHORIZATION)
+}
+
+/// Resolves the caller from an `Authorization: Bearer` access token when one is
+/// sent, otherwise from the cookie session.
+pub fn authenticate_account(req: &HttpRequest) -> AppResult<SessionAccount> {
+    let Some(value) = req.headers().get(header::AUTHORIZATION) else {
+        return account_from_session(&req.get_session());
+    };
+
+    let token = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or_else(|| AppError::Unauthorized("Malformed Authorization header".to_string()))?;
+
+    let config = req
+        .app_data::<web::Data<TokenConfig>>()
+        .ok_or_else(|| AppError::InternalError("Token configuration missing".to_string()))?;
+
+    verify_access_token(config, token)
+}
+
+/// Like `authenticate_account`, but only farmers get through. Other
+/// accounts are forbidden rather than unauthorized: they are logged in, just
+/// not as a farmer.
+pub fn authenticate(req: &HttpRequest) -> AppResult<FarmerSession> {
+    match authenticate_account(req)? {
+        SessionAccount::Farmer(farmer) => Ok(farmer),
+        _ => Err(AppError::Forbidden("A farmer account is required".to_string())),
+    }
+}
+
+/// Re-reads a staff account from the database. The role in a cookie session
+/// or access token is only a snapshot, so a staff member who has since been
+/// deactivated or demoted must not keep acting on it. Farmers and buyers are
+/// returned as they are.
+async fn current_account(db: Option<web::Data<Database>>, account: SessionAccount) -> AppResult<SessionAccount> {
+    match account {
+        SessionAccount::Staff(staff) => {
+            let db = db.ok_or_else(|| AppError::InternalError("Database missing".to_string()))?;
+            Ok(SessionAccount::Staff(staff_service::staff_session(&db, staff.staff_id).await?))
+        }
+        account => Ok(account),
+    }
+}
+
+fn check_permission(account: &SessionAccount, permission: Permission) -> AppResult<()> {
+    if !account.can(permission) {
+        return Err(AppError::Forbidden(format!(
+            "Not allowed for the {} role",
+            account.role().as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// Resolves the caller and checks their role grants `permission`, using the
+/// staff member's current role rather than the one they logged in with.
+pub fn authorize(req: &HttpRequest, permission: Permission) -> LocalBoxFuture<'static, AppResult<SessionAccount>> {
+    let account = authenticate_account(req);
+    let db = req.app_data::<web::Data<Database>>().cloned();
+
+    Box::pin(async move {
+        let account = current_account(db, account?).await?;
+        check_permission(&account, permission)?;
+        Ok(account)
+    })
+}
+
+/// Extractor for handlers that require a logged-in farmer, via either a
+/// bearer token or the cookie session.
+#[derive(Debug)]
+pub struct AuthenticatedFarmer(pub FarmerSession);
+
+impl Deref for AuthenticatedFarmer {
+    type Target = FarmerSession;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedFarmer {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(authenticate(req).map(AuthenticatedFarmer))
+    }
+}
+
+/// Extractor for handlers open to any logged-in account, farmer or buyer.
+#[derive(Debug)]
+pub struct AuthenticatedAccount(pub SessionAccount);
+
+impl Deref for AuthenticatedAccount {
+    type Target = SessionAccount;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AuthenticatedAccount {
+    /// The buyer, for handlers that only make sense for buyer accounts.
+    pub fn buyer(&self) -> AppResult<&BuyerSession> {
+        match &self.0 {
+            SessionAccount::Buyer(buyer) => Ok(buyer),
+            _ => Err(AppError::Forbidden("A buyer account is required".to_string())),
+        }
+    }
+}
+
+impl FromRequest for AuthenticatedAccount {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(authenticate_account(req).map(AuthenticatedAccount))
+    }
+}
+
+/// Extractor for the buyer side of the marketplace (cart and orders). Buyer
+/// accounts and farmers alike can buy, staff can't; `buyer_id` is the account id.
+#[derive(Debug)]
+pub struct AuthenticatedBuyer {
+    pub buyer_id: Uuid,
+}
+
+impl FromRequest for AuthenticatedBuyer {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let authorized = authorize(req, Permission::PlaceOrders);
+        Box::pin(async move { authorized.await.map(|account| AuthenticatedBuyer { buyer_id: account.id() }) })
+    }
+}
+
+/// Extractor for handlers that require a logged-in, active staff member of
+/// any role, as currently stored. Scopes wrapped in `RequirePermission` have
+/// already checked the role.
+#[derive(Debug)]
+pub struct AuthenticatedStaff(pub StaffSession);
+
+impl Deref for AuthenticatedStaff {
+    type Target = StaffSession;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedStaff {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let account = authenticate_account(req);
+        let db = req.app_data::<web::Data<Database>>().cloned();
+
+        Box::pin(async move {
+            match current_account(db, account?).await? {
+                SessionAccount::Staff(staff) => Ok(AuthenticatedStaff(staff)),
+                _ => Err(AppError::Forbidden("A staff account is required".to_string())),
+            }
+        })
+    }
+}
+
+/// Extractor for read-only handlers under `/api/farms/{id}`. Resolves to the
+/// farm's owner: farmers read their own farms as before, extension officers
+/// and cooperative managers read the farms assigned to them, and admins read
+/// any farm.
+#[derive(Debug)]
+pub struct FarmReader {
+    pub farmer_id: Uuid,
+}
+
+impl FromRequest for FarmReader {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let account = authenticate_account(req);
+        let farm_id = req.match_info().get("id").and_then(|id| Uuid::parse_str(id).ok());
+        let db = req.app_data::<web::Data<Database>>().cloned();
+
+        Box::pin(async move {
+            let staff = match current_account(db.clone(), account?).await? {
+                SessionAccount::Farmer(farmer) => return Ok(FarmReader { farmer_id: farmer.farmer_id }),
+                SessionAccount::Staff(staff) => staff,
+                SessionAccount::Buyer(_) => {
+                    return Err(AppError::Forbidden("A farmer account is required".to_string()))
+                }
+            };
+            let farm_id = farm_id.ok_or_else(|| AppError::ValidationError("Invalid farm id".to_string()))?;
+            let db = db.ok_or_else(|| AppError::InternalError("Database missing".to_string()))?;
+
+            let farmer_id = staff_service::readable_farm_owner(&db, &staff, farm_id).await?;
+            Ok(FarmReader { farmer_id })
+        })
+    }
+}
+
+/// What a guarded scope requires of the caller.
+#[derive(Debug, Clone, Copy)]
+enum Requirement {
+    Farmer,
+    Permission(Permission),
+}
+
+impl Requirement {
+    fn check(self, req: &HttpRequest) -> LocalBoxFuture<'static, AppResult<()>> {
+        match self {
+            Requirement::Farmer => Box::pin(ready(authenticate(req).map(|_| ()))),
+            Requirement::Permission(permission) => {
+                let authorized = authorize(req, permission);
+                Box::pin(async move { authorized.await.map(|_| ()) })
+            }
+        }
+    }
+}
+
+/// Rejects every request in the wrapped scope or resource that carries neither
+/// a valid bearer token nor a farmer session. Must be registered inside
+/// `SessionMiddleware`.
+pub struct RequireFarmer;
+
+impl<S, B> Transform<S, ServiceRequest> for RequireFarmer
+where
+    S: actix_web::dev::Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequireMiddleware { service: Rc::new(service), requirement: Requirement::Farmer })
+    }
+}
+
+/// Rejects every request in the wrapped scope or resource whose account's
+/// role lacks the permission: unauthorized when not logged in, forbidden
+/// otherwise. Must be registered inside `SessionMiddleware`.
+pub struct RequirePermission(pub Permission);
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: actix_web::dev::Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequireMiddleware { service: Rc::new(service), requirement: Requirement::Permission(self.0) })
+    }
+}
+
+pub struct RequireMiddleware<S> {
+    service: Rc<S>,
+    requirement: Requirement,
+}
+
+impl<S, B> actix_web::dev::Service<ServiceRequest> for RequireMiddleware<S>
+where
+    S: actix_web::dev::Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let check = self.requirement.check(req.request());
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            check.await?;
+            service.call(req).await
+        })
+    }
+}
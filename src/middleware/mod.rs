@@ -1,2 +1 @@
-pub mod validation;
 pub mod auth;
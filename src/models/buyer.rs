@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Buyer {
+    pub id: Uuid,
+    pub phone_number: String,
+    pub email: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub buyer_type: String,
+    pub business_name: Option<String>,
+    pub verification_status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `Buyer`.
+pub const BUYER_COLUMNS: &str = r#"
+    id, phone_number, email, first_name, last_name, buyer_type,
+    business_name, verification_status, created_at, updated_at
+"#;
+
+pub const BUYER_TYPES: &[&str] = &["individual", "restaurant", "aggregator"];
+
+/// Registers a buyer and sends a registration code to the phone.
+/// `buyer_type` defaults to `individual`.
+#[derive(Debug, Deserialize)]
+pub struct CreateBuyerRequest {
+    pub phone_number: String,
+    pub email: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub buyer_type: Option<String>,
+    pub business_name: Option<String>,
+}
+
+/// The phone number can't be changed, as it is what the buyer logs in with.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateBuyerRequest {
+    pub email: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub buyer_type: Option<String>,
+    pub business_name: Option<String>,
+}
+
+/// The buyer stored in the cookie session or carried in an access token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuyerSession {
+    pub buyer_id: Uuid,
+    pub name: String,
+}
+
+/// A place a buyer has orders delivered to.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BuyerAddress {
+    pub id: Uuid,
+    pub buyer_id: Uuid,
+    pub label: Option<String>,
+    pub recipient_name: Option<String>,
+    pub phone_number: Option<String>,
+    pub address_text: String,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub delivery_instructions: Option<String>,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `BuyerAddress`.
+pub const BUYER_ADDRESS_COLUMNS: &str = r#"
+    id, buyer_id, label, recipient_name, phone_number, address_text, city, state,
+    latitude, longitude, delivery_instructions, is_default, created_at, updated_at
+"#;
+
+/// A buyer's first address becomes their default regardless of `is_default`.
+#[derive(Debug, Deserialize)]
+pub struct CreateBuyerAddressRequest {
+    pub label: Option<String>,
+    pub recipient_name: Option<String>,
+    pub phone_number: Option<String>,
+    pub address_text: String,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub delivery_instructions: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Setting `is_default` moves the default from the buyer's other address;
+/// it can't be unset directly.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateBuyerAddressRequest {
+    pub label: Option<String>,
+    pub recipient_name: Option<String>,
+    pub phone_number: Option<String>,
+    pub address_text: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub delivery_instructions: Option<String>,
+    pub is_default: Option<bool>,
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use super::FarmActivity;
+
+/// A reference schedule for a crop, used to plan a new crop cycle.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CropTemplate {
+    pub id: Uuid,
+    pub crop_name: String,
+    /// Typical days from planting to harvest.
+    pub growing_days: i32,
+    pub tasks: Json<Vec<TemplateTask>>,
+}
+
+/// One step of a crop template. `offset_days` counts from the planting date
+/// and may be negative for work done before planting. Harvesting tasks are
+/// scheduled on the cycle's expected harvest date instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateTask {
+    pub activity_type: String,
+    pub description: String,
+    pub offset_days: i32,
+}
+
+/// One planting of a crop on a farm, grouping the season's activities.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CropCycle {
+    pub id: Uuid,
+    pub farmer_id: Uuid,
+    pub farm_id: Uuid,
+    pub crop_name: String,
+    pub field_plot: Option<String>,
+    pub planting_date: NaiveDate,
+    pub expected_harvest_date: Option<NaiveDate>,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `CropCycle`.
+pub const CROP_CYCLE_COLUMNS: &str = r#"
+    id, farmer_id, farm_id, crop_name, field_plot,
+    planting_date, expected_harvest_date, status, notes,
+    created_at, updated_at
+"#;
+
+/// Abandoning a cycle cancels its outstanding planned tasks.
+pub const CROP_CYCLE_STATUSES: &[&str] = &["active", "completed", "abandoned"];
+
+/// `expected_harvest_date` defaults to the planting date plus the crop
+/// template's growing days.
+#[derive(Debug, Deserialize)]
+pub struct CreateCropCycleRequest {
+    pub crop_name: String,
+    pub field_plot: Option<String>,
+    pub planting_date: NaiveDate,
+    pub expected_harvest_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    /// Whether to create planned activities from the crop's template.
+    /// Defaults to doing so when a template exists; `true` requires one.
+    pub generate_schedule: Option<bool>,
+}
+
+/// Partial update for a crop cycle. Moving the planting or harvest date
+/// moves the cycle's still-planned tasks with it.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateCropCycleRequest {
+    pub crop_name: Option<String>,
+    pub field_plot: Option<String>,
+    pub planting_date: Option<NaiveDate>,
+    pub expected_harvest_date: Option<NaiveDate>,
+    pub status: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Query string for `GET /api/farms/{farm_id}/cycles`.
+#[derive(Debug, Default, Deserialize)]
+pub struct CropCycleQuery {
+    pub status: Option<String>,
+    pub crop: Option<String>,
+}
+
+/// Task counts for a crop cycle. Overdue tasks are planned or in progress
+/// and dated before today; upcoming ones are dated today or later.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CycleProgress {
+    pub total_tasks: i64,
+    pub completed_tasks: i64,
+    pub overdue_tasks: i64,
+    pub upcoming_tasks: i64,
+    pub cancelled_tasks: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CropCycleOverview {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub cycle: CropCycle,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub progress: CycleProgress,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledTask {
+    #[serde(flatten)]
+    pub activity: FarmActivity,
+    pub overdue: bool,
+}
+
+/// Planned-versus-actual view of a crop cycle: every task in date order.
+#[derive(Debug, Serialize)]
+pub struct CropCycleSchedule {
+    #[serde(flatten)]
+    pub overview: CropCycleOverview,
+    pub tasks: Vec<ScheduledTask>,
+}
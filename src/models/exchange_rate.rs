@@ -0,0 +1,78 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How many major units of `quote_currency` one of `base_currency` buys,
+/// from `effective_date` until a later rate for the pair.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub id: Uuid,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: f64,
+    pub effective_date: NaiveDate,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column list matching `ExchangeRate`.
+pub const EXCHANGE_RATE_COLUMNS: &str = r#"
+    id, base_currency, quote_currency, rate::FLOAT8 AS rate, effective_date, created_by, created_at
+"#;
+
+/// Payload for `POST /api/admin/exchange-rates`. Replaces any rate already
+/// set for the pair on that day; `effective_date` defaults to today.
+#[derive(Debug, Deserialize)]
+pub struct CreateExchangeRateRequest {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: f64,
+    pub effective_date: Option<NaiveDate>,
+}
+
+/// Query string for `GET /api/admin/exchange-rates`. Either currency
+/// matches rates quoted in both directions.
+#[derive(Debug, Deserialize)]
+pub struct ExchangeRateQuery {
+    pub currency: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Query string for `GET /api/exchange-rates`: the rates in force on
+/// `as_of`, today by default.
+#[derive(Debug, Deserialize)]
+pub struct CurrentRatesQuery {
+    pub as_of: Option<NaiveDate>,
+}
+
+/// Query string for endpoints that can show prices in the viewer's
+/// currency. Display only: orders settle in the product's currency.
+#[derive(Debug, Default, Deserialize)]
+pub struct DisplayCurrencyQuery {
+    pub currency: Option<String>,
+}
+
+/// A listing's price restated in the viewer's currency.
+#[derive(Debug, Serialize)]
+pub struct ConvertedPrice {
+    pub currency_code: String,
+    /// Major units of `currency_code` per major unit of the product's
+    /// currency.
+    pub exchange_rate: f64,
+    pub price_cents: i64,
+    /// The unit-normalized price, when the search asked for a unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_price_cents: Option<i64>,
+}
+
+/// A cart line restated in the viewer's currency.
+#[derive(Debug, Serialize)]
+pub struct ConvertedLine {
+    pub currency_code: String,
+    pub exchange_rate: f64,
+    pub unit_price_cents: i64,
+    pub line_total_cents: i64,
+}
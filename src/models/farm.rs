@@ -1,8 +1,146 @@
+use chrono::{DateTime, Utc};
 use serde::{Serialize , Deserialize};
+use sqlx::types::Json;
+use uuid::Uuid;
 
+/// A farm owned by a farmer. A farmer may own several.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Farm {
+    pub id: Uuid,
+    pub farmer_id: Uuid,
+    pub farm_name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub address_text: Option<String>,
+    pub farm_size_hectares: Option<f64>,
+    /// Area of the mapped boundary, if one has been uploaded.
+    pub boundary_area_hectares: Option<f64>,
+    pub farm_type: String,
+    pub primary_crops: Json<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
 
+/// Column list matching `Farm`, for `SELECT`/`RETURNING` clauses.
+pub const FARM_COLUMNS: &str = r#"
+    id, farmer_id, farm_name,
+    ST_Y(location) AS latitude, ST_X(location) AS longitude,
+    address_text, farm_size_hectares::FLOAT8 AS farm_size_hectares,
+    boundary_area_hectares::FLOAT8 AS boundary_area_hectares,
+    farm_type, primary_crops,
+    created_at, updated_at
+"#;
+
+/// Partial update for a farm. Absent fields are left unchanged; `latitude`
+/// and `longitude` must be given together.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateFarmRequest {
+    pub farm_name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub address_text: Option<String>,
+    pub farm_size_hectares: Option<f64>,
+    pub farm_type: Option<String>,
+    pub primary_crops: Option<Vec<String>>,
+}
+
+/// Body for `PUT /api/farms/{id}/boundary`: a GeoJSON Polygon or
+/// MultiPolygon, bare or wrapped in a Feature.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FarmBoundaryRequest {
+    pub boundary: serde_json::Value,
+}
+
+/// A farm's mapped boundary compared with its self-reported size.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FarmBoundary {
+    pub farm_id: Uuid,
+    /// GeoJSON MultiPolygon.
+    pub boundary: Option<Json<serde_json::Value>>,
+    pub area_hectares: Option<f64>,
+    pub reported_size_hectares: Option<f64>,
+    /// Set when the mapped area and the reported size differ by more than
+    /// the configured tolerance.
+    #[sqlx(default)]
+    pub size_mismatch: bool,
+}
+
+/// A named plot within a farm. The name is what farm activities record in
+/// `field_plot`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FarmPlot {
+    pub id: Uuid,
+    pub farm_id: Uuid,
+    pub name: String,
+    /// GeoJSON MultiPolygon, if the plot has been mapped.
+    pub boundary: Option<Json<serde_json::Value>>,
+    pub area_hectares: Option<f64>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `FarmPlot`.
+pub const FARM_PLOT_COLUMNS: &str = r#"
+    id, farm_id, name,
+    ST_AsGeoJSON(boundary)::JSONB AS boundary,
+    area_hectares::FLOAT8 AS area_hectares,
+    notes, created_at, updated_at
+"#;
+
+/// `area_hectares` is only used for plots without a boundary; mapped plots
+/// get their area from the geometry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateFarmPlotRequest {
+    pub name: String,
+    pub boundary: Option<serde_json::Value>,
+    pub area_hectares: Option<f64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateFarmPlotRequest {
+    pub name: Option<String>,
+    pub boundary: Option<serde_json::Value>,
+    pub area_hectares: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// The caller's farms and which one is active in their session.
+#[derive(Debug, Serialize)]
+pub struct FarmerFarms {
+    pub active_farm_id: Option<Uuid>,
+    pub farms: Vec<Farm>,
+}
+
+/// Query string for `GET /api/farms`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FarmQuery {
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub radius_km: Option<f64>,
+    pub farm_type: Option<String>,
+    /// Matches farms listing this crop in `primary_crops`.
+    pub crop: Option<String>,
+    pub farmer_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// A farm in public search results. The location is snapped to a grid of
+/// about a kilometre and the owner and address are left out, so a listing
+/// can't lead a stranger to the farmer's door.
+#[derive(Debug, Serialize)]
+pub struct FarmListing {
+    pub id: Uuid,
+    pub farm_name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub farm_size_hectares: Option<f64>,
+    pub boundary_area_hectares: Option<f64>,
+    pub farm_type: String,
+    pub primary_crops: Json<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+    /// Whole kilometres from the searcher, when the search gave a location.
+    pub distance_km: Option<f64>,
+}
 
-#[derive(Debug, Serialize, Deserialize , sqlx::FromRow)]
-pub struct FarmResponse {
-    pub id : String
-}
\ No newline at end of file
@@ -5,11 +5,14 @@ use sqlx::types::Json;
 use uuid::Uuid;
 use chrono::{NaiveDate , DateTime , Utc};
 
+use super::product::nullable;
+
 #[derive(Debug , Serialize , Deserialize , sqlx::FromRow)]
 pub struct FarmActivity {
     pub id : Uuid , 
     pub farmer_id : Uuid , 
     pub farm_id : Option<Uuid>,
+    pub crop_cycle_id: Option<Uuid>,
     pub activity_type : String , 
     pub description : String , 
     pub activity_date : NaiveDate , 
@@ -28,14 +31,22 @@ pub struct FarmActivity {
 #[derive (Debug , Serialize , Deserialize , Clone)]
 pub struct InputUsed {
     pub item : String , 
-    pub Quantity : f64 , 
+    #[serde(rename = "Quantity")]
+    pub quantity : f64 , 
     pub unit : String
 }
 
+/// `farmer_id` comes from the authenticated farmer and `farm_id` from the
+/// URL, never from the body.
 #[derive(Debug , Deserialize)]
 pub struct CreateFarmActivityRequest{
+    #[serde(skip_deserializing)]
     pub farmer_id : Uuid , 
+    #[serde(skip_deserializing)]
     pub farm_id : Option<Uuid>, 
+    /// Crop cycle on the same farm. Its crop and plot fill in `crop_name`
+    /// and `field_plot` when those are left out.
+    pub crop_cycle_id: Option<Uuid>,
     pub activity_type : String , 
     pub description : String , 
     pub activity_date : NaiveDate , 
@@ -50,17 +61,204 @@ pub struct CreateFarmActivityRequest{
 
 }
 
+/// Partial update for an activity. Absent fields are left unchanged; the
+/// optional ones are cleared by sending `null`.
 #[derive(Debug, Deserialize)]
 pub struct UpdateFarmActivityRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    pub crop_cycle_id: Option<Option<Uuid>>,
     pub activity_type: Option<String>,
     pub description: Option<String>,
     pub activity_date: Option<NaiveDate>,
     pub status: Option<String>,
-    pub crop_name: Option<String>,
-    pub field_plot: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub crop_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub field_plot: Option<Option<String>>,
     pub inputs_used: Option<Vec<InputUsed>>,
-    pub quantity_measured: Option<f64>,
-    pub unit_measured: Option<String>,
-    pub expected_harvest_date: Option<NaiveDate>,
-    pub notes: Option<String>,
-}
\ No newline at end of file
+    #[serde(default, deserialize_with = "nullable")]
+    pub quantity_measured: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub unit_measured: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub expected_harvest_date: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub notes: Option<Option<String>>,
+}
+
+impl UpdateFarmActivityRequest {
+    /// Merges the update over `current`, producing the full activity to
+    /// validate and persist. An activity moved to another crop cycle takes
+    /// that cycle's crop, plot and expected harvest unless the update gives
+    /// its own; one detached from its cycle keeps them.
+    pub fn apply_to(self, current: &FarmActivity) -> CreateFarmActivityRequest {
+        let moved = matches!(self.crop_cycle_id, Some(Some(cycle_id)) if current.crop_cycle_id != Some(cycle_id));
+        let kept = |value: Option<String>| value.filter(|_| !moved);
+        CreateFarmActivityRequest {
+            farmer_id: current.farmer_id,
+            farm_id: current.farm_id,
+            crop_cycle_id: self.crop_cycle_id.unwrap_or(current.crop_cycle_id),
+            activity_type: self.activity_type.unwrap_or_else(|| current.activity_type.clone()),
+            description: self.description.unwrap_or_else(|| current.description.clone()),
+            activity_date: self.activity_date.unwrap_or(current.activity_date),
+            status: Some(self.status.unwrap_or_else(|| current.status.clone())),
+            crop_name: self.crop_name.unwrap_or_else(|| kept(current.crop_name.clone())),
+            field_plot: self.field_plot.unwrap_or_else(|| kept(current.field_plot.clone())),
+            inputs_used: self
+                .inputs_used
+                .or_else(|| current.inputs_used.as_ref().map(|inputs| inputs.0.clone())),
+            quantity_measured: self.quantity_measured.unwrap_or(current.quantity_measured),
+            unit_measured: self.unit_measured.unwrap_or_else(|| current.unit_measured.clone()),
+            expected_harvest_date: self
+                .expected_harvest_date
+                .unwrap_or(current.expected_harvest_date.filter(|_| !moved)),
+            notes: self.notes.unwrap_or_else(|| current.notes.clone()),
+        }
+    }
+}
+
+pub const ACTIVITY_TYPES: &[&str] = &[
+    "land_preparation",
+    "planting",
+    "fertilizing",
+    "irrigation",
+    "weeding",
+    "pest_control",
+    "harvesting",
+    "post_harvest",
+    "other",
+];
+
+/// Activities dated in the future default to "planned", past ones to "completed".
+pub const ACTIVITY_STATUSES: &[&str] = &["planned", "in_progress", "completed", "cancelled"];
+
+/// Column list matching `FarmActivity`.
+pub const ACTIVITY_COLUMNS: &str = r#"
+    id, farmer_id, farm_id, crop_cycle_id,
+    activity_type, description, activity_date, status,
+    crop_name, field_plot, inputs_used,
+    quantity_measured, unit_measured,
+    expected_harvest_date, notes,
+    created_at, updated_at
+"#;
+
+/// Query string for `GET /api/farms/{farm_id}/activities`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ActivityQuery {
+    /// Inclusive `activity_date` range.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Case-insensitive match on `crop_name`.
+    pub crop: Option<String>,
+    pub activity_type: Option<String>,
+    pub status: Option<String>,
+    pub field_plot: Option<String>,
+    pub crop_cycle_id: Option<Uuid>,
+    /// Only planned or in-progress activities dated before today.
+    pub overdue: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn current() -> FarmActivity {
+        let now = Utc::now();
+        FarmActivity {
+            id: Uuid::new_v4(),
+            farmer_id: Uuid::new_v4(),
+            farm_id: Some(Uuid::new_v4()),
+            crop_cycle_id: Some(Uuid::new_v4()),
+            activity_type: "harvesting".to_string(),
+            description: "Harvested the east plot".to_string(),
+            activity_date: NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            status: "completed".to_string(),
+            crop_name: Some("Maize".to_string()),
+            field_plot: Some("East".to_string()),
+            inputs_used: None,
+            quantity_measured: Some(12.5),
+            unit_measured: Some("bag".to_string()),
+            expected_harvest_date: NaiveDate::from_ymd_opt(2026, 3, 1),
+            notes: Some("Dry".to_string()),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn update(body: serde_json::Value) -> CreateFarmActivityRequest {
+        serde_json::from_value::<UpdateFarmActivityRequest>(body).unwrap().apply_to(&current())
+    }
+
+    #[test]
+    fn absent_fields_are_kept() {
+        let current = current();
+        let merged = serde_json::from_value::<UpdateFarmActivityRequest>(json!({})).unwrap().apply_to(&current);
+        assert_eq!(merged.crop_cycle_id, current.crop_cycle_id);
+        assert_eq!(merged.crop_name, current.crop_name);
+        assert_eq!(merged.field_plot, current.field_plot);
+        assert_eq!(merged.quantity_measured, current.quantity_measured);
+        assert_eq!(merged.unit_measured, current.unit_measured);
+        assert_eq!(merged.expected_harvest_date, current.expected_harvest_date);
+        assert_eq!(merged.notes, current.notes);
+    }
+
+    #[test]
+    fn null_clears_optional_fields() {
+        let merged = update(json!({
+            "crop_cycle_id": null,
+            "crop_name": null,
+            "field_plot": null,
+            "quantity_measured": null,
+            "unit_measured": null,
+            "expected_harvest_date": null,
+            "notes": null
+        }));
+        assert_eq!(merged.crop_cycle_id, None);
+        assert_eq!(merged.crop_name, None);
+        assert_eq!(merged.field_plot, None);
+        assert_eq!(merged.quantity_measured, None);
+        assert_eq!(merged.unit_measured, None);
+        assert_eq!(merged.expected_harvest_date, None);
+        assert_eq!(merged.notes, None);
+    }
+
+    #[test]
+    fn moving_to_another_cycle_drops_the_old_cycles_crop_and_plot() {
+        let merged = update(json!({ "crop_cycle_id": Uuid::new_v4() }));
+        assert_eq!(merged.crop_name, None);
+        assert_eq!(merged.field_plot, None);
+        assert_eq!(merged.expected_harvest_date, None);
+
+        let merged = update(json!({ "crop_cycle_id": Uuid::new_v4(), "crop_name": "Cassava" }));
+        assert_eq!(merged.crop_name.as_deref(), Some("Cassava"));
+        assert_eq!(merged.field_plot, None);
+    }
+
+    #[test]
+    fn staying_on_or_leaving_the_cycle_keeps_the_crop_and_plot() {
+        let current = current();
+        let same = json!({ "crop_cycle_id": current.crop_cycle_id });
+        let merged = serde_json::from_value::<UpdateFarmActivityRequest>(same).unwrap().apply_to(&current);
+        assert_eq!(merged.crop_name, current.crop_name);
+        assert_eq!(merged.field_plot, current.field_plot);
+
+        let merged = update(json!({ "crop_cycle_id": null }));
+        assert_eq!(merged.crop_cycle_id, None);
+        assert_eq!(merged.crop_name.as_deref(), Some("Maize"));
+        assert_eq!(merged.field_plot.as_deref(), Some("East"));
+        assert!(merged.expected_harvest_date.is_some());
+    }
+
+    #[test]
+    fn values_replace_the_current_ones() {
+        let merged = update(json!({ "crop_name": "Cassava", "quantity_measured": 3.0, "notes": "Wet" }));
+        assert_eq!(merged.crop_name.as_deref(), Some("Cassava"));
+        assert_eq!(merged.quantity_measured, Some(3.0));
+        assert_eq!(merged.notes.as_deref(), Some("Wet"));
+        assert_eq!(merged.field_plot.as_deref(), Some("East"));
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Paid work on a farm, in the smallest currency unit.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LabourEntry {
+    pub id: Uuid,
+    pub farm_id: Uuid,
+    pub crop_cycle_id: Option<Uuid>,
+    pub activity_id: Option<Uuid>,
+    pub crop_name: Option<String>,
+    pub work_date: NaiveDate,
+    pub description: String,
+    pub workers: i32,
+    pub hours: Option<f64>,
+    pub cost_cents: i64,
+    pub currency_code: String,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column list matching `LabourEntry`.
+pub const LABOUR_ENTRY_COLUMNS: &str = r#"
+    id, farm_id, crop_cycle_id, activity_id, crop_name, work_date,
+    description, workers, hours, cost_cents, currency_code, notes, created_at
+"#;
+
+/// `crop_name` defaults to the crop of the linked activity or crop cycle.
+#[derive(Debug, Deserialize)]
+pub struct CreateLabourEntryRequest {
+    pub crop_cycle_id: Option<Uuid>,
+    pub activity_id: Option<Uuid>,
+    pub crop_name: Option<String>,
+    pub work_date: NaiveDate,
+    pub description: String,
+    pub workers: Option<i32>,
+    pub hours: Option<f64>,
+    pub cost_cents: i64,
+    pub currency_code: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// A sale of produce from a farm.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProductSale {
+    pub id: Uuid,
+    pub farm_id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub crop_cycle_id: Option<Uuid>,
+    pub crop_name: Option<String>,
+    pub quantity: f64,
+    pub unit: String,
+    pub amount_cents: i64,
+    pub currency_code: String,
+    pub sold_on: NaiveDate,
+    pub buyer_name: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column list matching `ProductSale`.
+pub const PRODUCT_SALE_COLUMNS: &str = r#"
+    id, farm_id, product_id, crop_cycle_id, crop_name, quantity, unit,
+    amount_cents, currency_code, sold_on, buyer_name, notes, created_at
+"#;
+
+/// `unit` and `currency_code` default to the product's; `crop_name` to the
+/// crop cycle's, then the product name.
+#[derive(Debug, Deserialize)]
+pub struct CreateProductSaleRequest {
+    pub product_id: Option<Uuid>,
+    pub crop_cycle_id: Option<Uuid>,
+    pub crop_name: Option<String>,
+    pub quantity: f64,
+    pub unit: Option<String>,
+    pub amount_cents: i64,
+    pub currency_code: Option<String>,
+    pub sold_on: NaiveDate,
+    pub buyer_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Query string for listing labour entries and sales.
+#[derive(Debug, Default, Deserialize)]
+pub struct FinanceQuery {
+    /// Inclusive date range.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub crop: Option<String>,
+}
+
+/// Query string for `GET /api/farms/{farm_id}/reports/pnl`. The season
+/// defaults to the current calendar year. Only amounts in `currency_code`
+/// (default NGN) are counted.
+#[derive(Debug, Default, Deserialize)]
+pub struct PnlQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub currency_code: Option<String>,
+    /// Unit to report yields in. Yields in units that don't convert to it
+    /// are listed in their own unit.
+    pub unit: Option<String>,
+}
+
+/// Harvested quantity of a crop in one unit.
+#[derive(Debug, Serialize)]
+pub struct YieldTotal {
+    pub quantity: f64,
+    pub unit: String,
+}
+
+/// Costs, revenue and margin for a farm or one of its crops. Amounts are in
+/// the smallest currency unit.
+#[derive(Debug, Default, Serialize)]
+pub struct PnlLine {
+    pub input_cost_cents: i64,
+    pub labour_cost_cents: i64,
+    pub total_cost_cents: i64,
+    pub revenue_cents: i64,
+    pub margin_cents: i64,
+    pub area_hectares: Option<f64>,
+    /// Missing when the area is unknown.
+    pub margin_per_hectare_cents: Option<i64>,
+}
+
+/// A crop's share of the season. `crop_name` is null for costs and sales not
+/// tied to any crop. Its area is that of the plots its crop cycles used.
+#[derive(Debug, Serialize)]
+pub struct CropPnl {
+    pub crop_name: Option<String>,
+    #[serde(flatten)]
+    pub pnl: PnlLine,
+    pub yields: Vec<YieldTotal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeasonPnl {
+    pub farm_id: Uuid,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub currency_code: String,
+    /// Farm totals, over the mapped boundary area or else the reported size.
+    pub totals: PnlLine,
+    pub crops: Vec<CropPnl>,
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An input (seed, fertilizer, agrochemical...) stocked on a farm.
+/// Activities that list it in `inputs_used` draw down its stock.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FarmInput {
+    pub id: Uuid,
+    pub farm_id: Uuid,
+    pub name: String,
+    pub category: String,
+    pub unit: String,
+    pub currency_code: String,
+    pub quantity_on_hand: f64,
+    /// What the stock on hand cost, in the smallest currency unit.
+    pub stock_value_cents: i64,
+    pub reorder_level: Option<f64>,
+    /// Stock is at or below `reorder_level`.
+    pub low_stock: bool,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `FarmInput`.
+pub const FARM_INPUT_COLUMNS: &str = r#"
+    id, farm_id, name, category, unit, currency_code,
+    quantity_on_hand, stock_value_cents, reorder_level,
+    COALESCE(quantity_on_hand <= reorder_level, FALSE) AS low_stock,
+    notes, created_at, updated_at
+"#;
+
+pub const INPUT_CATEGORIES: &[&str] = &["seed", "fertilizer", "agrochemical", "other"];
+
+/// Stock starts at zero and is added by recording purchases.
+#[derive(Debug, Deserialize)]
+pub struct CreateFarmInputRequest {
+    pub name: String,
+    pub category: Option<String>,
+    pub unit: String,
+    pub currency_code: Option<String>,
+    pub reorder_level: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// Unit and currency are fixed once an input is created, as its stock and
+/// ledger are recorded in them.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateFarmInputRequest {
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub reorder_level: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// Body for `POST /api/farms/{farm_id}/inputs/{input_id}/purchases`.
+/// `quantity` is in the input's unit; `purchased_on` defaults to today.
+#[derive(Debug, Deserialize)]
+pub struct RecordPurchaseRequest {
+    pub quantity: f64,
+    pub cost_cents: i64,
+    pub purchased_on: Option<NaiveDate>,
+    pub supplier: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Query string for `GET /api/farms/{farm_id}/inputs`.
+#[derive(Debug, Default, Deserialize)]
+pub struct FarmInputQuery {
+    pub category: Option<String>,
+    pub low_stock: Option<bool>,
+}
+
+/// A stock movement. Usage rows carry the activity that consumed the
+/// input and are costed at the average price of the stock at the time.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InputTransaction {
+    pub id: Uuid,
+    pub input_id: Uuid,
+    pub input_name: String,
+    pub unit: String,
+    pub activity_id: Option<Uuid>,
+    pub kind: String,
+    pub quantity: f64,
+    pub cost_cents: i64,
+    pub currency_code: String,
+    pub occurred_on: NaiveDate,
+    pub supplier: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column list matching `InputTransaction`, for queries over
+/// `input_transactions t JOIN farm_inputs i`.
+pub const INPUT_TRANSACTION_COLUMNS: &str = r#"
+    t.id, t.input_id, i.name AS input_name, i.unit, t.activity_id,
+    t.kind, t.quantity, t.cost_cents, i.currency_code,
+    t.occurred_on, t.supplier, t.notes, t.created_at
+"#;
+
+pub const INPUT_TRANSACTION_KINDS: &[&str] = &["purchase", "usage"];
+
+/// Query string for `GET /api/farms/{farm_id}/inputs/ledger`.
+#[derive(Debug, Default, Deserialize)]
+pub struct InputLedgerQuery {
+    pub input_id: Option<Uuid>,
+    pub kind: Option<String>,
+    /// Inclusive `occurred_on` range.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use super::OtpPurpose;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Farmer {
     pub id: Uuid,
@@ -41,6 +43,23 @@ pub struct CreateFarmRequest {
 pub struct VerifyPhoneRequest {
     pub phone_number: String,
     pub otp_code: String,
+    /// When set, only a code issued for this purpose is accepted.
+    #[serde(default)]
+    pub purpose: Option<OtpPurpose>,
+    /// Mobile clients set this to receive bearer tokens instead of a cookie session.
+    #[serde(default)]
+    pub issue_tokens: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LogoutRequest {
+    /// Bearer-token clients pass their refresh token so it is revoked too.
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -69,9 +88,10 @@ pub struct LoginResponse{
    
 }
 
-#[derive(Debug , Serialize , Deserialize , sqlx::FromRow)] 
+#[derive(Debug , Clone , Serialize , Deserialize , sqlx::FromRow)] 
 pub struct FarmerSession{
     pub farmer_id: Uuid,
-    pub farm_id: String,
+    /// The farm the farmer is currently working on, if they have any.
+    pub farm_id: Option<Uuid>,
     pub name: String,
 }
\ No newline at end of file
@@ -0,0 +1,222 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An account in the escrow ledger. Escrow, farmer payable and payouts in
+/// transit are held per farmer and currency; the others per currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerAccountKind {
+    /// Money collected through the payment gateway and not yet paid out.
+    Gateway,
+    /// Buyer payments held until delivery is confirmed.
+    Escrow,
+    /// The farmer's wallet: released funds they can withdraw.
+    FarmerPayable,
+    /// Payouts requested but not yet sent.
+    PayoutsInTransit,
+    /// The platform's cut of released orders.
+    PlatformFees,
+}
+
+impl LedgerAccountKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerAccountKind::Gateway => "gateway",
+            LedgerAccountKind::Escrow => "escrow",
+            LedgerAccountKind::FarmerPayable => "farmer_payable",
+            LedgerAccountKind::PayoutsInTransit => "payouts_in_transit",
+            LedgerAccountKind::PlatformFees => "platform_fees",
+        }
+    }
+
+    pub fn is_farmer_account(&self) -> bool {
+        matches!(
+            self,
+            LedgerAccountKind::Escrow | LedgerAccountKind::FarmerPayable | LedgerAccountKind::PayoutsInTransit
+        )
+    }
+}
+
+/// What a ledger transaction records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerTransactionKind {
+    /// A buyer's payment for an order, into escrow.
+    Payment,
+    /// Escrow going back to the buyer.
+    Refund,
+    /// Reverses a refund the gateway turned down.
+    RefundFailed,
+    /// Escrow moving to the farmer's wallet, less the platform fee.
+    Release,
+    /// Wallet funds set aside for a payout request.
+    Payout,
+    /// A payout sent to the farmer's bank.
+    PayoutPaid,
+    /// Returns a failed payout to the wallet.
+    PayoutFailed,
+}
+
+impl LedgerTransactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerTransactionKind::Payment => "payment",
+            LedgerTransactionKind::Refund => "refund",
+            LedgerTransactionKind::RefundFailed => "refund_failed",
+            LedgerTransactionKind::Release => "release",
+            LedgerTransactionKind::Payout => "payout",
+            LedgerTransactionKind::PayoutPaid => "payout_paid",
+            LedgerTransactionKind::PayoutFailed => "payout_failed",
+        }
+    }
+}
+
+/// A farmer's money in one currency, in its minor unit.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WalletBalance {
+    pub currency_code: String,
+    /// Paid for orders that haven't been confirmed delivered yet.
+    pub held_cents: i64,
+    /// Released and free to withdraw.
+    pub available_cents: i64,
+    /// Requested payouts not yet sent.
+    pub in_payout_cents: i64,
+}
+
+/// A line on a farmer's wallet statement. `amount_cents` is from the
+/// farmer's side: positive when the account grows.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WalletEntry {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub transaction_kind: String,
+    pub account_kind: String,
+    pub currency_code: String,
+    pub amount_cents: i64,
+    pub order_id: Option<Uuid>,
+    pub payout_id: Option<Uuid>,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query string for `GET /api/wallet/entries`.
+#[derive(Debug, Deserialize)]
+pub struct WalletQuery {
+    pub currency_code: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BankAccount {
+    pub id: Uuid,
+    pub farmer_id: Uuid,
+    pub bank_name: String,
+    pub bank_code: Option<String>,
+    pub account_number: String,
+    pub account_name: String,
+    pub currency_code: String,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `BankAccount`.
+pub const BANK_ACCOUNT_COLUMNS: &str = r#"
+    id, farmer_id, bank_name, bank_code, account_number, account_name,
+    currency_code, is_active, created_at, updated_at
+"#;
+
+/// `currency_code` defaults to NGN.
+#[derive(Debug, Deserialize)]
+pub struct CreateBankAccountRequest {
+    pub bank_name: String,
+    pub bank_code: Option<String>,
+    pub account_number: String,
+    pub account_name: String,
+    pub currency_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Payout {
+    pub id: Uuid,
+    pub farmer_id: Uuid,
+    pub bank_account_id: Uuid,
+    pub amount_cents: i64,
+    pub currency_code: String,
+    /// "requested" | "paid" | "failed"
+    pub status: String,
+    pub transfer_reference: Option<String>,
+    pub failure_reason: Option<String>,
+    pub processed_by: Option<Uuid>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `Payout`.
+pub const PAYOUT_COLUMNS: &str = r#"
+    id, farmer_id, bank_account_id, amount_cents, currency_code, status,
+    transfer_reference, failure_reason, processed_by, processed_at, created_at, updated_at
+"#;
+
+pub const PAYOUT_STATUSES: &[&str] = &["requested", "paid", "failed"];
+
+/// Payload for `POST /api/wallet/payouts`. Paid in the bank account's
+/// currency.
+#[derive(Debug, Deserialize)]
+pub struct PayoutRequest {
+    pub bank_account_id: Uuid,
+    pub amount_cents: i64,
+}
+
+/// Query string for listing payouts. `farmer_id` only applies to admins.
+#[derive(Debug, Deserialize)]
+pub struct PayoutQuery {
+    pub status: Option<String>,
+    pub farmer_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Payload for `POST /api/admin/payouts/{id}/paid`.
+#[derive(Debug, Deserialize)]
+pub struct PayoutPaidRequest {
+    pub transfer_reference: String,
+}
+
+/// Payload for `POST /api/admin/payouts/{id}/failed`.
+#[derive(Debug, Deserialize)]
+pub struct PayoutFailedRequest {
+    pub reason: String,
+}
+
+/// Total balance of every account of a kind. Gateway is a debit balance,
+/// the rest credit balances, so all are normally positive.
+#[derive(Debug, Serialize)]
+pub struct LedgerAccountTotal {
+    pub account_kind: String,
+    pub balance_cents: i64,
+}
+
+/// A ledger balance compared with what the payment, refund, payout and
+/// order records say it should be.
+#[derive(Debug, Serialize)]
+pub struct ReconciliationCheck {
+    pub account_kind: String,
+    pub ledger_cents: i64,
+    pub expected_cents: i64,
+    pub difference_cents: i64,
+}
+
+/// The books for one currency. `balanced` is the trial balance: debits
+/// equal credits. `matches_records` is every check agreeing.
+#[derive(Debug, Serialize)]
+pub struct CurrencyReconciliation {
+    pub currency_code: String,
+    pub accounts: Vec<LedgerAccountTotal>,
+    pub total_debits_cents: i64,
+    pub total_credits_cents: i64,
+    pub balanced: bool,
+    pub checks: Vec<ReconciliationCheck>,
+    pub matches_records: bool,
+}
@@ -1,11 +1,33 @@
+pub mod buyer;
+pub mod crop_cycle;
+pub mod exchange_rate;
 pub mod farmer;
 pub mod farm;
 pub mod verification;
 pub mod farm_activity; // Added line
+pub mod farm_finance;
+pub mod farm_input;
+pub mod ledger;
+pub mod order;
+pub mod payment;
 pub mod product; // Added line
+pub mod session;
+pub mod staff;
+pub mod unit;
 
+pub use buyer::*;
+pub use crop_cycle::*;
+pub use exchange_rate::*;
 pub use farmer::*;
 pub use farm::*;
 pub use verification::*;
 pub use farm_activity::*; // Added line
+pub use farm_finance::*;
+pub use farm_input::*;
+pub use ledger::*;
+pub use order::*;
+pub use payment::*;
 pub use product::*; // Added line
+pub use session::*;
+pub use staff::*;
+pub use unit::*;
@@ -0,0 +1,346 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::ConvertedLine;
+
+/// A product in a buyer's cart, priced at the product's current price.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CartLine {
+    pub product_id: Uuid,
+    pub farmer_id: Uuid,
+    pub farm_id: Option<Uuid>,
+    pub name: String,
+    pub unit: String,
+    pub quantity: i32,
+    pub unit_price_cents: i64,
+    pub line_total_cents: i64,
+    pub currency_code: String,
+    pub min_order_qty: i32,
+    pub quantity_available: i32,
+    #[serde(skip_serializing)]
+    pub product_status: String,
+    /// Why the line can't be checked out as it stands, e.g. the product was
+    /// unpublished or stock dropped below the quantity in the cart.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+    /// The line in the viewer's currency, when asked for and a rate is known.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<ConvertedLine>,
+}
+
+/// Column list matching `CartLine`, for queries over
+/// `cart_items c JOIN products p`.
+pub const CART_LINE_COLUMNS: &str = r#"
+    c.product_id, p.farmer_id, p.farm_id, p.name, p.unit, c.quantity,
+    p.price_cents AS unit_price_cents, p.price_cents * c.quantity AS line_total_cents,
+    p.currency_code, p.min_order_qty, p.quantity_available, p.status AS product_status
+"#;
+
+/// What checking out a farmer's part of the cart would cost.
+#[derive(Debug, Serialize)]
+pub struct CartSubtotal {
+    pub farmer_id: Uuid,
+    pub currency_code: String,
+    pub subtotal_cents: i64,
+    /// The subtotal in the viewer's currency, for display.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted_subtotal_cents: Option<i64>,
+}
+
+/// A buyer's cart. Checkout places one order per farmer and currency, as
+/// listed in `subtotals`.
+#[derive(Debug, Serialize)]
+pub struct Cart {
+    pub items: Vec<CartLine>,
+    pub subtotals: Vec<CartSubtotal>,
+    /// The cart is non-empty and no line has a problem.
+    pub can_checkout: bool,
+    /// The viewer's currency, when one was asked for. Checkout still charges
+    /// each order in its own currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_currency_code: Option<String>,
+    /// Every subtotal in the viewer's currency, or absent if any of them
+    /// has no exchange rate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_total_cents: Option<i64>,
+}
+
+/// Body for `POST /api/cart/items`. Adds to the quantity already in the
+/// cart; `quantity` defaults to the product's minimum order.
+#[derive(Debug, Deserialize)]
+pub struct AddCartItemRequest {
+    pub product_id: Uuid,
+    pub quantity: Option<i32>,
+}
+
+/// Body for `PATCH /api/cart/items/{product_id}`.
+#[derive(Debug, Deserialize)]
+pub struct UpdateCartItemRequest {
+    pub quantity: i32,
+}
+
+/// Body for `POST /api/cart/checkout`.
+#[derive(Debug, Default, Deserialize)]
+pub struct CheckoutRequest {
+    pub delivery_notes: Option<String>,
+}
+
+/// One farmer's share of a checkout. Orders placed together share a
+/// `checkout_id`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Order {
+    pub id: Uuid,
+    pub checkout_id: Uuid,
+    pub buyer_id: Uuid,
+    pub farmer_id: Uuid,
+    pub status: String,
+    pub currency_code: String,
+    pub subtotal_cents: i64,
+    pub delivery_notes: Option<String>,
+    /// Set while pending: the order is cancelled if the farmer hasn't
+    /// accepted it by then.
+    pub confirm_by: Option<DateTime<Utc>>,
+    /// "unpaid" | "paid" | "refunded"
+    pub payment_status: String,
+    /// The payment that paid the order.
+    pub payment_id: Option<Uuid>,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `Order`.
+pub const ORDER_COLUMNS: &str = r#"
+    id, checkout_id, buyer_id, farmer_id, status, currency_code,
+    subtotal_cents, delivery_notes, confirm_by, payment_status, payment_id,
+    paid_at, created_at, updated_at
+"#;
+
+pub const ORDER_STATUSES: &[&str] = &[
+    "pending",
+    "confirmed",
+    "packed",
+    "shipped",
+    "ready_for_pickup",
+    "delivered",
+    "disputed",
+    "completed",
+    "cancelled",
+    "rejected",
+];
+
+/// Statuses in which an order's stock is no longer reserved.
+pub const RELEASED_ORDER_STATUSES: &[&str] = &["cancelled", "rejected"];
+
+/// Who changed an order's status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderActor {
+    Buyer,
+    Farmer,
+    /// Automatic changes, such as cancelling orders nobody accepted in time.
+    System,
+}
+
+impl OrderActor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderActor::Buyer => "buyer",
+            OrderActor::Farmer => "farmer",
+            OrderActor::System => "system",
+        }
+    }
+}
+
+/// Allowed order status changes and who may make them. The farmer accepts or
+/// rejects a pending order and fulfils it; either side can cancel before it
+/// is packed (the farmer also once packed); the buyer confirms receipt, or
+/// disputes a delivery they didn't get and confirms it once they do.
+pub fn can_transition_order(actor: OrderActor, from: &str, to: &str) -> bool {
+    match actor {
+        OrderActor::Farmer => matches!(
+            (from, to),
+            ("pending", "confirmed")
+                | ("pending", "rejected")
+                | ("confirmed", "packed")
+                | ("confirmed", "cancelled")
+                | ("packed", "shipped")
+                | ("packed", "ready_for_pickup")
+                | ("packed", "cancelled")
+                | ("shipped", "delivered")
+                | ("ready_for_pickup", "delivered")
+        ),
+        OrderActor::Buyer => matches!(
+            (from, to),
+            ("pending", "cancelled")
+                | ("confirmed", "cancelled")
+                | ("shipped", "delivered")
+                | ("ready_for_pickup", "delivered")
+                | ("delivered", "completed")
+                | ("delivered", "disputed")
+                | ("disputed", "completed")
+        ),
+        OrderActor::System => matches!((from, to), ("pending", "cancelled")),
+    }
+}
+
+/// Payload for `POST /api/orders/{id}/status`.
+#[derive(Debug, Deserialize)]
+pub struct OrderStatusChange {
+    pub status: String,
+    /// Shown to the other party, e.g. why an order was rejected.
+    pub note: Option<String>,
+}
+
+/// A recorded status change. `from_status` is null for the order being
+/// placed and `actor_id` for system changes.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OrderEvent {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub actor_id: Option<Uuid>,
+    pub actor_role: String,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column list matching `OrderEvent`.
+pub const ORDER_EVENT_COLUMNS: &str = r#"
+    id, order_id, from_status, to_status, actor_id, actor_role, note, created_at
+"#;
+
+/// A line of an order, with the product's name, unit and price as they were
+/// at checkout. `product_id` is null once the product is deleted.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OrderItem {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub product_id: Option<Uuid>,
+    pub product_name: String,
+    pub unit: String,
+    pub quantity: i32,
+    pub unit_price_cents: i64,
+    pub line_total_cents: i64,
+}
+
+/// Column list matching `OrderItem`.
+pub const ORDER_ITEM_COLUMNS: &str = r#"
+    id, order_id, product_id, product_name, unit, quantity,
+    unit_price_cents, line_total_cents
+"#;
+
+#[derive(Debug, Serialize)]
+pub struct OrderDetail {
+    #[serde(flatten)]
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+}
+
+/// Response to a checkout: the orders placed, one per farmer and currency.
+#[derive(Debug, Serialize)]
+pub struct Checkout {
+    pub checkout_id: Uuid,
+    pub orders: Vec<OrderDetail>,
+}
+
+/// Query string for `GET /api/orders` and `GET /api/orders/received`.
+#[derive(Debug, Default, Deserialize)]
+pub struct OrderQuery {
+    pub status: Option<String>,
+    pub checkout_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Why `quantity` of a product can't be ordered, if it can't.
+pub fn order_line_problem(
+    status: &str,
+    quantity: i32,
+    min_order_qty: i32,
+    quantity_available: i32,
+    unit: &str,
+) -> Option<String> {
+    if status != "published" {
+        Some("Product is no longer available".to_string())
+    } else if quantity < min_order_qty {
+        Some(format!("Minimum order is {} {}", min_order_qty, unit))
+    } else if quantity > quantity_available {
+        Some(format!("Only {} {} available", quantity_available, unit))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn farmer_moves_an_order_through_fulfilment() {
+        for (from, to) in [
+            ("pending", "confirmed"),
+            ("confirmed", "packed"),
+            ("packed", "shipped"),
+            ("packed", "ready_for_pickup"),
+            ("shipped", "delivered"),
+            ("ready_for_pickup", "delivered"),
+        ] {
+            assert!(can_transition_order(OrderActor::Farmer, from, to), "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn farmer_cannot_complete_or_cancel_after_shipping() {
+        assert!(!can_transition_order(OrderActor::Farmer, "delivered", "completed"));
+        assert!(!can_transition_order(OrderActor::Farmer, "shipped", "cancelled"));
+        assert!(!can_transition_order(OrderActor::Farmer, "pending", "cancelled"));
+    }
+
+    #[test]
+    fn buyer_cancels_only_before_packing() {
+        assert!(can_transition_order(OrderActor::Buyer, "pending", "cancelled"));
+        assert!(can_transition_order(OrderActor::Buyer, "confirmed", "cancelled"));
+        assert!(!can_transition_order(OrderActor::Buyer, "packed", "cancelled"));
+        assert!(!can_transition_order(OrderActor::Buyer, "pending", "confirmed"));
+        assert!(can_transition_order(OrderActor::Buyer, "delivered", "completed"));
+    }
+
+    #[test]
+    fn only_the_buyer_disputes_a_delivery() {
+        assert!(can_transition_order(OrderActor::Buyer, "delivered", "disputed"));
+        assert!(can_transition_order(OrderActor::Buyer, "disputed", "completed"));
+        assert!(!can_transition_order(OrderActor::Buyer, "shipped", "disputed"));
+        for to in ORDER_STATUSES {
+            assert!(!can_transition_order(OrderActor::Farmer, "delivered", to), "delivered -> {}", to);
+            assert!(!can_transition_order(OrderActor::Farmer, "disputed", to), "disputed -> {}", to);
+        }
+    }
+
+    #[test]
+    fn system_only_expires_pending_orders() {
+        assert!(can_transition_order(OrderActor::System, "pending", "cancelled"));
+        for from in ORDER_STATUSES {
+            for to in ORDER_STATUSES {
+                if (*from, *to) != ("pending", "cancelled") {
+                    assert!(!can_transition_order(OrderActor::System, from, to), "{} -> {}", from, to);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn terminal_statuses_are_final() {
+        for actor in [OrderActor::Farmer, OrderActor::Buyer, OrderActor::System] {
+            for from in ["completed", "cancelled", "rejected"] {
+                for to in ORDER_STATUSES {
+                    assert!(!can_transition_order(actor, from, to), "{:?}: {} -> {}", actor, from, to);
+                }
+            }
+        }
+    }
+}
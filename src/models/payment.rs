@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A buyer's payment for the orders of one checkout in one currency.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: Uuid,
+    pub checkout_id: Uuid,
+    pub buyer_id: Uuid,
+    pub provider: String,
+    /// Our reference for the transaction, shared with the gateway.
+    pub reference: String,
+    pub amount_cents: i64,
+    pub currency_code: String,
+    /// "pending" | "succeeded" | "failed" | "partially_refunded" | "refunded"
+    pub status: String,
+    /// Where to send the buyer to pay.
+    pub authorization_url: Option<String>,
+    pub provider_transaction_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub refunded_cents: i64,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `Payment`.
+pub const PAYMENT_COLUMNS: &str = r#"
+    id, checkout_id, buyer_id, provider, reference, amount_cents, currency_code,
+    status, authorization_url, provider_transaction_id, failure_reason,
+    refunded_cents, paid_at, created_at, updated_at
+"#;
+
+/// One order covered by a payment.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PaymentOrder {
+    pub order_id: Uuid,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PaymentRefund {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub order_id: Option<Uuid>,
+    pub amount_cents: i64,
+    pub reason: Option<String>,
+    /// "pending" | "processed" | "failed"
+    pub status: String,
+    pub provider_refund_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `PaymentRefund`.
+pub const PAYMENT_REFUND_COLUMNS: &str = r#"
+    id, payment_id, order_id, amount_cents, reason, status,
+    provider_refund_id, failure_reason, created_at, updated_at
+"#;
+
+pub const PAYMENT_REFUND_STATUSES: &[&str] = &["pending", "processed", "failed"];
+
+/// Query string for `GET /api/admin/refunds`.
+#[derive(Debug, Deserialize)]
+pub struct RefundQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentDetail {
+    #[serde(flatten)]
+    pub payment: Payment,
+    pub orders: Vec<PaymentOrder>,
+    pub refunds: Vec<PaymentRefund>,
+}
+
+/// Payload for `POST /api/payments`. `currency_code` is only needed when the
+/// checkout has orders in more than one currency.
+#[derive(Debug, Deserialize)]
+pub struct CreatePaymentRequest {
+    pub checkout_id: Uuid,
+    pub currency_code: Option<String>,
+}
+
+/// Payload for the mock gateway's `POST /api/dev/payments/{reference}`.
+#[derive(Debug, Deserialize)]
+pub struct MockPaymentOutcome {
+    /// "success" or "failed".
+    pub status: String,
+}
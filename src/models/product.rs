@@ -1,8 +1,10 @@
 use chrono::{DateTime, NaiveDate, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::{ConvertedPrice, NormalizedQuantity};
+
 /// Core product domain model (source of truth).
 /// MVP: single price per product, stock at product level.
 /// Later: extend with product_variants and product_images tables when needed.
@@ -34,7 +36,7 @@ pub struct Product {
     pub expected_harvest_date: Option<NaiveDate>,
     pub expiry_date: Option<NaiveDate>,
 
-    /// Publication status: "draft" | "published" | "archived"
+    /// Publication status: "draft" | "published" | "archived" | "suspended"
     pub status: String,
     /// Visibility: "local_only" | "public" | "both"
     pub visibility: String,
@@ -46,10 +48,26 @@ pub struct Product {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Column list matching `Product`, for `SELECT`/`RETURNING` clauses.
+pub const PRODUCT_COLUMNS: &str = r#"
+    id, farmer_id, farm_id,
+    name, slug, description,
+    category, unit, tags,
+    price_cents, currency_code,
+    min_order_qty, quantity_available,
+    organic, perishable,
+    expected_harvest_date, expiry_date,
+    status, visibility,
+    images,
+    created_at, updated_at
+"#;
+
 /// Payload for creating a new product.
 /// Server generates: id, timestamps; also computes slug if not provided.
+/// `farmer_id` is taken from the authenticated session, never from the body.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewProduct {
+    #[serde(skip_deserializing)]
     pub farmer_id: Uuid,
     pub farm_id: Option<Uuid>,
 
@@ -113,6 +131,196 @@ impl Default for NewProduct {
     }
 }
 
+/// Reads a field that may be cleared: absent is `None`, `null` is
+/// `Some(None)`.
+pub(crate) fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Partial update for an existing product. Absent fields are left unchanged;
+/// the optional ones are cleared by sending `null`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProductUpdate {
+    #[serde(default, deserialize_with = "nullable")]
+    pub farm_id: Option<Option<Uuid>>,
+
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+
+    pub category: Option<String>,
+    pub unit: Option<String>,
+    pub tags: Option<Vec<String>>,
+
+    pub price_cents: Option<i64>,
+    pub currency_code: Option<String>,
+
+    pub min_order_qty: Option<i32>,
+    pub quantity_available: Option<i32>,
+
+    pub organic: Option<bool>,
+    pub perishable: Option<bool>,
+
+    #[serde(default, deserialize_with = "nullable")]
+    pub expected_harvest_date: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub expiry_date: Option<Option<NaiveDate>>,
+
+    pub status: Option<String>,
+    pub visibility: Option<String>,
+
+    pub images: Option<Vec<String>>,
+}
+
+impl ProductUpdate {
+    /// Merges the update over `current`, producing the full payload to
+    /// validate and persist.
+    pub fn apply_to(self, current: &Product) -> NewProduct {
+        NewProduct {
+            farmer_id: current.farmer_id,
+            farm_id: self.farm_id.unwrap_or(current.farm_id),
+
+            name: self.name.unwrap_or_else(|| current.name.clone()),
+            slug: Some(self.slug.unwrap_or_else(|| current.slug.clone())),
+            description: self.description.unwrap_or_else(|| current.description.clone()),
+
+            category: self.category.unwrap_or_else(|| current.category.clone()),
+            unit: self.unit.unwrap_or_else(|| current.unit.clone()),
+            tags: self.tags.unwrap_or_else(|| current.tags.clone()),
+
+            price_cents: self.price_cents.unwrap_or(current.price_cents),
+            currency_code: Some(self.currency_code.unwrap_or_else(|| current.currency_code.clone())),
+
+            min_order_qty: self.min_order_qty.unwrap_or(current.min_order_qty),
+            quantity_available: self.quantity_available.unwrap_or(current.quantity_available),
+
+            organic: self.organic.unwrap_or(current.organic),
+            perishable: self.perishable.unwrap_or(current.perishable),
+
+            expected_harvest_date: self.expected_harvest_date.unwrap_or(current.expected_harvest_date),
+            expiry_date: self.expiry_date.unwrap_or(current.expiry_date),
+
+            status: Some(self.status.unwrap_or_else(|| current.status.clone())),
+            visibility: Some(self.visibility.unwrap_or_else(|| current.visibility.clone())),
+
+            images: self.images.unwrap_or_else(|| current.images.clone()),
+        }
+    }
+}
+
+/// Payload for `POST /api/products/{id}/status`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductStatusChange {
+    pub status: String,
+}
+
+pub const PRODUCT_STATUSES: &[&str] = &["draft", "published", "archived", "suspended"];
+pub const PRODUCT_VISIBILITIES: &[&str] = &["local_only", "public", "both"];
+
+/// Allowed publication status changes. Archived products go back to draft
+/// before they can be published again. Only admins suspend and reinstate
+/// products, so farmers can't move a product into or out of `suspended`.
+pub fn can_transition_status(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("draft", "published")
+            | ("draft", "archived")
+            | ("published", "draft")
+            | ("published", "archived")
+            | ("archived", "draft")
+    ) || from == to
+}
+
+/// Sort orders for the catalogue listing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSort {
+    #[default]
+    Newest,
+    PriceAsc,
+    PriceDesc,
+    /// Soonest expected harvest first; products without a date come last.
+    HarvestDate,
+    /// Nearest farm first; requires `lat`/`lng`.
+    Distance,
+}
+
+impl ProductSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProductSort::Newest => "newest",
+            ProductSort::PriceAsc => "price_asc",
+            ProductSort::PriceDesc => "price_desc",
+            ProductSort::HarvestDate => "harvest_date",
+            ProductSort::Distance => "distance",
+        }
+    }
+}
+
+/// Query string for `GET /api/products`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProductQuery {
+    /// Full-text search over name, tags and description.
+    pub q: Option<String>,
+    pub category: Option<String>,
+    pub tag: Option<String>,
+    pub organic: Option<bool>,
+    pub perishable: Option<bool>,
+    pub min_price_cents: Option<i64>,
+    pub max_price_cents: Option<i64>,
+    pub visibility: Option<String>,
+    /// Defaults to "published"; other statuses only list the caller's own products.
+    pub status: Option<String>,
+    pub farm_id: Option<Uuid>,
+    pub farmer_id: Option<Uuid>,
+    /// Searcher's location; enables distance sorting and `radius_km`.
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub radius_km: Option<f64>,
+    /// Defaults to `distance` when a location is given, otherwise `newest`.
+    pub sort: Option<ProductSort>,
+    pub limit: Option<i64>,
+    /// Opaque `next_cursor` from the previous page.
+    pub cursor: Option<String>,
+    /// Restate price and quantities in this unit where the product's unit
+    /// converts to it.
+    pub unit: Option<String>,
+    /// Also show prices in this currency, where an exchange rate is known.
+    /// Price filters and sorting stay in each product's own currency.
+    pub currency: Option<String>,
+}
+
+/// A catalogue result: the product plus its distance from the searcher in
+/// whole kilometres to the farm's public grid point, when a location was
+/// given and the product is linked to a located farm.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ProductListing {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub product: Product,
+    pub distance_km: Option<f64>,
+    /// Present when the search asked for a unit the product converts to.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<NormalizedQuantity>,
+    /// Present when the search asked for a currency the price converts to.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<ConvertedPrice>,
+}
+
+/// One page of catalogue results.
+#[derive(Debug, Serialize)]
+pub struct ProductPage {
+    pub items: Vec<ProductListing>,
+    pub next_cursor: Option<String>,
+}
+
 /// Helper: basic slugify logic (server-side)
 pub fn slugify(name: &str) -> String {
     let mut s = name.to_lowercase();
@@ -137,3 +345,114 @@ pub fn slugify(name: &str) -> String {
     }
     collapsed.trim_matches('-').to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn current() -> Product {
+        let now = Utc::now();
+        Product {
+            id: Uuid::new_v4(),
+            farmer_id: Uuid::new_v4(),
+            farm_id: Some(Uuid::new_v4()),
+            name: "Yellow maize".to_string(),
+            slug: "yellow-maize".to_string(),
+            description: Some("Dried".to_string()),
+            category: "grains".to_string(),
+            unit: "bag".to_string(),
+            tags: vec!["maize".to_string()],
+            price_cents: 4_500_000,
+            currency_code: "NGN".to_string(),
+            min_order_qty: 1,
+            quantity_available: 40,
+            organic: false,
+            perishable: false,
+            expected_harvest_date: NaiveDate::from_ymd_opt(2026, 9, 1),
+            expiry_date: NaiveDate::from_ymd_opt(2027, 3, 1),
+            status: "published".to_string(),
+            visibility: "both".to_string(),
+            images: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn update(body: serde_json::Value) -> ProductUpdate {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn farmer_moves_between_draft_published_and_archived() {
+        for (from, to) in [
+            ("draft", "published"),
+            ("draft", "archived"),
+            ("published", "draft"),
+            ("published", "archived"),
+            ("archived", "draft"),
+        ] {
+            assert!(can_transition_status(from, to), "{} -> {}", from, to);
+        }
+        assert!(!can_transition_status("archived", "published"));
+    }
+
+    #[test]
+    fn farmer_never_suspends_or_reinstates() {
+        for status in PRODUCT_STATUSES.iter().filter(|status| **status != "suspended") {
+            assert!(!can_transition_status(status, "suspended"), "{} -> suspended", status);
+            assert!(!can_transition_status("suspended", status), "suspended -> {}", status);
+        }
+    }
+
+    #[test]
+    fn unchanged_status_is_allowed() {
+        for status in PRODUCT_STATUSES {
+            assert!(can_transition_status(status, status), "{}", status);
+        }
+    }
+
+    #[test]
+    fn absent_null_and_value_are_told_apart() {
+        let absent = update(json!({}));
+        assert_eq!(absent.description, None);
+        assert_eq!(absent.farm_id, None);
+
+        let null = update(json!({ "description": null, "farm_id": null, "expiry_date": null }));
+        assert_eq!(null.description, Some(None));
+        assert_eq!(null.farm_id, Some(None));
+        assert_eq!(null.expiry_date, Some(None));
+        assert_eq!(null.expected_harvest_date, None);
+
+        let value = update(json!({ "description": "Sun-dried", "expected_harvest_date": "2026-10-01" }));
+        assert_eq!(value.description, Some(Some("Sun-dried".to_string())));
+        assert_eq!(value.expected_harvest_date, Some(NaiveDate::from_ymd_opt(2026, 10, 1)));
+    }
+
+    #[test]
+    fn apply_keeps_absent_fields_and_clears_null_ones() {
+        let current = current();
+
+        let kept = update(json!({ "price_cents": 5_000_000 })).apply_to(&current);
+        assert_eq!(kept.price_cents, 5_000_000);
+        assert_eq!(kept.farm_id, current.farm_id);
+        assert_eq!(kept.description, current.description);
+        assert_eq!(kept.expected_harvest_date, current.expected_harvest_date);
+        assert_eq!(kept.expiry_date, current.expiry_date);
+        assert_eq!(kept.status.as_deref(), Some("published"));
+
+        let cleared = update(json!({
+            "farm_id": null,
+            "description": null,
+            "expected_harvest_date": null,
+            "expiry_date": null
+        }))
+        .apply_to(&current);
+        assert_eq!(cleared.farm_id, None);
+        assert_eq!(cleared.description, None);
+        assert_eq!(cleared.expected_harvest_date, None);
+        assert_eq!(cleared.expiry_date, None);
+        assert_eq!(cleared.name, current.name);
+    }
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use super::{BuyerSession, FarmerSession, StaffSession};
+
+/// One logged-in device as shown to the farmer. Only available with the
+/// Postgres session store.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ActiveSession {
+    pub session_id: Uuid,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[sqlx(default)]
+    pub current: bool,
+}
+
+/// What kind of account a session or access token belongs to. Farmers and
+/// buyers register themselves; the staff roles are granted by an admin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountRole {
+    /// Tokens issued before buyer accounts existed carry no role and are
+    /// farmers'.
+    #[default]
+    Farmer,
+    Buyer,
+    ExtensionOfficer,
+    CoopManager,
+    Admin,
+}
+
+impl AccountRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountRole::Farmer => "farmer",
+            AccountRole::Buyer => "buyer",
+            AccountRole::ExtensionOfficer => "extension_officer",
+            AccountRole::CoopManager => "coop_manager",
+            AccountRole::Admin => "admin",
+        }
+    }
+
+    /// The staff role stored in `staff.role`, if `role` names one.
+    pub fn staff_role(role: &str) -> Option<AccountRole> {
+        match role {
+            "extension_officer" => Some(AccountRole::ExtensionOfficer),
+            "coop_manager" => Some(AccountRole::CoopManager),
+            "admin" => Some(AccountRole::Admin),
+            _ => None,
+        }
+    }
+
+    /// Whether accounts with this role live in the `staff` table.
+    pub fn is_staff(&self) -> bool {
+        Self::staff_role(self.as_str()).is_some()
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        use Permission::*;
+        match self {
+            AccountRole::Farmer | AccountRole::Buyer => matches!(permission, PlaceOrders),
+            AccountRole::ExtensionOfficer | AccountRole::CoopManager => matches!(permission, ReadAssignedFarms),
+            AccountRole::Admin => matches!(
+                permission,
+                ReadAssignedFarms | ReadAnyFarm | ModerateProducts | ManageStaff | ManagePayouts | ManageExchangeRates
+            ),
+        }
+    }
+}
+
+/// Something a role may be allowed to do. Checked by `RequirePermission` and
+/// the auth extractors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Fill a cart and order from farmers.
+    PlaceOrders,
+    /// Read the records of farms assigned to the staff member.
+    ReadAssignedFarms,
+    /// Read the records of every farm.
+    ReadAnyFarm,
+    /// Suspend and reinstate marketplace products.
+    ModerateProducts,
+    /// Create staff accounts and assign farms to them.
+    ManageStaff,
+    /// Send farmer payouts and read the escrow ledger.
+    ManagePayouts,
+    /// Maintain the exchange rates used to display prices.
+    ManageExchangeRates,
+}
+
+/// The logged-in account, whichever kind it is.
+#[derive(Debug, Clone)]
+pub enum SessionAccount {
+    Farmer(FarmerSession),
+    Buyer(BuyerSession),
+    Staff(StaffSession),
+}
+
+impl SessionAccount {
+    pub fn id(&self) -> Uuid {
+        match self {
+            SessionAccount::Farmer(farmer) => farmer.farmer_id,
+            SessionAccount::Buyer(buyer) => buyer.buyer_id,
+            SessionAccount::Staff(staff) => staff.staff_id,
+        }
+    }
+
+    pub fn role(&self) -> AccountRole {
+        match self {
+            SessionAccount::Farmer(_) => AccountRole::Farmer,
+            SessionAccount::Buyer(_) => AccountRole::Buyer,
+            SessionAccount::Staff(staff) => staff.role,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            SessionAccount::Farmer(farmer) => &farmer.name,
+            SessionAccount::Buyer(buyer) => &buyer.name,
+            SessionAccount::Staff(staff) => &staff.name,
+        }
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role().can(permission)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERMISSIONS: [Permission; 7] = [
+        Permission::PlaceOrders,
+        Permission::ReadAssignedFarms,
+        Permission::ReadAnyFarm,
+        Permission::ModerateProducts,
+        Permission::ManageStaff,
+        Permission::ManagePayouts,
+        Permission::ManageExchangeRates,
+    ];
+
+    fn granted(role: AccountRole) -> Vec<Permission> {
+        PERMISSIONS.into_iter().filter(|permission| role.can(*permission)).collect()
+    }
+
+    #[test]
+    fn farmers_and_buyers_only_place_orders() {
+        assert_eq!(granted(AccountRole::Farmer), [Permission::PlaceOrders]);
+        assert_eq!(granted(AccountRole::Buyer), [Permission::PlaceOrders]);
+    }
+
+    #[test]
+    fn field_staff_only_read_their_assigned_farms() {
+        assert_eq!(granted(AccountRole::ExtensionOfficer), [Permission::ReadAssignedFarms]);
+        assert_eq!(granted(AccountRole::CoopManager), [Permission::ReadAssignedFarms]);
+    }
+
+    #[test]
+    fn admins_can_do_everything_but_order() {
+        let expected: Vec<Permission> =
+            PERMISSIONS.into_iter().filter(|permission| *permission != Permission::PlaceOrders).collect();
+        assert_eq!(granted(AccountRole::Admin), expected);
+    }
+
+    #[test]
+    fn staff_roles_round_trip_through_the_staff_table() {
+        for role in [AccountRole::ExtensionOfficer, AccountRole::CoopManager, AccountRole::Admin] {
+            assert_eq!(AccountRole::staff_role(role.as_str()), Some(role));
+            assert!(role.is_staff());
+        }
+        for role in [AccountRole::Farmer, AccountRole::Buyer] {
+            assert_eq!(AccountRole::staff_role(role.as_str()), None);
+            assert!(!role.is_staff());
+        }
+        assert_eq!(AccountRole::staff_role("Admin"), None);
+    }
+}
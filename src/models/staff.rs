@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::AccountRole;
+
+/// An extension officer, cooperative manager or admin.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Staff {
+    pub id: Uuid,
+    pub phone_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: String,
+    pub is_active: bool,
+    pub verification_status: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Column list matching `Staff`.
+pub const STAFF_COLUMNS: &str = r#"
+    id, phone_number, first_name, last_name, role, is_active,
+    verification_status, created_by, created_at, updated_at
+"#;
+
+pub const STAFF_ROLES: &[&str] = &["extension_officer", "coop_manager", "admin"];
+
+/// Payload for `POST /api/admin/staff`.
+#[derive(Debug, Deserialize)]
+pub struct CreateStaffRequest {
+    pub phone_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: String,
+}
+
+/// Deactivating a staff member also ends their sessions and refresh tokens.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateStaffRequest {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub role: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// The staff member stored in the cookie session or carried in an access
+/// token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaffSession {
+    pub staff_id: Uuid,
+    pub name: String,
+    pub role: AccountRole,
+}
+
+/// Query string for `GET /api/admin/staff`.
+#[derive(Debug, Default, Deserialize)]
+pub struct StaffQuery {
+    pub role: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// Payload for `POST /api/admin/staff/{id}/farms`.
+#[derive(Debug, Deserialize)]
+pub struct AssignFarmRequest {
+    pub farm_id: Uuid,
+}
+
+/// Payload for suspending or reinstating a product.
+#[derive(Debug, Default, Deserialize)]
+pub struct ModerationRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ModerationEvent {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub staff_id: Option<Uuid>,
+    pub action: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Column list matching `ModerationEvent`.
+pub const MODERATION_EVENT_COLUMNS: &str = "id, product_id, staff_id, action, reason, created_at";
@@ -0,0 +1,90 @@
+use serde::Serialize;
+
+/// A unit of measure from the registry.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Unit {
+    pub code: String,
+    pub name: String,
+    /// "mass", "volume" or "count".
+    pub dimension: String,
+    /// Size in the dimension's base unit (kg, litre or piece). Missing for
+    /// packaging units, which only convert to themselves.
+    pub to_base: Option<f64>,
+    pub aliases: Vec<String>,
+}
+
+/// Column list matching `Unit`.
+pub const UNIT_COLUMNS: &str = "code, name, dimension, to_base, aliases";
+
+impl Unit {
+    /// How many `other` make one of this unit, if they are convertible.
+    pub fn factor_to(&self, other: &Unit) -> Option<f64> {
+        if self.code == other.code {
+            return Some(1.0);
+        }
+        if self.dimension != other.dimension {
+            return None;
+        }
+        Some(self.to_base? / other.to_base?)
+    }
+}
+
+/// A quantity and price restated in a requested unit.
+#[derive(Debug, Serialize)]
+pub struct NormalizedQuantity {
+    pub unit: String,
+    pub price_cents: i64,
+    pub quantity_available: f64,
+    pub min_order_qty: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(code: &str, dimension: &str, to_base: Option<f64>) -> Unit {
+        Unit {
+            code: code.to_string(),
+            name: code.to_string(),
+            dimension: dimension.to_string(),
+            to_base,
+            aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn converts_within_a_dimension() {
+        let kg = unit("kg", "mass", Some(1.0));
+        let g = unit("g", "mass", Some(0.001));
+        let tonne = unit("tonne", "mass", Some(1000.0));
+        assert!((kg.factor_to(&g).unwrap() - 1000.0).abs() < 1e-9);
+        assert!((g.factor_to(&kg).unwrap() - 0.001).abs() < 1e-12);
+        assert!((tonne.factor_to(&kg).unwrap() - 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn same_unit_is_one_even_without_a_base_size() {
+        let bag = unit("bag", "count", None);
+        assert_eq!(bag.factor_to(&bag.clone()), Some(1.0));
+        let kg = unit("kg", "mass", Some(1.0));
+        assert_eq!(kg.factor_to(&kg.clone()), Some(1.0));
+    }
+
+    #[test]
+    fn other_dimensions_do_not_convert() {
+        let kg = unit("kg", "mass", Some(1.0));
+        let litre = unit("l", "volume", Some(1.0));
+        assert_eq!(kg.factor_to(&litre), None);
+        assert_eq!(litre.factor_to(&kg), None);
+    }
+
+    #[test]
+    fn packaging_units_only_convert_to_themselves() {
+        let bag = unit("bag", "count", None);
+        let piece = unit("piece", "count", Some(1.0));
+        let crate_ = unit("crate", "count", None);
+        assert_eq!(bag.factor_to(&piece), None);
+        assert_eq!(piece.factor_to(&bag), None);
+        assert_eq!(bag.factor_to(&crate_), None);
+    }
+}
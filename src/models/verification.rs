@@ -2,18 +2,45 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use super::AccountRole;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct PhoneVerification {
     pub id: Uuid,
     pub phone_number: String,
-    pub otp_code: String,
+    pub otp_hash: String,
+    pub purpose: String,
     pub expires_at: DateTime<Utc>,
     pub verified: bool,
     pub attempts: i32,
+    pub request_ip: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+/// What an OTP was issued for. A code only verifies for its own purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpPurpose {
+    Registration,
+    Login,
+}
+
+impl OtpPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtpPurpose::Registration => "registration",
+            OtpPurpose::Login => "login",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SendOtpRequest {
     pub phone_number: String,
+    /// Defaults to `login` for request-otp; resend-otp reuses the purpose of
+    /// the last code sent.
+    pub purpose: Option<OtpPurpose>,
+    /// Which kind of account the phone logs in to.
+    #[serde(default)]
+    pub role: AccountRole,
 }
@@ -0,0 +1,433 @@
+use chrono::Utc;
+use sqlx::{types::Json, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        ActivityQuery, CreateFarmActivityRequest, FarmActivity, InputUsed, UpdateFarmActivityRequest, ACTIVITY_COLUMNS,
+        ACTIVITY_STATUSES, ACTIVITY_TYPES,
+    },
+    services::{
+        crop_cycle_service, farm_service, input_service, plot_service,
+        unit_service::{self, UnitRegistry},
+    },
+    utils::{clean_text, one_of},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Validates and normalises an activity in place, filling in the default
+/// status from the activity date.
+fn validate_activity(activity: &mut CreateFarmActivityRequest) -> AppResult<()> {
+    one_of("activity_type", &activity.activity_type, ACTIVITY_TYPES)?;
+
+    if activity.description.trim().is_empty() {
+        return Err(AppError::ValidationError("Description is required".to_string()));
+    }
+
+    let status = activity.status.take().unwrap_or_else(|| {
+        if activity.activity_date > Utc::now().date_naive() { "planned" } else { "completed" }.to_string()
+    });
+    one_of("status", &status, ACTIVITY_STATUSES)?;
+    activity.status = Some(status);
+
+    activity.crop_name = clean_text(activity.crop_name.take());
+    activity.field_plot = clean_text(activity.field_plot.take());
+    activity.unit_measured = clean_text(activity.unit_measured.take());
+    activity.notes = clean_text(activity.notes.take());
+
+    for input in activity.inputs_used.iter().flatten() {
+        if input.item.trim().is_empty() || input.unit.trim().is_empty() {
+            return Err(AppError::ValidationError("Each input needs an item and a unit".to_string()));
+        }
+        if !(input.quantity > 0.0 && input.quantity.is_finite()) {
+            return Err(AppError::ValidationError(format!("Quantity of {} must be greater than 0", input.item)));
+        }
+    }
+
+    if let Some(quantity) = activity.quantity_measured {
+        if !(quantity >= 0.0 && quantity.is_finite()) {
+            return Err(AppError::ValidationError("quantity_measured must be >= 0".to_string()));
+        }
+        if activity.unit_measured.is_none() {
+            return Err(AppError::ValidationError("unit_measured is required with quantity_measured".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewrites input and measurement units to their registry codes.
+fn normalize_units(
+    units: &UnitRegistry,
+    inputs_used: &mut Option<Vec<InputUsed>>,
+    unit_measured: Option<&mut String>,
+) -> AppResult<()> {
+    for input in inputs_used.iter_mut().flatten() {
+        input.unit = units.canonical(&input.unit)?;
+    }
+    if let Some(unit) = unit_measured.filter(|unit| !unit.trim().is_empty()) {
+        *unit = units.canonical(unit)?;
+    }
+    Ok(())
+}
+
+/// Replaces `field_plot` with the farm's spelling of the plot, rejecting
+/// names that aren't plots on this farm.
+async fn resolve_plot(db: &Database, farm_id: Uuid, activity: &mut CreateFarmActivityRequest) -> AppResult<()> {
+    let Some(name) = activity.field_plot.as_deref() else {
+        return Ok(());
+    };
+
+    let plot = plot_service::find_plot_by_name(db, farm_id, name)
+        .await?
+        .ok_or_else(|| AppError::ValidationError(format!("'{}' is not a plot on this farm", name)))?;
+    activity.field_plot = Some(plot.name);
+    Ok(())
+}
+
+/// Checks the activity's crop cycle is on this farm and fills in the crop,
+/// plot and expected harvest from it where the activity doesn't give its own.
+async fn resolve_cycle(db: &Database, farm_id: Uuid, activity: &mut CreateFarmActivityRequest) -> AppResult<()> {
+    let Some(cycle_id) = activity.crop_cycle_id else {
+        return Ok(());
+    };
+
+    let cycle = crop_cycle_service::find_cycle(db, farm_id, cycle_id)
+        .await?
+        .ok_or_else(|| AppError::ValidationError("Crop cycle not found on this farm".to_string()))?;
+    activity.crop_name.get_or_insert(cycle.crop_name);
+    if activity.field_plot.is_none() {
+        activity.field_plot = cycle.field_plot;
+    }
+    if activity.expected_harvest_date.is_none() {
+        activity.expected_harvest_date = cycle.expected_harvest_date;
+    }
+    Ok(())
+}
+
+pub async fn list_activities(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    query: &ActivityQuery,
+) -> AppResult<Vec<FarmActivity>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(AppError::ValidationError("from must be on or before to".to_string()));
+        }
+    }
+    if let Some(activity_type) = &query.activity_type {
+        one_of("activity_type", activity_type, ACTIVITY_TYPES)?;
+    }
+    if let Some(status) = &query.status {
+        one_of("status", status, ACTIVITY_STATUSES)?;
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM farm_activities WHERE farm_id = ",
+        ACTIVITY_COLUMNS
+    ));
+    qb.push_bind(farm_id);
+
+    if let Some(from) = query.from {
+        qb.push(" AND activity_date >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND activity_date <= ").push_bind(to);
+    }
+    if let Some(crop) = query.crop.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        qb.push(" AND lower(crop_name) = lower(").push_bind(crop.to_string()).push(")");
+    }
+    if let Some(activity_type) = &query.activity_type {
+        qb.push(" AND activity_type = ").push_bind(activity_type.clone());
+    }
+    if let Some(status) = &query.status {
+        qb.push(" AND status = ").push_bind(status.clone());
+    }
+    if let Some(field_plot) = query.field_plot.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        qb.push(" AND lower(field_plot) = lower(").push_bind(field_plot.to_string()).push(")");
+    }
+    if let Some(crop_cycle_id) = query.crop_cycle_id {
+        qb.push(" AND crop_cycle_id = ").push_bind(crop_cycle_id);
+    }
+    if let Some(overdue) = query.overdue {
+        qb.push(if overdue { " AND " } else { " AND NOT " });
+        qb.push("(status IN ('planned', 'in_progress') AND activity_date < ")
+            .push_bind(Utc::now().date_naive())
+            .push(")");
+    }
+
+    qb.push(" ORDER BY activity_date DESC, created_at DESC LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+
+    Ok(qb.build_query_as::<FarmActivity>().fetch_all(&db.pool).await?)
+}
+
+pub async fn get_activity(db: &Database, farmer_id: Uuid, farm_id: Uuid, activity_id: Uuid) -> AppResult<FarmActivity> {
+    sqlx::query_as::<_, FarmActivity>(&format!(
+        "SELECT {} FROM farm_activities WHERE id = $1 AND farm_id = $2 AND farmer_id = $3",
+        ACTIVITY_COLUMNS
+    ))
+    .bind(activity_id)
+    .bind(farm_id)
+    .bind(farmer_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Activity not found".to_string()))
+}
+
+pub async fn create_activity(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    mut request: CreateFarmActivityRequest,
+) -> AppResult<FarmActivity> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    request.farmer_id = farmer_id;
+    request.farm_id = Some(farm_id);
+
+    validate_activity(&mut request)?;
+    let units = unit_service::registry(db).await?;
+    normalize_units(&units, &mut request.inputs_used, request.unit_measured.as_mut())?;
+    resolve_cycle(db, farm_id, &mut request).await?;
+    resolve_plot(db, farm_id, &mut request).await?;
+
+    let mut tx = db.pool.begin().await?;
+
+    let activity = sqlx::query_as::<_, FarmActivity>(&format!(
+        r#"
+        INSERT INTO farm_activities (
+            farmer_id, farm_id, activity_type, description, activity_date, status,
+            crop_name, field_plot, inputs_used, quantity_measured, unit_measured,
+            expected_harvest_date, notes, crop_cycle_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        RETURNING {}
+        "#,
+        ACTIVITY_COLUMNS
+    ))
+    .bind(request.farmer_id)
+    .bind(request.farm_id)
+    .bind(&request.activity_type)
+    .bind(request.description.trim())
+    .bind(request.activity_date)
+    .bind(&request.status)
+    .bind(&request.crop_name)
+    .bind(&request.field_plot)
+    .bind(request.inputs_used.map(Json))
+    .bind(request.quantity_measured)
+    .bind(&request.unit_measured)
+    .bind(request.expected_harvest_date)
+    .bind(&request.notes)
+    .bind(request.crop_cycle_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    input_service::apply_usage(&mut tx, &activity, &units).await?;
+
+    tx.commit().await?;
+    Ok(activity)
+}
+
+pub async fn update_activity(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    activity_id: Uuid,
+    mut request: UpdateFarmActivityRequest,
+) -> AppResult<FarmActivity> {
+    let current = get_activity(db, farmer_id, farm_id, activity_id).await?;
+
+    // Only units sent with the update are checked; ones already stored may
+    // predate the units table.
+    let units = unit_service::registry(db).await?;
+    normalize_units(
+        &units,
+        &mut request.inputs_used,
+        request.unit_measured.as_mut().and_then(Option::as_mut),
+    )?;
+    let mut merged = request.apply_to(&current);
+
+    validate_activity(&mut merged)?;
+    resolve_cycle(db, farm_id, &mut merged).await?;
+    resolve_plot(db, farm_id, &mut merged).await?;
+
+    let mut tx = db.pool.begin().await?;
+    input_service::reverse_usage(&mut tx, activity_id).await?;
+
+    let activity = sqlx::query_as::<_, FarmActivity>(&format!(
+        r#"
+        UPDATE farm_activities SET
+            activity_type = $3, description = $4, activity_date = $5, status = $6,
+            crop_name = $7, field_plot = $8, inputs_used = $9,
+            quantity_measured = $10, unit_measured = $11,
+            expected_harvest_date = $12, notes = $13, crop_cycle_id = $14
+        WHERE id = $1 AND farmer_id = $2
+        RETURNING {}
+        "#,
+        ACTIVITY_COLUMNS
+    ))
+    .bind(activity_id)
+    .bind(farmer_id)
+    .bind(&merged.activity_type)
+    .bind(merged.description.trim())
+    .bind(merged.activity_date)
+    .bind(&merged.status)
+    .bind(&merged.crop_name)
+    .bind(&merged.field_plot)
+    .bind(merged.inputs_used.map(Json))
+    .bind(merged.quantity_measured)
+    .bind(&merged.unit_measured)
+    .bind(merged.expected_harvest_date)
+    .bind(&merged.notes)
+    .bind(merged.crop_cycle_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    input_service::apply_usage(&mut tx, &activity, &units).await?;
+
+    tx.commit().await?;
+    Ok(activity)
+}
+
+pub async fn delete_activity(db: &Database, farmer_id: Uuid, farm_id: Uuid, activity_id: Uuid) -> AppResult<()> {
+    let mut tx = db.pool.begin().await?;
+    input_service::reverse_usage(&mut tx, activity_id).await?;
+
+    let deleted = sqlx::query("DELETE FROM farm_activities WHERE id = $1 AND farm_id = $2 AND farmer_id = $3")
+        .bind(activity_id)
+        .bind(farm_id)
+        .bind(farmer_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Activity not found".to_string()));
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, NaiveDate};
+
+    use super::*;
+
+    fn activity(activity_date: NaiveDate) -> CreateFarmActivityRequest {
+        CreateFarmActivityRequest {
+            farmer_id: Uuid::new_v4(),
+            farm_id: Some(Uuid::new_v4()),
+            crop_cycle_id: None,
+            activity_type: "fertilizing".to_string(),
+            description: "Top dressing".to_string(),
+            activity_date,
+            status: None,
+            crop_name: Some("  Maize ".to_string()),
+            field_plot: Some(" ".to_string()),
+            inputs_used: Some(vec![InputUsed { item: "NPK".to_string(), quantity: 2.0, unit: "bag".to_string() }]),
+            quantity_measured: None,
+            unit_measured: None,
+            expected_harvest_date: None,
+            notes: None,
+        }
+    }
+
+    fn today() -> NaiveDate {
+        Utc::now().date_naive()
+    }
+
+    fn rejection(mut activity: CreateFarmActivityRequest) -> String {
+        match validate_activity(&mut activity) {
+            Err(AppError::ValidationError(message)) => message,
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn status_defaults_from_the_date() {
+        let mut future = activity(today() + Days::new(3));
+        validate_activity(&mut future).unwrap();
+        assert_eq!(future.status.as_deref(), Some("planned"));
+
+        for date in [today(), today() - Days::new(3)] {
+            let mut past = activity(date);
+            validate_activity(&mut past).unwrap();
+            assert_eq!(past.status.as_deref(), Some("completed"), "{}", date);
+        }
+
+        let mut given = activity(today() + Days::new(3));
+        given.status = Some("in_progress".to_string());
+        validate_activity(&mut given).unwrap();
+        assert_eq!(given.status.as_deref(), Some("in_progress"));
+    }
+
+    #[test]
+    fn text_fields_are_trimmed_and_blanks_dropped() {
+        let mut activity = activity(today());
+        validate_activity(&mut activity).unwrap();
+        assert_eq!(activity.crop_name.as_deref(), Some("Maize"));
+        assert_eq!(activity.field_plot, None);
+    }
+
+    #[test]
+    fn type_status_and_description_are_checked() {
+        let mut bad_type = activity(today());
+        bad_type.activity_type = "dancing".to_string();
+        assert!(rejection(bad_type).starts_with("activity_type must be one of"));
+
+        let mut bad_status = activity(today());
+        bad_status.status = Some("done".to_string());
+        assert!(rejection(bad_status).starts_with("status must be one of"));
+
+        let mut blank = activity(today());
+        blank.description = "   ".to_string();
+        assert_eq!(rejection(blank), "Description is required");
+    }
+
+    #[test]
+    fn inputs_need_an_item_a_unit_and_a_positive_quantity() {
+        let inputs = [
+            ("", 2.0, "bag"),
+            ("NPK", 2.0, " "),
+            ("NPK", 0.0, "bag"),
+            ("NPK", -1.0, "bag"),
+            ("NPK", f64::NAN, "bag"),
+        ];
+        for (item, quantity, unit) in inputs {
+            let mut activity = activity(today());
+            activity.inputs_used = Some(vec![InputUsed { item: item.to_string(), quantity, unit: unit.to_string() }]);
+            assert!(validate_activity(&mut activity).is_err(), "{:?} {} {:?}", item, quantity, unit);
+        }
+    }
+
+    #[test]
+    fn a_measured_quantity_needs_a_unit() {
+        let mut without_unit = activity(today());
+        without_unit.quantity_measured = Some(12.0);
+        without_unit.unit_measured = Some("  ".to_string());
+        assert_eq!(rejection(without_unit), "unit_measured is required with quantity_measured");
+
+        let mut negative = activity(today());
+        negative.quantity_measured = Some(-1.0);
+        negative.unit_measured = Some("kg".to_string());
+        assert_eq!(rejection(negative), "quantity_measured must be >= 0");
+
+        let mut measured = activity(today());
+        measured.quantity_measured = Some(0.0);
+        measured.unit_measured = Some("kg".to_string());
+        assert!(validate_activity(&mut measured).is_ok());
+    }
+}
@@ -0,0 +1,328 @@
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        Buyer, BuyerAddress, BuyerSession, CreateBuyerAddressRequest, CreateBuyerRequest, OtpPurpose,
+        UpdateBuyerAddressRequest, UpdateBuyerRequest, VerifyPhoneRequest, BUYER_ADDRESS_COLUMNS, BUYER_COLUMNS,
+        BUYER_TYPES,
+    },
+    services::{otp_service, sms_service::SmsProvider},
+    utils::{clean_text, geo::GeoPoint, one_of, phone, required_text},
+};
+
+/// Registers a buyer and texts a registration code to their phone. A phone
+/// already used by a farmer account can also register as a buyer.
+pub async fn create_buyer(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    request: CreateBuyerRequest,
+    client_ip: Option<&str>,
+) -> AppResult<Buyer> {
+    let phone_number = phone::normalize(&request.phone_number)?;
+    let first_name = required_text("first_name", &request.first_name)?;
+    let last_name = required_text("last_name", &request.last_name)?;
+    let buyer_type = request.buyer_type.unwrap_or_else(|| "individual".to_string());
+    one_of("buyer_type", &buyer_type, BUYER_TYPES)?;
+
+    let existing: Option<Uuid> = sqlx::query_scalar("SELECT id FROM buyers WHERE phone_number = $1")
+        .bind(&phone_number)
+        .fetch_optional(&db.pool)
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::ValidationError("Phone number already registered".to_string()));
+    }
+    otp_service::reserve_send(db, &phone_number, client_ip).await?;
+
+    let buyer = sqlx::query_as::<_, Buyer>(&format!(
+        "INSERT INTO buyers (phone_number, email, first_name, last_name, buyer_type, business_name) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        BUYER_COLUMNS
+    ))
+    .bind(&phone_number)
+    .bind(clean_text(request.email))
+    .bind(first_name)
+    .bind(last_name)
+    .bind(buyer_type)
+    .bind(clean_text(request.business_name))
+    .fetch_one(&db.pool)
+    .await?;
+
+    otp_service::deliver_otp(db, sms, &phone_number, OtpPurpose::Registration, client_ip).await?;
+    Ok(buyer)
+}
+
+/// Sends a code to a registered buyer. Registration codes are only issued
+/// while the phone is still unverified.
+pub async fn request_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    purpose: OtpPurpose,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let phone_number = phone::normalize(phone_number)?;
+
+    let status: String = sqlx::query_scalar("SELECT verification_status FROM buyers WHERE phone_number = $1")
+        .bind(&phone_number)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::ValidationError("Buyer is not registered".to_string()))?;
+
+    if purpose == OtpPurpose::Registration && status == "phone_verified" {
+        return Err(AppError::ValidationError("Phone number already verified, log in instead".to_string()));
+    }
+
+    otp_service::send_otp(db, sms, &phone_number, purpose, client_ip).await
+}
+
+/// Sends a fresh code, reusing the purpose of the last code when none is given.
+pub async fn resend_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    purpose: Option<OtpPurpose>,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let phone_number = phone::normalize(phone_number)?;
+    let purpose = match purpose {
+        Some(purpose) => purpose,
+        None => otp_service::last_purpose(db, &phone_number).await?,
+    };
+
+    request_otp(db, sms, &phone_number, purpose, client_ip).await
+}
+
+/// Checks the code, marks the buyer's phone as verified and returns the
+/// buyer to log in.
+pub async fn verify_buyer(db: &Database, request: VerifyPhoneRequest, client_ip: Option<&str>) -> AppResult<Buyer> {
+    let phone_number = otp_service::verify_otp(db, &request, client_ip).await?;
+
+    sqlx::query_as::<_, Buyer>(&format!(
+        "UPDATE buyers SET verification_status = 'phone_verified' WHERE phone_number = $1 RETURNING {}",
+        BUYER_COLUMNS
+    ))
+    .bind(&phone_number)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Buyer is not registered".to_string()))
+}
+
+/// Rebuilds the session payload for a buyer, e.g. when refreshing a token.
+pub async fn buyer_session(db: &Database, buyer_id: Uuid) -> AppResult<BuyerSession> {
+    let name: String = sqlx::query_scalar("SELECT first_name FROM buyers WHERE id = $1")
+        .bind(buyer_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Buyer no longer exists".to_string()))?;
+
+    Ok(BuyerSession { buyer_id, name })
+}
+
+pub async fn get_buyer(db: &Database, buyer_id: Uuid) -> AppResult<Buyer> {
+    sqlx::query_as::<_, Buyer>(&format!("SELECT {} FROM buyers WHERE id = $1", BUYER_COLUMNS))
+        .bind(buyer_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Buyer not found".to_string()))
+}
+
+pub async fn update_buyer(db: &Database, buyer_id: Uuid, request: UpdateBuyerRequest) -> AppResult<Buyer> {
+    let first_name = request.first_name.as_deref().map(|name| required_text("first_name", name)).transpose()?;
+    let last_name = request.last_name.as_deref().map(|name| required_text("last_name", name)).transpose()?;
+    if let Some(buyer_type) = &request.buyer_type {
+        one_of("buyer_type", buyer_type, BUYER_TYPES)?;
+    }
+
+    sqlx::query_as::<_, Buyer>(&format!(
+        r#"
+        UPDATE buyers SET
+            email = COALESCE($2, email),
+            first_name = COALESCE($3, first_name),
+            last_name = COALESCE($4, last_name),
+            buyer_type = COALESCE($5, buyer_type),
+            business_name = COALESCE($6, business_name)
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        BUYER_COLUMNS
+    ))
+    .bind(buyer_id)
+    .bind(clean_text(request.email))
+    .bind(first_name)
+    .bind(last_name)
+    .bind(request.buyer_type)
+    .bind(clean_text(request.business_name))
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Buyer not found".to_string()))
+}
+
+/// The buyer's addresses, default first.
+pub async fn list_addresses(db: &Database, buyer_id: Uuid) -> AppResult<Vec<BuyerAddress>> {
+    Ok(sqlx::query_as::<_, BuyerAddress>(&format!(
+        "SELECT {} FROM buyer_addresses WHERE buyer_id = $1 ORDER BY is_default DESC, created_at",
+        BUYER_ADDRESS_COLUMNS
+    ))
+    .bind(buyer_id)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+async fn find_address(db: &Database, buyer_id: Uuid, address_id: Uuid) -> AppResult<BuyerAddress> {
+    sqlx::query_as::<_, BuyerAddress>(&format!(
+        "SELECT {} FROM buyer_addresses WHERE id = $1 AND buyer_id = $2",
+        BUYER_ADDRESS_COLUMNS
+    ))
+    .bind(address_id)
+    .bind(buyer_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Address not found".to_string()))
+}
+
+fn contact_phone(phone_number: Option<String>) -> AppResult<Option<String>> {
+    clean_text(phone_number).map(|number| phone::normalize(&number)).transpose()
+}
+
+pub async fn create_address(
+    db: &Database,
+    buyer_id: Uuid,
+    request: CreateBuyerAddressRequest,
+) -> AppResult<BuyerAddress> {
+    let address_text = required_text("address_text", &request.address_text)?;
+    GeoPoint::from_query(request.latitude, request.longitude)?;
+    let phone_number = contact_phone(request.phone_number)?;
+
+    let mut tx = db.pool.begin().await?;
+    let has_addresses: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM buyer_addresses WHERE buyer_id = $1)")
+        .bind(buyer_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let is_default = request.is_default || !has_addresses;
+    if is_default {
+        sqlx::query("UPDATE buyer_addresses SET is_default = FALSE WHERE buyer_id = $1 AND is_default")
+            .bind(buyer_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let address = sqlx::query_as::<_, BuyerAddress>(&format!(
+        r#"
+        INSERT INTO buyer_addresses (
+            buyer_id, label, recipient_name, phone_number, address_text, city, state,
+            latitude, longitude, delivery_instructions, is_default
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        BUYER_ADDRESS_COLUMNS
+    ))
+    .bind(buyer_id)
+    .bind(clean_text(request.label))
+    .bind(clean_text(request.recipient_name))
+    .bind(phone_number)
+    .bind(address_text)
+    .bind(clean_text(request.city))
+    .bind(clean_text(request.state))
+    .bind(request.latitude)
+    .bind(request.longitude)
+    .bind(clean_text(request.delivery_instructions))
+    .bind(is_default)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(address)
+}
+
+pub async fn update_address(
+    db: &Database,
+    buyer_id: Uuid,
+    address_id: Uuid,
+    request: UpdateBuyerAddressRequest,
+) -> AppResult<BuyerAddress> {
+    let current = find_address(db, buyer_id, address_id).await?;
+    if request.is_default == Some(false) && current.is_default {
+        return Err(AppError::ValidationError(
+            "Make another address the default instead".to_string(),
+        ));
+    }
+    let address_text = request.address_text.as_deref().map(|text| required_text("address_text", text)).transpose()?;
+    let latitude = request.latitude.or(current.latitude);
+    let longitude = request.longitude.or(current.longitude);
+    GeoPoint::from_query(latitude, longitude)?;
+    let phone_number = contact_phone(request.phone_number)?;
+    let make_default = request.is_default == Some(true) && !current.is_default;
+
+    let mut tx = db.pool.begin().await?;
+    if make_default {
+        sqlx::query("UPDATE buyer_addresses SET is_default = FALSE WHERE buyer_id = $1 AND is_default")
+            .bind(buyer_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let address = sqlx::query_as::<_, BuyerAddress>(&format!(
+        r#"
+        UPDATE buyer_addresses SET
+            label = COALESCE($3, label),
+            recipient_name = COALESCE($4, recipient_name),
+            phone_number = COALESCE($5, phone_number),
+            address_text = COALESCE($6, address_text),
+            city = COALESCE($7, city),
+            state = COALESCE($8, state),
+            latitude = $9,
+            longitude = $10,
+            delivery_instructions = COALESCE($11, delivery_instructions),
+            is_default = is_default OR $12
+        WHERE id = $1 AND buyer_id = $2
+        RETURNING {}
+        "#,
+        BUYER_ADDRESS_COLUMNS
+    ))
+    .bind(address_id)
+    .bind(buyer_id)
+    .bind(clean_text(request.label))
+    .bind(clean_text(request.recipient_name))
+    .bind(phone_number)
+    .bind(address_text)
+    .bind(clean_text(request.city))
+    .bind(clean_text(request.state))
+    .bind(latitude)
+    .bind(longitude)
+    .bind(clean_text(request.delivery_instructions))
+    .bind(make_default)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(address)
+}
+
+/// Deletes an address. When it was the default, the most recently added
+/// remaining address takes over.
+pub async fn delete_address(db: &Database, buyer_id: Uuid, address_id: Uuid) -> AppResult<()> {
+    let mut tx = db.pool.begin().await?;
+    let was_default: bool =
+        sqlx::query_scalar("DELETE FROM buyer_addresses WHERE id = $1 AND buyer_id = $2 RETURNING is_default")
+            .bind(address_id)
+            .bind(buyer_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Address not found".to_string()))?;
+
+    if was_default {
+        sqlx::query(
+            "UPDATE buyer_addresses SET is_default = TRUE WHERE id = \
+             (SELECT id FROM buyer_addresses WHERE buyer_id = $1 ORDER BY created_at DESC LIMIT 1)",
+        )
+        .bind(buyer_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
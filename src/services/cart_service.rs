@@ -0,0 +1,334 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        order_line_problem, AddCartItemRequest, Cart, CartLine, CartSubtotal, ConvertedLine, UpdateCartItemRequest,
+        CART_LINE_COLUMNS,
+    },
+    services::exchange_rate_service,
+    utils::{currency::Currency, geo},
+};
+
+#[derive(sqlx::FromRow)]
+struct CartProduct {
+    farmer_id: Uuid,
+    farm_id: Option<Uuid>,
+    unit: String,
+    status: String,
+    visibility: String,
+    min_order_qty: i32,
+    quantity_available: i32,
+}
+
+/// Lines of a buyer's cart, oldest first. With `lock`, the products are
+/// locked for update so their stock can be reserved.
+pub(crate) async fn cart_lines(conn: &mut PgConnection, buyer_id: Uuid, lock: bool) -> AppResult<Vec<CartLine>> {
+    let sql = format!(
+        "SELECT {} FROM cart_items c JOIN products p ON p.id = c.product_id \
+         WHERE c.buyer_id = $1 ORDER BY c.created_at, c.product_id{}",
+        CART_LINE_COLUMNS,
+        if lock { " FOR UPDATE OF p" } else { "" }
+    );
+    let mut lines = sqlx::query_as::<_, CartLine>(&sql).bind(buyer_id).fetch_all(conn).await?;
+
+    for line in &mut lines {
+        line.problem = order_line_problem(
+            &line.product_status,
+            line.quantity,
+            line.min_order_qty,
+            line.quantity_available,
+            &line.unit,
+        );
+    }
+    Ok(lines)
+}
+
+/// Groups cart lines the way checkout splits them into orders: one per
+/// farmer and currency, in order of first appearance.
+pub(crate) fn group_lines(lines: &[CartLine]) -> Vec<((Uuid, String), Vec<&CartLine>)> {
+    let mut groups: Vec<((Uuid, String), Vec<&CartLine>)> = Vec::new();
+    for line in lines {
+        let group = groups
+            .iter_mut()
+            .find(|((farmer_id, currency_code), _)| *farmer_id == line.farmer_id && *currency_code == line.currency_code);
+        match group {
+            Some((_, group)) => group.push(line),
+            None => groups.push(((line.farmer_id, line.currency_code.clone()), vec![line])),
+        }
+    }
+    groups
+}
+
+/// The buyer's cart. With `display`, amounts are also shown in that
+/// currency where an exchange rate is known.
+pub async fn get_cart(db: &Database, buyer_id: Uuid, display: Option<&Currency>) -> AppResult<Cart> {
+    let mut conn = db.pool.acquire().await?;
+    let mut items = cart_lines(&mut conn, buyer_id, false).await?;
+
+    let mut subtotals: Vec<CartSubtotal> = group_lines(&items)
+        .into_iter()
+        .map(|((farmer_id, currency_code), lines)| CartSubtotal {
+            farmer_id,
+            currency_code,
+            subtotal_cents: lines.iter().map(|line| line.line_total_cents).sum(),
+            converted_subtotal_cents: None,
+        })
+        .collect();
+    let can_checkout = !items.is_empty() && items.iter().all(|line| line.problem.is_none());
+
+    let mut display_total_cents = None;
+    if let Some(display) = display {
+        let rates = exchange_rate_service::rates(db).await?;
+        for line in &mut items {
+            line.converted = rates.rate(&line.currency_code, display.code).map(|rate| {
+                let convert = |amount| exchange_rate_service::apply(amount, rate, &line.currency_code, display.code);
+                ConvertedLine {
+                    currency_code: display.code.to_string(),
+                    exchange_rate: rate,
+                    unit_price_cents: convert(line.unit_price_cents),
+                    line_total_cents: convert(line.line_total_cents),
+                }
+            });
+        }
+        for subtotal in &mut subtotals {
+            subtotal.converted_subtotal_cents =
+                rates.convert(subtotal.subtotal_cents, &subtotal.currency_code, display.code);
+        }
+        display_total_cents = subtotals.iter().map(|subtotal| subtotal.converted_subtotal_cents).sum();
+    }
+
+    Ok(Cart {
+        items,
+        subtotals,
+        can_checkout,
+        display_currency_code: display.map(|display| display.code.to_string()),
+        display_total_cents,
+    })
+}
+
+async fn find_product(db: &Database, product_id: Uuid) -> AppResult<CartProduct> {
+    sqlx::query_as::<_, CartProduct>(
+        "SELECT farmer_id, farm_id, unit, status, visibility, min_order_qty, quantity_available \
+         FROM products WHERE id = $1",
+    )
+    .bind(product_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Product not found".to_string()))
+}
+
+/// Checks that `quantity` of the product can go in the buyer's cart.
+fn check_quantity(buyer_id: Uuid, product: &CartProduct, quantity: i32) -> AppResult<()> {
+    if product.farmer_id == buyer_id {
+        return Err(AppError::ValidationError("You can't order your own product".to_string()));
+    }
+    if quantity <= 0 {
+        return Err(AppError::ValidationError("Quantity must be positive".to_string()));
+    }
+    match order_line_problem(
+        &product.status,
+        quantity,
+        product.min_order_qty,
+        product.quantity_available,
+        &product.unit,
+    ) {
+        Some(problem) => Err(AppError::ValidationError(problem)),
+        None => Ok(()),
+    }
+}
+
+/// Checks a `local_only` product is offered to the buyer: one of their
+/// delivery addresses must be within the local radius of the farm's public
+/// grid point, as in product search.
+async fn check_local(db: &Database, buyer_id: Uuid, product: &CartProduct) -> AppResult<()> {
+    if product.visibility != "local_only" {
+        return Ok(());
+    }
+
+    let nearby: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM farms f JOIN buyer_addresses a ON a.buyer_id = $2 \
+         WHERE f.id = $1 AND a.latitude IS NOT NULL AND a.longitude IS NOT NULL \
+           AND ST_DWithin(ST_SnapToGrid(f.location, $4)::geography, \
+                          ST_SetSRID(ST_MakePoint(a.longitude, a.latitude), 4326)::geography, $3))",
+    )
+    .bind(product.farm_id)
+    .bind(buyer_id)
+    .bind(geo::config().local_radius_km * 1000.0)
+    .bind(geo::public_grid_degrees())
+    .fetch_one(&db.pool)
+    .await?;
+    if !nearby {
+        return Err(AppError::ValidationError(
+            "This product is only sold to buyers near the farm; add a delivery address close by".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn add_item(
+    db: &Database,
+    buyer_id: Uuid,
+    payload: AddCartItemRequest,
+    display: Option<&Currency>,
+) -> AppResult<Cart> {
+    let product = find_product(db, payload.product_id).await?;
+    let added = payload.quantity.unwrap_or(product.min_order_qty.max(1));
+    if added <= 0 {
+        return Err(AppError::ValidationError("Quantity must be positive".to_string()));
+    }
+    check_local(db, buyer_id, &product).await?;
+
+    // Add to whatever is in the cart in one statement so concurrent adds
+    // can't overwrite each other, then check the total before committing.
+    let mut tx = db.pool.begin().await?;
+    let quantity: i32 = sqlx::query_scalar(
+        "INSERT INTO cart_items (buyer_id, product_id, quantity) VALUES ($1, $2, $3) \
+         ON CONFLICT (buyer_id, product_id) DO UPDATE SET quantity = cart_items.quantity + EXCLUDED.quantity \
+         RETURNING quantity",
+    )
+    .bind(buyer_id)
+    .bind(payload.product_id)
+    .bind(added)
+    .fetch_one(&mut *tx)
+    .await?;
+    check_quantity(buyer_id, &product, quantity)?;
+    tx.commit().await?;
+
+    get_cart(db, buyer_id, display).await
+}
+
+pub async fn update_item(
+    db: &Database,
+    buyer_id: Uuid,
+    product_id: Uuid,
+    payload: UpdateCartItemRequest,
+    display: Option<&Currency>,
+) -> AppResult<Cart> {
+    let product = find_product(db, product_id).await?;
+    check_quantity(buyer_id, &product, payload.quantity)?;
+
+    let result = sqlx::query("UPDATE cart_items SET quantity = $3 WHERE buyer_id = $1 AND product_id = $2")
+        .bind(buyer_id)
+        .bind(product_id)
+        .bind(payload.quantity)
+        .execute(&db.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Product is not in the cart".to_string()));
+    }
+
+    get_cart(db, buyer_id, display).await
+}
+
+pub async fn remove_item(
+    db: &Database,
+    buyer_id: Uuid,
+    product_id: Uuid,
+    display: Option<&Currency>,
+) -> AppResult<Cart> {
+    let result = sqlx::query("DELETE FROM cart_items WHERE buyer_id = $1 AND product_id = $2")
+        .bind(buyer_id)
+        .bind(product_id)
+        .execute(&db.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Product is not in the cart".to_string()));
+    }
+
+    get_cart(db, buyer_id, display).await
+}
+
+pub async fn clear_cart(db: &Database, buyer_id: Uuid) -> AppResult<()> {
+    sqlx::query("DELETE FROM cart_items WHERE buyer_id = $1")
+        .bind(buyer_id)
+        .execute(&db.pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(farmer_id: Uuid, currency_code: &str, line_total_cents: i64) -> CartLine {
+        CartLine {
+            product_id: Uuid::new_v4(),
+            farmer_id,
+            farm_id: None,
+            name: "Maize".to_string(),
+            unit: "kg".to_string(),
+            quantity: 1,
+            unit_price_cents: line_total_cents,
+            line_total_cents,
+            currency_code: currency_code.to_string(),
+            min_order_qty: 1,
+            quantity_available: 10,
+            product_status: "published".to_string(),
+            problem: None,
+            converted: None,
+        }
+    }
+
+    fn product(farmer_id: Uuid) -> CartProduct {
+        CartProduct {
+            farmer_id,
+            farm_id: None,
+            unit: "kg".to_string(),
+            status: "published".to_string(),
+            visibility: "public".to_string(),
+            min_order_qty: 5,
+            quantity_available: 20,
+        }
+    }
+
+    #[test]
+    fn lines_are_grouped_by_farmer_and_currency_in_cart_order() {
+        let (ada, bola) = (Uuid::new_v4(), Uuid::new_v4());
+        let lines = [line(ada, "NGN", 100), line(bola, "NGN", 200), line(ada, "USD", 300), line(ada, "NGN", 400)];
+
+        let groups = group_lines(&lines);
+        let keys: Vec<_> = groups.iter().map(|(key, _)| key.clone()).collect();
+        assert_eq!(
+            keys,
+            [(ada, "NGN".to_string()), (bola, "NGN".to_string()), (ada, "USD".to_string())]
+        );
+        let totals: Vec<Vec<i64>> = groups
+            .iter()
+            .map(|(_, lines)| lines.iter().map(|line| line.line_total_cents).collect())
+            .collect();
+        assert_eq!(totals, [vec![100, 400], vec![200], vec![300]]);
+        assert!(group_lines(&[]).is_empty());
+    }
+
+    #[test]
+    fn quantity_must_be_orderable() {
+        let buyer_id = Uuid::new_v4();
+        let product = product(Uuid::new_v4());
+        assert!(check_quantity(buyer_id, &product, 5).is_ok());
+        assert!(check_quantity(buyer_id, &product, 20).is_ok());
+        for quantity in [0, -1, 4, 21] {
+            assert!(
+                matches!(check_quantity(buyer_id, &product, quantity), Err(AppError::ValidationError(_))),
+                "{}",
+                quantity
+            );
+        }
+    }
+
+    #[test]
+    fn farmers_cannot_order_their_own_products() {
+        let farmer_id = Uuid::new_v4();
+        let result = check_quantity(farmer_id, &product(farmer_id), 5);
+        assert!(matches!(result, Err(AppError::ValidationError(message)) if message.contains("own product")));
+    }
+
+    #[test]
+    fn unpublished_products_cannot_be_added() {
+        let product = CartProduct { status: "suspended".to_string(), ..product(Uuid::new_v4()) };
+        let result = check_quantity(Uuid::new_v4(), &product, 5);
+        assert!(matches!(result, Err(AppError::ValidationError(message)) if message.contains("no longer available")));
+    }
+}
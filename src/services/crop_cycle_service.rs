@@ -0,0 +1,382 @@
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        CreateCropCycleRequest, CropCycle, CropCycleOverview, CropCycleQuery, CropCycleSchedule, CropTemplate,
+        FarmActivity, ScheduledTask, UpdateCropCycleRequest, ACTIVITY_COLUMNS, CROP_CYCLE_COLUMNS,
+        CROP_CYCLE_STATUSES,
+    },
+    services::{farm_service, plot_service},
+    utils::{clean_text, one_of},
+};
+
+const TEMPLATE_COLUMNS: &str = "id, crop_name, growing_days, tasks";
+
+/// Starts a query for cycles with their task counts as of `today`; callers
+/// append further `AND` conditions.
+fn overview_query(farm_id: Uuid, today: NaiveDate) -> QueryBuilder<'static, Postgres> {
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        r#"
+        SELECT {}, p.* FROM crop_cycles, LATERAL (
+            SELECT
+                COUNT(*) AS total_tasks,
+                COUNT(*) FILTER (WHERE a.status = 'completed') AS completed_tasks,
+                COUNT(*) FILTER (WHERE a.status IN ('planned', 'in_progress') AND a.activity_date < "#,
+        CROP_CYCLE_COLUMNS
+    ));
+    qb.push_bind(today).push(
+        r#") AS overdue_tasks,
+                COUNT(*) FILTER (WHERE a.status IN ('planned', 'in_progress') AND a.activity_date >= "#,
+    );
+    qb.push_bind(today).push(
+        r#") AS upcoming_tasks,
+                COUNT(*) FILTER (WHERE a.status = 'cancelled') AS cancelled_tasks
+            FROM farm_activities a
+            WHERE a.crop_cycle_id = crop_cycles.id
+        ) p
+        WHERE farm_id = "#,
+    );
+    qb.push_bind(farm_id);
+    qb
+}
+
+fn clean_crop(crop_name: &str) -> AppResult<String> {
+    let crop_name = crop_name.trim();
+    if crop_name.is_empty() {
+        return Err(AppError::ValidationError("crop_name is required".to_string()));
+    }
+    Ok(crop_name.to_string())
+}
+
+fn validate_dates(planting_date: NaiveDate, expected_harvest_date: Option<NaiveDate>) -> AppResult<()> {
+    if expected_harvest_date.is_some_and(|harvest| harvest < planting_date) {
+        return Err(AppError::ValidationError(
+            "expected_harvest_date must be on or after planting_date".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the farm's spelling of a plot name, rejecting unknown plots.
+async fn canonical_plot(db: &Database, farm_id: Uuid, field_plot: Option<String>) -> AppResult<Option<String>> {
+    let Some(name) = clean_text(field_plot) else {
+        return Ok(None);
+    };
+    let plot = plot_service::find_plot_by_name(db, farm_id, &name)
+        .await?
+        .ok_or_else(|| AppError::ValidationError(format!("'{}' is not a plot on this farm", name)))?;
+    Ok(Some(plot.name))
+}
+
+pub async fn list_templates(db: &Database) -> AppResult<Vec<CropTemplate>> {
+    Ok(sqlx::query_as::<_, CropTemplate>(&format!(
+        "SELECT {} FROM crop_templates ORDER BY crop_name",
+        TEMPLATE_COLUMNS
+    ))
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+pub async fn find_template(db: &Database, crop_name: &str) -> AppResult<Option<CropTemplate>> {
+    Ok(sqlx::query_as::<_, CropTemplate>(&format!(
+        "SELECT {} FROM crop_templates WHERE lower(crop_name) = lower($1)",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(crop_name)
+    .fetch_optional(&db.pool)
+    .await?)
+}
+
+/// Looks up a cycle on a farm without checking who is asking.
+pub async fn find_cycle(db: &Database, farm_id: Uuid, cycle_id: Uuid) -> AppResult<Option<CropCycle>> {
+    Ok(sqlx::query_as::<_, CropCycle>(&format!(
+        "SELECT {} FROM crop_cycles WHERE id = $1 AND farm_id = $2",
+        CROP_CYCLE_COLUMNS
+    ))
+    .bind(cycle_id)
+    .bind(farm_id)
+    .fetch_optional(&db.pool)
+    .await?)
+}
+
+pub async fn list_cycles(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    query: &CropCycleQuery,
+) -> AppResult<Vec<CropCycleOverview>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    if let Some(status) = &query.status {
+        one_of("status", status, CROP_CYCLE_STATUSES)?;
+    }
+
+    let mut qb = overview_query(farm_id, Utc::now().date_naive());
+    if let Some(status) = &query.status {
+        qb.push(" AND status = ").push_bind(status.clone());
+    }
+    if let Some(crop) = query.crop.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        qb.push(" AND lower(crop_name) = lower(").push_bind(crop.to_string()).push(")");
+    }
+    qb.push(" ORDER BY planting_date DESC, created_at DESC");
+
+    Ok(qb.build_query_as::<CropCycleOverview>().fetch_all(&db.pool).await?)
+}
+
+pub async fn get_cycle(db: &Database, farmer_id: Uuid, farm_id: Uuid, cycle_id: Uuid) -> AppResult<CropCycleOverview> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let mut qb = overview_query(farm_id, Utc::now().date_naive());
+    qb.push(" AND id = ").push_bind(cycle_id);
+
+    qb.build_query_as::<CropCycleOverview>()
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Crop cycle not found".to_string()))
+}
+
+/// The cycle's tasks in date order, each flagged if overdue.
+pub async fn cycle_schedule(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    cycle_id: Uuid,
+) -> AppResult<CropCycleSchedule> {
+    let overview = get_cycle(db, farmer_id, farm_id, cycle_id).await?;
+    let today = Utc::now().date_naive();
+
+    let activities = sqlx::query_as::<_, FarmActivity>(&format!(
+        "SELECT {} FROM farm_activities WHERE crop_cycle_id = $1 ORDER BY activity_date, created_at",
+        ACTIVITY_COLUMNS
+    ))
+    .bind(cycle_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let tasks = activities
+        .into_iter()
+        .map(|activity| ScheduledTask {
+            overdue: matches!(activity.status.as_str(), "planned" | "in_progress") && activity.activity_date < today,
+            activity,
+        })
+        .collect();
+
+    Ok(CropCycleSchedule { overview, tasks })
+}
+
+/// Creates planned activities for `cycle` from `template`.
+async fn insert_schedule(conn: &mut PgConnection, cycle: &CropCycle, template: &CropTemplate) -> AppResult<()> {
+    for task in template.tasks.iter() {
+        let activity_date = match (task.activity_type.as_str(), cycle.expected_harvest_date) {
+            ("harvesting", Some(harvest)) => harvest,
+            _ => cycle.planting_date + Duration::days(task.offset_days.into()),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO farm_activities (
+                farmer_id, farm_id, crop_cycle_id, activity_type, description, activity_date,
+                status, crop_name, field_plot, expected_harvest_date)
+            VALUES ($1, $2, $3, $4, $5, $6, 'planned', $7, $8, $9)
+            "#,
+        )
+        .bind(cycle.farmer_id)
+        .bind(cycle.farm_id)
+        .bind(cycle.id)
+        .bind(&task.activity_type)
+        .bind(&task.description)
+        .bind(activity_date)
+        .bind(&cycle.crop_name)
+        .bind(&cycle.field_plot)
+        .bind(cycle.expected_harvest_date)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Starts a crop cycle, planning its tasks from the crop's template unless
+/// asked not to.
+pub async fn create_cycle(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    request: CreateCropCycleRequest,
+) -> AppResult<CropCycleSchedule> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    let crop_name = clean_crop(&request.crop_name)?;
+    let field_plot = canonical_plot(db, farm_id, request.field_plot).await?;
+
+    let template = match request.generate_schedule {
+        Some(false) => None,
+        Some(true) => Some(find_template(db, &crop_name).await?.ok_or_else(|| {
+            AppError::ValidationError(format!("There is no schedule template for {}", crop_name))
+        })?),
+        None => find_template(db, &crop_name).await?,
+    };
+
+    let expected_harvest_date = request.expected_harvest_date.or_else(|| {
+        template
+            .as_ref()
+            .map(|t| request.planting_date + Duration::days(t.growing_days.into()))
+    });
+    validate_dates(request.planting_date, expected_harvest_date)?;
+
+    let mut tx = db.pool.begin().await?;
+
+    let cycle = sqlx::query_as::<_, CropCycle>(&format!(
+        r#"
+        INSERT INTO crop_cycles (farmer_id, farm_id, crop_name, field_plot, planting_date, expected_harvest_date, notes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {}
+        "#,
+        CROP_CYCLE_COLUMNS
+    ))
+    .bind(farmer_id)
+    .bind(farm_id)
+    .bind(&crop_name)
+    .bind(&field_plot)
+    .bind(request.planting_date)
+    .bind(expected_harvest_date)
+    .bind(clean_text(request.notes))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(template) = &template {
+        insert_schedule(&mut tx, &cycle, template).await?;
+    }
+
+    tx.commit().await?;
+    cycle_schedule(db, farmer_id, farm_id, cycle.id).await
+}
+
+/// Updates a cycle and keeps its activities in step: still-planned tasks
+/// follow date changes, crop and plot changes are copied to the cycle's
+/// activities, and abandoning the cycle cancels its planned tasks.
+pub async fn update_cycle(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    cycle_id: Uuid,
+    request: UpdateCropCycleRequest,
+) -> AppResult<CropCycleOverview> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    let current = find_cycle(db, farm_id, cycle_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Crop cycle not found".to_string()))?;
+
+    let crop_name = match &request.crop_name {
+        Some(crop_name) => clean_crop(crop_name)?,
+        None => current.crop_name.clone(),
+    };
+    let field_plot = match request.field_plot {
+        Some(field_plot) => canonical_plot(db, farm_id, Some(field_plot)).await?,
+        None => current.field_plot.clone(),
+    };
+    let planting_date = request.planting_date.unwrap_or(current.planting_date);
+    let expected_harvest_date = request.expected_harvest_date.or(current.expected_harvest_date);
+    validate_dates(planting_date, expected_harvest_date)?;
+    let status = request.status.unwrap_or_else(|| current.status.clone());
+    one_of("status", &status, CROP_CYCLE_STATUSES)?;
+
+    let mut tx = db.pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE crop_cycles SET
+            crop_name = $2, field_plot = $3, planting_date = $4,
+            expected_harvest_date = $5, status = $6, notes = COALESCE($7, notes)
+        WHERE id = $1
+        "#,
+    )
+    .bind(cycle_id)
+    .bind(&crop_name)
+    .bind(&field_plot)
+    .bind(planting_date)
+    .bind(expected_harvest_date)
+    .bind(&status)
+    .bind(clean_text(request.notes))
+    .execute(&mut *tx)
+    .await?;
+
+    let shift_days = (planting_date - current.planting_date).num_days() as i32;
+    if shift_days != 0 {
+        sqlx::query(
+            r#"
+            UPDATE farm_activities SET activity_date = activity_date + $2
+            WHERE crop_cycle_id = $1 AND status = 'planned' AND activity_type <> 'harvesting'
+            "#,
+        )
+        .bind(cycle_id)
+        .bind(shift_days)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if expected_harvest_date != current.expected_harvest_date {
+        sqlx::query(
+            r#"
+            UPDATE farm_activities SET
+                expected_harvest_date = $2,
+                activity_date = CASE
+                    WHEN status = 'planned' AND activity_type = 'harvesting' THEN $2
+                    ELSE activity_date
+                END
+            WHERE crop_cycle_id = $1
+            "#,
+        )
+        .bind(cycle_id)
+        .bind(expected_harvest_date)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if crop_name != current.crop_name || field_plot != current.field_plot {
+        sqlx::query("UPDATE farm_activities SET crop_name = $2, field_plot = $3 WHERE crop_cycle_id = $1")
+            .bind(cycle_id)
+            .bind(&crop_name)
+            .bind(&field_plot)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if status == "abandoned" && current.status != "abandoned" {
+        sqlx::query("UPDATE farm_activities SET status = 'cancelled' WHERE crop_cycle_id = $1 AND status = 'planned'")
+            .bind(cycle_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    get_cycle(db, farmer_id, farm_id, cycle_id).await
+}
+
+/// Deletes a cycle along with its planned and cancelled tasks. Work already
+/// logged against it is kept and detached from the cycle.
+pub async fn delete_cycle(db: &Database, farmer_id: Uuid, farm_id: Uuid, cycle_id: Uuid) -> AppResult<()> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let mut tx = db.pool.begin().await?;
+
+    sqlx::query("DELETE FROM farm_activities WHERE crop_cycle_id = $1 AND farm_id = $2 AND status IN ('planned', 'cancelled')")
+        .bind(cycle_id)
+        .bind(farm_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let deleted = sqlx::query("DELETE FROM crop_cycles WHERE id = $1 AND farm_id = $2")
+        .bind(cycle_id)
+        .bind(farm_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Crop cycle not found".to_string()));
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
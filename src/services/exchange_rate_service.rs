@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{CreateExchangeRateRequest, DisplayCurrencyQuery, ExchangeRate, ExchangeRateQuery, EXCHANGE_RATE_COLUMNS},
+    utils::currency::{self, Currency},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// NUMERIC(24, 12) holds up to twelve integer digits.
+const MAX_RATE: f64 = 1e12;
+
+/// The exchange rates in force today, loaded for the duration of a request.
+#[derive(Debug)]
+pub struct RateTable {
+    rates: BTreeMap<(String, String), f64>,
+}
+
+impl RateTable {
+    /// A rate as quoted, or the inverse of the pair quoted the other way.
+    fn quoted(&self, from: &str, to: &str) -> Option<f64> {
+        if let Some(rate) = self.rates.get(&(from.to_string(), to.to_string())) {
+            return Some(*rate);
+        }
+        self.rates.get(&(to.to_string(), from.to_string())).map(|rate| 1.0 / rate)
+    }
+
+    /// Major units of `to` per major unit of `from`. Pairs without a rate of
+    /// their own are crossed through a currency both are quoted against.
+    pub fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        if let Some(rate) = self.quoted(from, to) {
+            return Some(rate);
+        }
+        let currencies: BTreeSet<&str> =
+            self.rates.keys().flat_map(|(base, quote)| [base.as_str(), quote.as_str()]).collect();
+        currencies
+            .into_iter()
+            .find_map(|via| Some(self.quoted(from, via)? * self.quoted(via, to)?))
+    }
+
+    /// Converts a minor-unit amount, or `None` if there is no rate.
+    pub fn convert(&self, amount: i64, from: &str, to: &str) -> Option<i64> {
+        self.rate(from, to).map(|rate| apply(amount, rate, from, to))
+    }
+}
+
+/// Converts a minor-unit amount at `rate`, allowing for the currencies'
+/// different minor units. Rounds half away from zero.
+pub fn apply(amount: i64, rate: f64, from: &str, to: &str) -> i64 {
+    let shift = currency::exponent(to) as i32 - currency::exponent(from) as i32;
+    (amount as f64 * rate * 10f64.powi(shift)).round() as i64
+}
+
+/// The currency a viewer asked to see prices in, if any.
+pub fn display_currency(query: &DisplayCurrencyQuery) -> AppResult<Option<&'static Currency>> {
+    query.currency.as_deref().map(currency::resolve).transpose()
+}
+
+/// The latest rate for each pair effective on `as_of`, today by default.
+pub async fn current_rates(db: &Database, as_of: Option<NaiveDate>) -> AppResult<Vec<ExchangeRate>> {
+    Ok(sqlx::query_as::<_, ExchangeRate>(&format!(
+        "SELECT DISTINCT ON (base_currency, quote_currency) {} FROM exchange_rates \
+         WHERE effective_date <= COALESCE($1, CURRENT_DATE) \
+         ORDER BY base_currency, quote_currency, effective_date DESC",
+        EXCHANGE_RATE_COLUMNS
+    ))
+    .bind(as_of)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+pub async fn rates(db: &Database) -> AppResult<RateTable> {
+    Ok(RateTable {
+        rates: current_rates(db, None)
+            .await?
+            .into_iter()
+            .map(|rate| ((rate.base_currency, rate.quote_currency), rate.rate))
+            .collect(),
+    })
+}
+
+/// Every rate on record, newest first.
+pub async fn list_exchange_rates(db: &Database, query: &ExchangeRateQuery) -> AppResult<Vec<ExchangeRate>> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let mut qb =
+        QueryBuilder::<Postgres>::new(format!("SELECT {} FROM exchange_rates WHERE TRUE", EXCHANGE_RATE_COLUMNS));
+    if let Some(code) = &query.currency {
+        let code = currency::resolve(code)?.code;
+        qb.push(" AND (base_currency = ").push_bind(code);
+        qb.push(" OR quote_currency = ").push_bind(code).push(")");
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND effective_date >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND effective_date <= ").push_bind(to);
+    }
+    qb.push(" ORDER BY effective_date DESC, base_currency, quote_currency LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(query.offset.unwrap_or(0).max(0));
+
+    Ok(qb.build_query_as::<ExchangeRate>().fetch_all(&db.pool).await?)
+}
+
+/// Records a rate, replacing the pair's rate for that day if there is one.
+/// Each pair is quoted one way round only, so the two can't disagree.
+pub async fn set_exchange_rate(
+    db: &Database,
+    staff_id: Uuid,
+    request: CreateExchangeRateRequest,
+) -> AppResult<ExchangeRate> {
+    let base = currency::resolve(&request.base_currency)?.code;
+    let quote = currency::resolve(&request.quote_currency)?.code;
+    if base == quote {
+        return Err(AppError::ValidationError("base_currency and quote_currency must differ".to_string()));
+    }
+    if !request.rate.is_finite() || request.rate <= 0.0 || request.rate >= MAX_RATE {
+        return Err(AppError::ValidationError("rate must be a positive number".to_string()));
+    }
+
+    let inverted: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM exchange_rates WHERE base_currency = $1 AND quote_currency = $2)",
+    )
+    .bind(quote)
+    .bind(base)
+    .fetch_one(&db.pool)
+    .await?;
+    if inverted {
+        return Err(AppError::ValidationError(format!(
+            "{}/{} is already quoted as {}/{}",
+            base, quote, quote, base
+        )));
+    }
+
+    Ok(sqlx::query_as::<_, ExchangeRate>(&format!(
+        "INSERT INTO exchange_rates (base_currency, quote_currency, rate, effective_date, created_by) \
+         VALUES ($1, $2, $3::NUMERIC, COALESCE($4, CURRENT_DATE), $5) \
+         ON CONFLICT (base_currency, quote_currency, effective_date) \
+         DO UPDATE SET rate = EXCLUDED.rate, created_by = EXCLUDED.created_by, created_at = NOW() \
+         RETURNING {}",
+        EXCHANGE_RATE_COLUMNS
+    ))
+    .bind(base)
+    .bind(quote)
+    .bind(request.rate)
+    .bind(request.effective_date)
+    .bind(staff_id)
+    .fetch_one(&db.pool)
+    .await?)
+}
+
+pub async fn delete_exchange_rate(db: &Database, rate_id: Uuid) -> AppResult<()> {
+    let deleted = sqlx::query("DELETE FROM exchange_rates WHERE id = $1")
+        .bind(rate_id)
+        .execute(&db.pool)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(AppError::NotFound("Exchange rate not found".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rates: &[(&str, &str, f64)]) -> RateTable {
+        RateTable {
+            rates: rates.iter().map(|(base, quote, rate)| ((base.to_string(), quote.to_string()), *rate)).collect(),
+        }
+    }
+
+    #[test]
+    fn apply_shifts_between_minor_units() {
+        assert_eq!(apply(100, 1500.0, "USD", "NGN"), 150_000);
+        assert_eq!(apply(100, 150.0, "USD", "JPY"), 150);
+        assert_eq!(apply(1_000, 0.3, "KWD", "USD"), 30);
+        assert_eq!(apply(150, 1.0 / 150.0, "JPY", "USD"), 100);
+    }
+
+    #[test]
+    fn apply_rounds_half_away_from_zero() {
+        assert_eq!(apply(1, 0.5, "USD", "EUR"), 1);
+        assert_eq!(apply(-1, 0.5, "USD", "EUR"), -1);
+        assert_eq!(apply(3, 0.5, "USD", "EUR"), 2);
+        assert_eq!(apply(1, 0.4, "USD", "EUR"), 0);
+    }
+
+    #[test]
+    fn rate_uses_quotes_and_their_inverse() {
+        let rates = table(&[("USD", "NGN", 1500.0)]);
+        assert_eq!(rates.rate("USD", "NGN"), Some(1500.0));
+        assert_eq!(rates.rate("NGN", "USD"), Some(1.0 / 1500.0));
+        assert_eq!(rates.rate("NGN", "NGN"), Some(1.0));
+        assert_eq!(rates.rate("USD", "GHS"), None);
+    }
+
+    #[test]
+    fn direct_quotes_win_over_inverses() {
+        let rates = table(&[("USD", "NGN", 1500.0), ("NGN", "USD", 0.0007)]);
+        assert_eq!(rates.rate("USD", "NGN"), Some(1500.0));
+        assert_eq!(rates.rate("NGN", "USD"), Some(0.0007));
+    }
+
+    #[test]
+    fn rate_crosses_through_a_shared_currency() {
+        let rates = table(&[("USD", "NGN", 1500.0), ("USD", "GHS", 15.0)]);
+        let rate = rates.rate("GHS", "NGN").unwrap();
+        assert!((rate - 100.0).abs() < 1e-9);
+        let rate = rates.rate("NGN", "GHS").unwrap();
+        assert!((rate - 0.01).abs() < 1e-12);
+        assert_eq!(rates.convert(250, "GHS", "NGN"), Some(25_000));
+        assert_eq!(rates.convert(250, "GHS", "KES"), None);
+    }
+}
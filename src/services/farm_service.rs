@@ -0,0 +1,359 @@
+use serde_json::Value;
+use sqlx::{types::Json, PgConnection, Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{CreateFarmRequest, Farm, FarmBoundary, FarmListing, FarmQuery, UpdateFarmRequest, FARM_COLUMNS},
+    utils::geo::{self, GeoPoint},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+const DEFAULT_FARM_TYPE: &str = "subsistence";
+
+fn validate_size(farm_size_hectares: Option<f64>) -> AppResult<()> {
+    match farm_size_hectares {
+        Some(size) if !(size > 0.0 && size.is_finite()) => Err(AppError::ValidationError(
+            "farm_size_hectares must be greater than 0".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_farm_type(farm_type: Option<&str>) -> AppResult<()> {
+    match farm_type {
+        Some(farm_type) if farm_type.trim().is_empty() => {
+            Err(AppError::ValidationError("farm_type cannot be empty".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Trims crop names and drops blanks and duplicates, keeping the first spelling.
+fn clean_crops(crops: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for crop in crops {
+        let crop = crop.trim();
+        if !crop.is_empty() && !cleaned.iter().any(|c| c.eq_ignore_ascii_case(crop)) {
+            cleaned.push(crop.to_string());
+        }
+    }
+    cleaned
+}
+
+/// Inserts a farm for `farmer_id`. Takes a connection so registration can
+/// create the farmer and their first farm in one transaction.
+pub async fn insert_farm(conn: &mut PgConnection, farmer_id: Uuid, request: CreateFarmRequest) -> AppResult<Farm> {
+    let point = GeoPoint::from_query(request.latitude, request.longitude)?;
+    validate_size(request.farm_size_hectares)?;
+    validate_farm_type(request.farm_type.as_deref())?;
+
+    let farm = sqlx::query_as::<_, Farm>(&format!(
+        r#"
+        INSERT INTO farms (farmer_id, farm_name, location, address_text, farm_size_hectares, farm_type, primary_crops)
+        VALUES ($1, $2, ST_SetSRID(ST_MakePoint($3, $4), 4326), $5, $6::NUMERIC, $7, $8)
+        RETURNING {}
+        "#,
+        FARM_COLUMNS
+    ))
+    .bind(farmer_id)
+    .bind(&request.farm_name)
+    .bind(point.map(|p| p.lng))
+    .bind(point.map(|p| p.lat))
+    .bind(&request.address_text)
+    .bind(request.farm_size_hectares)
+    .bind(request.farm_type.as_deref().map(str::trim).unwrap_or(DEFAULT_FARM_TYPE))
+    .bind(Json(clean_crops(request.primary_crops.unwrap_or_default())))
+    .fetch_one(conn)
+    .await?;
+
+    Ok(farm)
+}
+
+pub async fn create_farm(db: &Database, farmer_id: Uuid, request: CreateFarmRequest) -> AppResult<Farm> {
+    let mut conn = db.pool.acquire().await?;
+    insert_farm(&mut conn, farmer_id, request).await
+}
+
+pub async fn get_farm(db: &Database, farm_id: Uuid) -> AppResult<Farm> {
+    sqlx::query_as::<_, Farm>(&format!("SELECT {} FROM farms WHERE id = $1", FARM_COLUMNS))
+        .bind(farm_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Farm not found".to_string()))
+}
+
+/// Loads a farm for a write by `farmer_id`. Someone else's farm is reported
+/// as not found.
+pub async fn owned_farm(db: &Database, farmer_id: Uuid, farm_id: Uuid) -> AppResult<Farm> {
+    let farm = get_farm(db, farm_id).await?;
+    if farm.farmer_id != farmer_id {
+        return Err(AppError::NotFound("Farm not found".to_string()));
+    }
+    Ok(farm)
+}
+
+pub async fn list_farmer_farms(db: &Database, farmer_id: Uuid) -> AppResult<Vec<Farm>> {
+    let farms = sqlx::query_as::<_, Farm>(&format!(
+        "SELECT {} FROM farms WHERE farmer_id = $1 ORDER BY created_at, id",
+        FARM_COLUMNS
+    ))
+    .bind(farmer_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(farms)
+}
+
+/// The farm a new login starts on: the farmer's oldest farm, if any.
+pub async fn default_farm_id(db: &Database, farmer_id: Uuid) -> AppResult<Option<Uuid>> {
+    let farm_id = sqlx::query_scalar("SELECT id FROM farms WHERE farmer_id = $1 ORDER BY created_at, id LIMIT 1")
+        .bind(farmer_id)
+        .fetch_optional(&db.pool)
+        .await?;
+
+    Ok(farm_id)
+}
+
+pub async fn update_farm(db: &Database, farmer_id: Uuid, farm_id: Uuid, request: UpdateFarmRequest) -> AppResult<Farm> {
+    let point = GeoPoint::from_query(request.latitude, request.longitude)?;
+    validate_size(request.farm_size_hectares)?;
+    validate_farm_type(request.farm_type.as_deref())?;
+
+    owned_farm(db, farmer_id, farm_id).await?;
+
+    let farm = sqlx::query_as::<_, Farm>(&format!(
+        r#"
+        UPDATE farms SET
+            farm_name = COALESCE($3, farm_name),
+            location = COALESCE(ST_SetSRID(ST_MakePoint($4, $5), 4326), location),
+            address_text = COALESCE($6, address_text),
+            farm_size_hectares = COALESCE($7::NUMERIC, farm_size_hectares),
+            farm_type = COALESCE($8, farm_type),
+            primary_crops = COALESCE($9, primary_crops)
+        WHERE id = $1 AND farmer_id = $2
+        RETURNING {}
+        "#,
+        FARM_COLUMNS
+    ))
+    .bind(farm_id)
+    .bind(farmer_id)
+    .bind(&request.farm_name)
+    .bind(point.map(|p| p.lng))
+    .bind(point.map(|p| p.lat))
+    .bind(&request.address_text)
+    .bind(request.farm_size_hectares)
+    .bind(request.farm_type.as_deref().map(str::trim))
+    .bind(request.primary_crops.map(|crops| Json(clean_crops(crops))))
+    .fetch_one(&db.pool)
+    .await?;
+
+    Ok(farm)
+}
+
+/// Deletes a farm. Its products stay listed but lose their farm link.
+pub async fn delete_farm(db: &Database, farmer_id: Uuid, farm_id: Uuid) -> AppResult<()> {
+    let deleted = sqlx::query("DELETE FROM farms WHERE id = $1 AND farmer_id = $2")
+        .bind(farm_id)
+        .bind(farmer_id)
+        .execute(&db.pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Farm not found".to_string()));
+    }
+    Ok(())
+}
+
+/// SQL turning the GeoJSON bound at `param` (e.g. "$2") into the stored
+/// boundary type.
+pub fn boundary_from_geojson(param: &str) -> String {
+    format!("ST_Multi(ST_SetSRID(ST_GeomFromGeoJSON({}), 4326))", param)
+}
+
+/// Validates a client-supplied boundary, structurally and then topologically
+/// in PostGIS, and returns the GeoJSON geometry text to bind.
+pub async fn validate_boundary(db: &Database, boundary: &Value) -> AppResult<String> {
+    let geojson = geo::boundary_geojson(boundary)?;
+
+    let row = sqlx::query(
+        "SELECT ST_IsValid(g) AS valid, ST_IsValidReason(g) AS reason FROM (SELECT ST_GeomFromGeoJSON($1) AS g) s",
+    )
+    .bind(&geojson)
+    .fetch_one(&db.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db_err) => AppError::ValidationError(format!("Invalid boundary: {}", db_err.message())),
+        e => e.into(),
+    })?;
+
+    if !row.get::<bool, _>("valid") {
+        let reason: String = row.get("reason");
+        return Err(AppError::ValidationError(format!("Invalid boundary: {}", reason)));
+    }
+
+    Ok(geojson)
+}
+
+pub async fn farm_boundary(db: &Database, farmer_id: Uuid, farm_id: Uuid) -> AppResult<FarmBoundary> {
+    owned_farm(db, farmer_id, farm_id).await?;
+
+    let mut boundary = sqlx::query_as::<_, FarmBoundary>(
+        r#"
+        SELECT id AS farm_id,
+               ST_AsGeoJSON(boundary)::JSONB AS boundary,
+               boundary_area_hectares::FLOAT8 AS area_hectares,
+               farm_size_hectares::FLOAT8 AS reported_size_hectares
+        FROM farms
+        WHERE id = $1
+        "#,
+    )
+    .bind(farm_id)
+    .fetch_one(&db.pool)
+    .await?;
+
+    boundary.size_mismatch = geo::config().size_mismatch(boundary.area_hectares, boundary.reported_size_hectares);
+    Ok(boundary)
+}
+
+/// Replaces the farm boundary and recomputes its area. Farms without a pin
+/// get the boundary's centroid as their location. Mapped plots must still
+/// fit inside the new boundary.
+pub async fn set_farm_boundary(db: &Database, farmer_id: Uuid, farm_id: Uuid, boundary: &Value) -> AppResult<FarmBoundary> {
+    owned_farm(db, farmer_id, farm_id).await?;
+    let geojson = validate_boundary(db, boundary).await?;
+    let new_boundary = boundary_from_geojson("$2");
+
+    let outside: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT name FROM farm_plots WHERE farm_id = $1 AND boundary IS NOT NULL AND NOT ST_CoveredBy(boundary, {}) ORDER BY name",
+        new_boundary
+    ))
+    .bind(farm_id)
+    .bind(&geojson)
+    .fetch_all(&db.pool)
+    .await?;
+
+    if !outside.is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "These plots fall outside the new boundary: {}",
+            outside.join(", ")
+        )));
+    }
+
+    sqlx::query(&format!(
+        r#"
+        UPDATE farms SET
+            boundary = b.g,
+            boundary_area_hectares = ST_Area(b.g::geography) / 10000.0,
+            location = COALESCE(location, ST_Centroid(b.g))
+        FROM (SELECT {} AS g) b
+        WHERE farms.id = $1
+        "#,
+        new_boundary
+    ))
+    .bind(farm_id)
+    .bind(&geojson)
+    .execute(&db.pool)
+    .await?;
+
+    farm_boundary(db, farmer_id, farm_id).await
+}
+
+pub async fn clear_farm_boundary(db: &Database, farmer_id: Uuid, farm_id: Uuid) -> AppResult<()> {
+    owned_farm(db, farmer_id, farm_id).await?;
+
+    sqlx::query("UPDATE farms SET boundary = NULL, boundary_area_hectares = NULL WHERE id = $1")
+        .bind(farm_id)
+        .execute(&db.pool)
+        .await?;
+
+    Ok(())
+}
+
+/// A farm matched by `search_farms`, before it is made fit for the public.
+#[derive(sqlx::FromRow)]
+struct FarmMatch {
+    #[sqlx(flatten)]
+    farm: Farm,
+    distance_km: Option<f64>,
+}
+
+impl From<FarmMatch> for FarmListing {
+    fn from(FarmMatch { farm, distance_km }: FarmMatch) -> Self {
+        FarmListing {
+            distance_km: distance_km.map(f64::round),
+            ..public_farm(farm)
+        }
+    }
+}
+
+/// A farm as shown to anyone but its owner and the staff who may read it.
+pub fn public_farm(farm: Farm) -> FarmListing {
+    FarmListing {
+        id: farm.id,
+        farm_name: farm.farm_name,
+        latitude: farm.latitude.map(geo::coarsen),
+        longitude: farm.longitude.map(geo::coarsen),
+        farm_size_hectares: farm.farm_size_hectares,
+        boundary_area_hectares: farm.boundary_area_hectares,
+        farm_type: farm.farm_type,
+        primary_crops: farm.primary_crops,
+        created_at: farm.created_at,
+        distance_km: None,
+    }
+}
+
+/// Lists farms, nearest first when the query gives a location.
+pub async fn search_farms(db: &Database, query: &FarmQuery) -> AppResult<Vec<FarmListing>> {
+    let point = GeoPoint::from_query(query.lat, query.lng)?;
+    let radius_km = geo::config().radius_km(query.radius_km, point)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT {}, ", FARM_COLUMNS));
+    match point {
+        Some(point) => {
+            qb.push("ST_Distance(");
+            geo::push_public_location(&mut qb, "location");
+            qb.push(", ");
+            geo::push_point(&mut qb, point);
+            qb.push(") / 1000.0");
+        }
+        None => {
+            qb.push("NULL::FLOAT8");
+        }
+    }
+    qb.push(" AS distance_km FROM farms WHERE TRUE");
+
+    if let (Some(point), Some(radius_km)) = (point, radius_km) {
+        qb.push(" AND ST_DWithin(");
+        geo::push_public_location(&mut qb, "location");
+        qb.push(", ");
+        geo::push_point(&mut qb, point);
+        qb.push(", ").push_bind(radius_km * 1000.0).push(")");
+    }
+    if let Some(farm_type) = &query.farm_type {
+        qb.push(" AND farm_type = ").push_bind(farm_type.clone());
+    }
+    if let Some(crop) = &query.crop {
+        qb.push(" AND primary_crops ? ").push_bind(crop.clone());
+    }
+    if let Some(farmer_id) = query.farmer_id {
+        qb.push(" AND farmer_id = ").push_bind(farmer_id);
+    }
+
+    qb.push(if point.is_some() {
+        " ORDER BY distance_km ASC NULLS LAST, id"
+    } else {
+        " ORDER BY created_at DESC, id"
+    });
+    qb.push(" LIMIT ").push_bind(limit);
+
+    let matches = qb.build_query_as::<FarmMatch>().fetch_all(&db.pool).await?;
+    Ok(matches.into_iter().map(FarmListing::from).collect())
+}
@@ -1,23 +1,22 @@
-use actix_web::App;
-use sqlx::{Row, types::BigDecimal};
+use sqlx::Row;
 use uuid::Uuid;
-use chrono::{DateTime, Utc, Duration};
 
 use crate::{
     database::Database, 
     errors::{AppError, AppResult}, 
-    models::{CreateFarmerRequest, FarmerResponse, FarmerLogin, LoginResponse, VerifyPhoneRequest}, 
-    services::{self, sms_service}, 
-    utils::generate_otp
+    models::{CreateFarmerRequest, Farmer, FarmerResponse, FarmerLogin, FarmerSession, LoginResponse, OtpPurpose, VerifyPhoneRequest}, 
+    services::{farm_service, otp_service, sms_service::SmsProvider}, 
+    utils::phone
 };
 
-pub async fn create_farmer(db: &Database, request: CreateFarmerRequest) -> AppResult<FarmerResponse> {
+pub async fn create_farmer(db: &Database, sms: &dyn SmsProvider, request: CreateFarmerRequest, client_ip: Option<&str>) -> AppResult<FarmerResponse> {
     if request.phone_number.is_empty() {
         return Err(AppError::ValidationError("Phone number cannot be empty".to_string()));
     }
+    let phone_number = phone::normalize(&request.phone_number)?;
 
     let existing_farmer = sqlx::query("SELECT id FROM farmers WHERE phone_number = $1")
-        .bind(&request.phone_number)
+        .bind(&phone_number)
         .fetch_optional(&db.pool)
         .await?;
 
@@ -25,6 +24,8 @@ pub async fn create_farmer(db: &Database, request: CreateFarmerRequest) -> AppRe
         return Err(AppError::ValidationError("Phone number already registered".to_string()));
     }
 
+    otp_service::reserve_send(db, &phone_number, client_ip).await?;
+
     let farmer_id = Uuid::new_v4();
 
     let mut tx = db.pool.begin().await?;
@@ -35,7 +36,7 @@ pub async fn create_farmer(db: &Database, request: CreateFarmerRequest) -> AppRe
         "#,
     )
     .bind(farmer_id)
-    .bind(&request.phone_number)
+    .bind(&phone_number)
     .bind(&request.email)
     .bind(&request.first_name)
     .bind(&request.last_name)
@@ -44,36 +45,16 @@ pub async fn create_farmer(db: &Database, request: CreateFarmerRequest) -> AppRe
     .await?;
 
     if let Some(farm_data) = request.farm_data {
-        let location = if let (Some(lat), Some(lng)) = (farm_data.latitude, farm_data.longitude) {
-            Some(format!("POINT({} {})", lng, lat))
-        } else {
-            None
-        };
-
-        sqlx::query(
-            r#"
-            INSERT INTO farms (farmer_id, farm_name, location, address_text, farm_size_hectares, farm_type, primary_crops)
-            VALUES ($1, $2, ST_GeomFromText($3, 4326), $4, $5, $6, $7)
-            "#
-        )
-        .bind(farmer_id)
-        .bind(&farm_data.farm_name)
-        .bind(location)
-        .bind(&farm_data.address_text)
-        .bind(BigDecimal::try_from(farm_data.farm_size_hectares.unwrap()).unwrap())
-        .bind(farm_data.farm_type.unwrap_or_else(|| "subsistence".to_string()))
-        .bind(serde_json::to_value(farm_data.primary_crops.unwrap_or_default()).unwrap())
-        .execute(&mut *tx)
-        .await?;
+        farm_service::insert_farm(&mut tx, farmer_id, farm_data).await?;
     }
 
     tx.commit().await?;
 
-    send_otp(&db, &request.phone_number).await?;
+    otp_service::deliver_otp(db, sms, &phone_number, OtpPurpose::Registration, client_ip).await?;
 
     Ok(FarmerResponse {
         id: farmer_id,
-        phone_number: request.phone_number,
+        phone_number,
         email: request.email,
         first_name: request.first_name,
         last_name: request.last_name,
@@ -82,122 +63,126 @@ pub async fn create_farmer(db: &Database, request: CreateFarmerRequest) -> AppRe
     })
 }
 
-pub async fn send_otp(db: &Database, phone_number: &str) -> AppResult<()> {
-    let otp_code = generate_otp();
-    let expires_at = Utc::now() + Duration::minutes(30);
-
-    sqlx::query(
-        r#"
-        DELETE FROM phone_verifications WHERE phone_number = $1 AND expires_at < NOW()
-        "#,
-    )
-    .bind(phone_number)
-    .execute(&db.pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        INSERT INTO phone_verifications (phone_number, otp_code, expires_at)
-        VALUES ($1, $2, $3)
-        "#,
-    )
-    .bind(phone_number)
-    .bind(&otp_code)
-    .bind(expires_at)
-    .execute(&db.pool)
-    .await?;
-
-    let message = format!("Your otp code is {}", otp_code);
-    services::sms_service::send_sms_twilio(phone_number, &message)
-        .await
-        .unwrap();
+/// Checks the code and marks the farmer's phone as verified.
+pub async fn verify_phone_number(db: &Database, request: VerifyPhoneRequest, client_ip: Option<&str>) -> AppResult<()> {
+    let phone_number = otp_service::verify_otp(db, &request, client_ip).await?;
 
+    sqlx::query("UPDATE farmers SET verification_status = 'phone_verified' WHERE phone_number = $1")
+        .bind(&phone_number)
+        .execute(&db.pool)
+        .await?;
     Ok(())
 }
 
-pub async fn verify_phone_number(db: &Database, request: VerifyPhoneRequest) -> AppResult<bool> {
-    let row = sqlx::query(
-        r#"
-        SELECT id, verified, attempts, expires_at 
-        FROM phone_verifications 
-        WHERE phone_number = $1 AND otp_code = $2 
-        ORDER BY created_at DESC 
-        LIMIT 1
-        "#,
-    )
-    .bind(&request.phone_number)
-    .bind(&request.otp_code)
-    .fetch_optional(&db.pool)
-    .await?;
-
-    if let Some(row) = row {
-        let id: Uuid = row.get("id");
-        let verified: Option<bool> = row.get("verified");
-        let attempts: Option<i32> = row.get("attempts");
-        let expires_at: DateTime<Utc> = row.get("expires_at");
+/// Sends a code to a registered farmer. Registration codes are only issued
+/// while the phone is still unverified.
+pub async fn request_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    purpose: OtpPurpose,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    if phone_number.is_empty() {
+        return Err(AppError::ValidationError("No phone number provided".to_string()));
+    }
+    let phone_number = phone::normalize(phone_number)?;
 
-        if verified.unwrap_or(false) || attempts.unwrap_or(0) >= 3 || expires_at < Utc::now() {
-            return Ok(false);
-        }
+    let farmer = sqlx::query("SELECT verification_status FROM farmers WHERE phone_number = $1")
+        .bind(&phone_number)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::ValidationError("Farmer is not registered".to_string()))?;
 
-        let mut tx = db.pool.begin().await?;
+    let status: String = farmer.get("verification_status");
+    if purpose == OtpPurpose::Registration && status == "phone_verified" {
+        return Err(AppError::ValidationError("Phone number already verified, log in instead".to_string()));
+    }
 
-        sqlx::query("UPDATE phone_verifications SET verified = true WHERE id = $1")
-            .bind(id)
-            .execute(&mut *tx)
-            .await?;
+    otp_service::send_otp(db, sms, &phone_number, purpose, client_ip).await
+}
 
-        sqlx::query("UPDATE farmers SET verification_status = 'phone_verified' WHERE phone_number = $1")
-            .bind(&request.phone_number)
-            .execute(&mut *tx)
-            .await?;
+/// Sends a fresh code, reusing the purpose of the last code when none is given.
+/// The send cooldown stops this being used to spam a number.
+pub async fn resend_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    purpose: Option<OtpPurpose>,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let phone_number = phone::normalize(phone_number)?;
+    let purpose = match purpose {
+        Some(purpose) => purpose,
+        None => otp_service::last_purpose(db, &phone_number).await?,
+    };
+
+    request_otp(db, sms, &phone_number, purpose, client_ip).await
+}
 
-        tx.commit().await?;
-        return Ok(true);
-    }
+pub async fn send_login_otp(db: &Database, sms: &dyn SmsProvider, request: FarmerLogin, client_ip: Option<&str>) -> AppResult<bool> {
+    request_otp(db, sms, &request.phone_number, OtpPurpose::Login, client_ip).await?;
 
-    Ok(false)
+    Ok(true)
 }
 
-pub async fn send_login_otp(db: &Database, request: FarmerLogin) -> AppResult<bool> {
-    if request.phone_number.is_empty() {
-        return Err(AppError::ValidationError("No phone number provided".to_string()));
-    }
+pub async fn login_farmer_after_otp(db: &Database, request: VerifyPhoneRequest, client_ip: Option<&str>) -> AppResult<LoginResponse> {
+    verify_phone_number(db, request.clone(), client_ip).await?;
 
-    let exists = sqlx::query("SELECT id FROM farmers WHERE phone_number = $1")
-        .bind(&request.phone_number)
+    let row = sqlx::query("SELECT id, phone_number, email, first_name, last_name FROM farmers WHERE phone_number = $1")
+        .bind(phone::normalize(&request.phone_number)?)
         .fetch_optional(&db.pool)
-        .await?;
+        .await?
+        .ok_or_else(|| AppError::NotFound("Farmer is not registered".to_string()))?;
+
+    Ok(LoginResponse {
+        id: row.get("id"),
+        phone_number: row.get("phone_number"),
+        email: row.get("email"),
+        first_name: row.get("first_name"),
+        last_name: row.get("last_name"),
+    })
+}
 
-    if exists.is_none() {
-        return Err(AppError::ValidationError("Farmer is not registered".to_string()));
-    }
+/// Rebuilds the session payload for a farmer, e.g. when refreshing a token.
+pub async fn farmer_session(db: &Database, farmer_id: Uuid) -> AppResult<FarmerSession> {
+    let farmer = sqlx::query("SELECT id, first_name FROM farmers WHERE id = $1")
+        .bind(farmer_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Farmer no longer exists".to_string()))?;
+
+    Ok(FarmerSession {
+        farmer_id: farmer.get("id"),
+        farm_id: farm_service::default_farm_id(db, farmer_id).await?,
+        name: farmer.get("first_name"),
+    })
+}
 
-    send_otp(&db, &request.phone_number).await?;
+pub async fn farmer_session_for_phone(db: &Database, phone_number: &str) -> AppResult<FarmerSession> {
+    let farmer_id: Uuid = sqlx::query_scalar("SELECT id FROM farmers WHERE phone_number = $1")
+        .bind(phone::normalize(phone_number)?)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Farmer is not registered".to_string()))?;
 
-    Ok(true)
+    farmer_session(db, farmer_id).await
 }
 
-pub async fn login_farmer_after_otp(db: &Database, request: VerifyPhoneRequest) -> AppResult<LoginResponse> {
-    println!("logging after otp {:?}", request);
-    match verify_phone_number(&db, request.clone()).await {
-        Ok(true) => {
-            let row = sqlx::query("SELECT id, phone_number, email, first_name, last_name FROM farmers WHERE phone_number = $1")
-                .bind(&request.phone_number)
-                .fetch_one(&db.pool)
-                .await?;
-
-            Ok(LoginResponse {
-                id: row.get("id"),
-                phone_number: row.get("phone_number"),
-                email: row.get("email"),
-                first_name: row.get("first_name"),
-                last_name: row.get("last_name"),
-            })
-        }
-        _ => {
-            log::info!("Error logging in");
-            Err(AppError::ValidationError("Otp Error".to_string()))
-        }
-    }
+pub async fn farmer_profile(db: &Database, farmer_id: Uuid) -> AppResult<FarmerResponse> {
+    let farmer = sqlx::query_as::<_, Farmer>("SELECT * FROM farmers WHERE id = $1")
+        .bind(farmer_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Farmer not found".to_string()))?;
+
+    Ok(FarmerResponse {
+        id: farmer.id,
+        phone_number: farmer.phone_number,
+        email: farmer.email,
+        first_name: farmer.first_name,
+        last_name: farmer.last_name,
+        verification_status: farmer.verification_status,
+        profile_completed: farmer.profile_completed,
+    })
 }
@@ -0,0 +1,546 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        CreateLabourEntryRequest, CreateProductSaleRequest, CropPnl, FinanceQuery, LabourEntry, PnlLine, PnlQuery,
+        ProductSale, SeasonPnl, YieldTotal, LABOUR_ENTRY_COLUMNS, PRODUCT_SALE_COLUMNS,
+    },
+    services::{crop_cycle_service, farm_service, unit_service},
+    utils::{clean_text, currency_code},
+};
+
+fn validate_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> AppResult<()> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(AppError::ValidationError("from must be on or before to".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Appends the shared date-range and crop filters for `date_column`.
+fn push_filters(qb: &mut QueryBuilder<'_, Postgres>, date_column: &str, query: &FinanceQuery) {
+    if let Some(from) = query.from {
+        qb.push(format!(" AND {} >= ", date_column)).push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(format!(" AND {} <= ", date_column)).push_bind(to);
+    }
+    if let Some(crop) = query.crop.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        qb.push(" AND lower(crop_name) = lower(").push_bind(crop.to_string()).push(")");
+    }
+}
+
+/// The crop of a crop cycle on this farm.
+async fn cycle_crop(db: &Database, farm_id: Uuid, cycle_id: Uuid) -> AppResult<String> {
+    crop_cycle_service::find_cycle(db, farm_id, cycle_id)
+        .await?
+        .map(|cycle| cycle.crop_name)
+        .ok_or_else(|| AppError::ValidationError("Crop cycle not found on this farm".to_string()))
+}
+
+pub async fn list_labour(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    query: &FinanceQuery,
+) -> AppResult<Vec<LabourEntry>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    validate_range(query.from, query.to)?;
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM labour_entries WHERE farm_id = ",
+        LABOUR_ENTRY_COLUMNS
+    ));
+    qb.push_bind(farm_id);
+    push_filters(&mut qb, "work_date", query);
+    qb.push(" ORDER BY work_date DESC, created_at DESC");
+
+    Ok(qb.build_query_as::<LabourEntry>().fetch_all(&db.pool).await?)
+}
+
+pub async fn create_labour(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    request: CreateLabourEntryRequest,
+) -> AppResult<LabourEntry> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    if request.description.trim().is_empty() {
+        return Err(AppError::ValidationError("Description is required".to_string()));
+    }
+    if request.cost_cents < 0 {
+        return Err(AppError::ValidationError("cost_cents must be >= 0".to_string()));
+    }
+    let workers = request.workers.unwrap_or(1);
+    if workers < 1 {
+        return Err(AppError::ValidationError("workers must be at least 1".to_string()));
+    }
+    if request.hours.is_some_and(|hours| !(hours > 0.0 && hours.is_finite())) {
+        return Err(AppError::ValidationError("hours must be greater than 0".to_string()));
+    }
+    let currency_code = currency_code(request.currency_code)?;
+
+    let mut crop_name = clean_text(request.crop_name);
+    let mut crop_cycle_id = request.crop_cycle_id;
+    if let Some(activity_id) = request.activity_id {
+        let activity: Option<(Option<String>, Option<Uuid>)> =
+            sqlx::query_as("SELECT crop_name, crop_cycle_id FROM farm_activities WHERE id = $1 AND farm_id = $2")
+                .bind(activity_id)
+                .bind(farm_id)
+                .fetch_optional(&db.pool)
+                .await?;
+        let (activity_crop, activity_cycle) =
+            activity.ok_or_else(|| AppError::ValidationError("Activity not found on this farm".to_string()))?;
+        crop_name = crop_name.or(activity_crop);
+        crop_cycle_id = crop_cycle_id.or(activity_cycle);
+    }
+    if let Some(cycle_id) = crop_cycle_id {
+        let cycle_crop = cycle_crop(db, farm_id, cycle_id).await?;
+        crop_name.get_or_insert(cycle_crop);
+    }
+
+    let entry = sqlx::query_as::<_, LabourEntry>(&format!(
+        r#"
+        INSERT INTO labour_entries (
+            farm_id, crop_cycle_id, activity_id, crop_name, work_date, description,
+            workers, hours, cost_cents, currency_code, notes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        LABOUR_ENTRY_COLUMNS
+    ))
+    .bind(farm_id)
+    .bind(crop_cycle_id)
+    .bind(request.activity_id)
+    .bind(&crop_name)
+    .bind(request.work_date)
+    .bind(request.description.trim())
+    .bind(workers)
+    .bind(request.hours)
+    .bind(request.cost_cents)
+    .bind(&currency_code)
+    .bind(clean_text(request.notes))
+    .fetch_one(&db.pool)
+    .await?;
+
+    Ok(entry)
+}
+
+pub async fn delete_labour(db: &Database, farmer_id: Uuid, farm_id: Uuid, entry_id: Uuid) -> AppResult<()> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let deleted = sqlx::query("DELETE FROM labour_entries WHERE id = $1 AND farm_id = $2")
+        .bind(entry_id)
+        .bind(farm_id)
+        .execute(&db.pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Labour entry not found".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn list_sales(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    query: &FinanceQuery,
+) -> AppResult<Vec<ProductSale>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    validate_range(query.from, query.to)?;
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM product_sales WHERE farm_id = ",
+        PRODUCT_SALE_COLUMNS
+    ));
+    qb.push_bind(farm_id);
+    push_filters(&mut qb, "sold_on", query);
+    qb.push(" ORDER BY sold_on DESC, created_at DESC");
+
+    Ok(qb.build_query_as::<ProductSale>().fetch_all(&db.pool).await?)
+}
+
+pub async fn create_sale(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    request: CreateProductSaleRequest,
+) -> AppResult<ProductSale> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    if !(request.quantity > 0.0 && request.quantity.is_finite()) {
+        return Err(AppError::ValidationError("Quantity must be greater than 0".to_string()));
+    }
+    if request.amount_cents < 0 {
+        return Err(AppError::ValidationError("amount_cents must be >= 0".to_string()));
+    }
+
+    let product: Option<(String, String, String)> = match request.product_id {
+        Some(product_id) => Some(
+            sqlx::query_as("SELECT name, unit, currency_code FROM products WHERE id = $1 AND farm_id = $2")
+                .bind(product_id)
+                .bind(farm_id)
+                .fetch_optional(&db.pool)
+                .await?
+                .ok_or_else(|| AppError::ValidationError("Product not found on this farm".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let unit = clean_text(request.unit)
+        .or_else(|| product.as_ref().map(|(_, unit, _)| unit.clone()))
+        .ok_or_else(|| AppError::ValidationError("Unit is required".to_string()))?;
+    let unit = unit_service::registry(db).await?.canonical(&unit)?;
+    let currency_code = currency_code(
+        request
+            .currency_code
+            .or_else(|| product.as_ref().map(|(_, _, currency)| currency.clone())),
+    )?;
+
+    let mut crop_name = clean_text(request.crop_name);
+    if let Some(cycle_id) = request.crop_cycle_id {
+        let cycle_crop = cycle_crop(db, farm_id, cycle_id).await?;
+        crop_name.get_or_insert(cycle_crop);
+    }
+    if let Some((product_name, _, _)) = product {
+        crop_name.get_or_insert(product_name);
+    }
+
+    let sale = sqlx::query_as::<_, ProductSale>(&format!(
+        r#"
+        INSERT INTO product_sales (
+            farm_id, product_id, crop_cycle_id, crop_name, quantity, unit,
+            amount_cents, currency_code, sold_on, buyer_name, notes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        PRODUCT_SALE_COLUMNS
+    ))
+    .bind(farm_id)
+    .bind(request.product_id)
+    .bind(request.crop_cycle_id)
+    .bind(&crop_name)
+    .bind(request.quantity)
+    .bind(&unit)
+    .bind(request.amount_cents)
+    .bind(&currency_code)
+    .bind(request.sold_on)
+    .bind(clean_text(request.buyer_name))
+    .bind(clean_text(request.notes))
+    .fetch_one(&db.pool)
+    .await?;
+
+    Ok(sale)
+}
+
+pub async fn delete_sale(db: &Database, farmer_id: Uuid, farm_id: Uuid, sale_id: Uuid) -> AppResult<()> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let deleted = sqlx::query("DELETE FROM product_sales WHERE id = $1 AND farm_id = $2")
+        .bind(sale_id)
+        .bind(farm_id)
+        .execute(&db.pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Sale not found".to_string()));
+    }
+    Ok(())
+}
+
+impl PnlLine {
+    /// Fills in the derived totals once costs, revenue and area are known.
+    fn finish(mut self) -> Self {
+        self.total_cost_cents = self.input_cost_cents + self.labour_cost_cents;
+        self.margin_cents = self.revenue_cents - self.total_cost_cents;
+        self.margin_per_hectare_cents = self
+            .area_hectares
+            .filter(|area| *area > 0.0)
+            .map(|area| (self.margin_cents as f64 / area).round() as i64);
+        self
+    }
+}
+
+/// Per-crop amounts from one of the report queries, keyed by crop name.
+type CropAmounts = Vec<(Option<String>, i64)>;
+
+/// The report line for a crop, matched case-insensitively.
+fn crop_entry(crops: &mut BTreeMap<Option<String>, CropPnl>, name: Option<String>) -> &mut CropPnl {
+    crops.entry(name.as_deref().map(str::to_lowercase)).or_insert_with(|| CropPnl {
+        crop_name: name,
+        pnl: PnlLine::default(),
+        yields: Vec::new(),
+    })
+}
+
+/// Season profit and loss for a farm, overall and per crop.
+///
+/// Input cost is the average-cost value of stock drawn down by activities;
+/// labour comes from recorded labour entries, and revenue from recorded sales
+/// and completed marketplace orders, the latter under the product's name.
+/// Yield is what completed harvest activities measured, converted to the requested
+/// unit where possible. Everything is attributed to the crop it was recorded
+/// against and dated within the season.
+pub async fn season_pnl(db: &Database, farmer_id: Uuid, farm_id: Uuid, query: &PnlQuery) -> AppResult<SeasonPnl> {
+    let farm = farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let year = Utc::now().date_naive().year();
+    let from = query.from.unwrap_or_else(|| NaiveDate::from_ymd_opt(year, 1, 1).expect("valid date"));
+    let to = query.to.unwrap_or_else(|| NaiveDate::from_ymd_opt(year, 12, 31).expect("valid date"));
+    validate_range(Some(from), Some(to))?;
+    let currency_code = currency_code(query.currency_code.clone())?;
+    let units = unit_service::registry(db).await?;
+    let target_unit = query.unit.as_deref().map(|unit| units.canonical(unit)).transpose()?;
+
+    let input_costs: CropAmounts = sqlx::query_as(
+        r#"
+        SELECT MIN(a.crop_name), SUM(t.cost_cents)::BIGINT
+        FROM input_transactions t
+        JOIN farm_inputs i ON i.id = t.input_id
+        JOIN farm_activities a ON a.id = t.activity_id
+        WHERE t.farm_id = $1 AND t.kind = 'usage'
+          AND t.occurred_on BETWEEN $2 AND $3 AND i.currency_code = $4
+        GROUP BY lower(a.crop_name)
+        "#,
+    )
+    .bind(farm_id)
+    .bind(from)
+    .bind(to)
+    .bind(&currency_code)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let labour_costs: CropAmounts = sqlx::query_as(
+        r#"
+        SELECT MIN(crop_name), SUM(cost_cents)::BIGINT FROM labour_entries
+        WHERE farm_id = $1 AND work_date BETWEEN $2 AND $3 AND currency_code = $4
+        GROUP BY lower(crop_name)
+        "#,
+    )
+    .bind(farm_id)
+    .bind(from)
+    .bind(to)
+    .bind(&currency_code)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let revenue: CropAmounts = sqlx::query_as(
+        r#"
+        SELECT MIN(crop_name), SUM(amount_cents)::BIGINT FROM product_sales
+        WHERE farm_id = $1 AND sold_on BETWEEN $2 AND $3 AND currency_code = $4
+        GROUP BY lower(crop_name)
+        "#,
+    )
+    .bind(farm_id)
+    .bind(from)
+    .bind(to)
+    .bind(&currency_code)
+    .fetch_all(&db.pool)
+    .await?;
+
+    // Marketplace orders for this farm's listings count once the buyer
+    // confirms them complete, at the order price. Like a sale recorded
+    // against a product, each line goes to the crop named after the product.
+    let order_revenue: CropAmounts = sqlx::query_as(
+        r#"
+        SELECT MIN(i.product_name), SUM(i.line_total_cents)::BIGINT
+        FROM orders o
+        JOIN order_items i ON i.order_id = o.id
+        JOIN products p ON p.id = i.product_id
+        WHERE p.farm_id = $1 AND o.status = 'completed' AND o.currency_code = $4
+          AND (SELECT MAX(e.created_at) FROM order_events e
+               WHERE e.order_id = o.id AND e.to_status = 'completed')::DATE BETWEEN $2 AND $3
+        GROUP BY lower(i.product_name)
+        "#,
+    )
+    .bind(farm_id)
+    .bind(from)
+    .bind(to)
+    .bind(&currency_code)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let yields: Vec<(Option<String>, String, f64)> = sqlx::query_as(
+        r#"
+        SELECT MIN(crop_name), MIN(unit_measured), SUM(quantity_measured)
+        FROM farm_activities
+        WHERE farm_id = $1 AND activity_type = 'harvesting' AND status = 'completed'
+          AND quantity_measured IS NOT NULL AND unit_measured IS NOT NULL
+          AND activity_date BETWEEN $2 AND $3
+        GROUP BY lower(crop_name), lower(unit_measured)
+        ORDER BY MIN(unit_measured)
+        "#,
+    )
+    .bind(farm_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db.pool)
+    .await?;
+
+    // Area of the plots used by each crop's cycles during the season.
+    let areas: Vec<(String, Option<f64>)> = sqlx::query_as(
+        r#"
+        SELECT MIN(c.crop_name), SUM(p.area_hectares)::FLOAT8
+        FROM (
+            SELECT DISTINCT crop_name, field_plot FROM crop_cycles
+            WHERE farm_id = $1 AND status <> 'abandoned'
+              AND planting_date <= $3 AND COALESCE(expected_harvest_date, planting_date) >= $2
+        ) c
+        LEFT JOIN farm_plots p ON p.farm_id = $1 AND lower(p.name) = lower(c.field_plot)
+        GROUP BY lower(c.crop_name)
+        "#,
+    )
+    .bind(farm_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let mut crops: BTreeMap<Option<String>, CropPnl> = BTreeMap::new();
+    add_amounts(&mut crops, input_costs, labour_costs, revenue.into_iter().chain(order_revenue).collect());
+    for (name, unit, quantity) in yields {
+        let (quantity, unit) = match target_unit
+            .as_deref()
+            .and_then(|target| Some((units.convert(quantity, &unit, target)?, target.to_string())))
+        {
+            Some(converted) => converted,
+            None => (quantity, unit),
+        };
+        let crop = crop_entry(&mut crops, name);
+        match crop.yields.iter_mut().find(|y| y.unit == unit) {
+            Some(total) => total.quantity += quantity,
+            None => crop.yields.push(YieldTotal { quantity, unit }),
+        }
+    }
+    for (name, area) in areas {
+        crop_entry(&mut crops, Some(name)).pnl.area_hectares = area;
+    }
+
+    let (totals, crops) = finish_crops(crops, farm.boundary_area_hectares.or(farm.farm_size_hectares));
+    Ok(SeasonPnl {
+        farm_id,
+        from,
+        to,
+        currency_code,
+        totals,
+        crops,
+    })
+}
+
+/// Adds the report queries' amounts to the crop lines.
+fn add_amounts(
+    crops: &mut BTreeMap<Option<String>, CropPnl>,
+    input_costs: CropAmounts,
+    labour_costs: CropAmounts,
+    revenue: CropAmounts,
+) {
+    for (name, cents) in input_costs {
+        crop_entry(crops, name).pnl.input_cost_cents += cents;
+    }
+    for (name, cents) in labour_costs {
+        crop_entry(crops, name).pnl.labour_cost_cents += cents;
+    }
+    for (name, cents) in revenue {
+        crop_entry(crops, name).pnl.revenue_cents += cents;
+    }
+}
+
+/// Finishes the crop lines and sums them into farm totals over
+/// `area_hectares`.
+fn finish_crops(crops: BTreeMap<Option<String>, CropPnl>, area_hectares: Option<f64>) -> (PnlLine, Vec<CropPnl>) {
+    let mut totals = PnlLine {
+        area_hectares,
+        ..PnlLine::default()
+    };
+    // Unassigned amounts (crop_name NULL) sort first in the map; list them last.
+    let (unassigned, named): (Vec<_>, Vec<_>) = crops.into_values().partition(|c| c.crop_name.is_none());
+    let crops: Vec<CropPnl> = named
+        .into_iter()
+        .chain(unassigned)
+        .map(|mut c| {
+            totals.input_cost_cents += c.pnl.input_cost_cents;
+            totals.labour_cost_cents += c.pnl.labour_cost_cents;
+            totals.revenue_cents += c.pnl.revenue_cents;
+            c.pnl = c.pnl.finish();
+            c
+        })
+        .collect();
+    (totals.finish(), crops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amounts(rows: &[(Option<&str>, i64)]) -> CropAmounts {
+        rows.iter().map(|(name, cents)| (name.map(str::to_string), *cents)).collect()
+    }
+
+    #[test]
+    fn finish_derives_cost_margin_and_margin_per_hectare() {
+        let line = PnlLine {
+            input_cost_cents: 30_000,
+            labour_cost_cents: 20_000,
+            revenue_cents: 80_000,
+            area_hectares: Some(2.5),
+            ..PnlLine::default()
+        }
+        .finish();
+        assert_eq!(line.total_cost_cents, 50_000);
+        assert_eq!(line.margin_cents, 30_000);
+        assert_eq!(line.margin_per_hectare_cents, Some(12_000));
+
+        let loss = PnlLine { input_cost_cents: 1_000, area_hectares: Some(3.0), ..PnlLine::default() }.finish();
+        assert_eq!(loss.margin_cents, -1_000);
+        assert_eq!(loss.margin_per_hectare_cents, Some(-333));
+    }
+
+    #[test]
+    fn finish_leaves_margin_per_hectare_out_without_an_area() {
+        for area_hectares in [None, Some(0.0)] {
+            let line = PnlLine { revenue_cents: 5_000, area_hectares, ..PnlLine::default() }.finish();
+            assert_eq!(line.margin_cents, 5_000);
+            assert_eq!(line.margin_per_hectare_cents, None);
+        }
+    }
+
+    #[test]
+    fn marketplace_revenue_merges_with_sales_of_the_same_crop() {
+        let mut crops = BTreeMap::new();
+        let sales = amounts(&[(Some("Maize"), 10_000), (None, 500)]);
+        let orders = amounts(&[(Some("maize"), 4_000), (Some("Cassava"), 2_500)]);
+        add_amounts(
+            &mut crops,
+            amounts(&[(Some("MAIZE"), 3_000), (None, 700)]),
+            amounts(&[(Some("Cassava"), 1_000)]),
+            sales.into_iter().chain(orders).collect(),
+        );
+
+        let (totals, crops) = finish_crops(crops, Some(2.0));
+        let lines: Vec<_> = crops
+            .iter()
+            .map(|c| (c.crop_name.as_deref(), c.pnl.revenue_cents, c.pnl.total_cost_cents, c.pnl.margin_cents))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (Some("Cassava"), 2_500, 1_000, 1_500),
+                (Some("MAIZE"), 14_000, 3_000, 11_000),
+                (None, 500, 700, -200),
+            ]
+        );
+        assert_eq!(totals.revenue_cents, 17_000);
+        assert_eq!(totals.total_cost_cents, 4_700);
+        assert_eq!(totals.margin_cents, 12_300);
+        assert_eq!(totals.margin_per_hectare_cents, Some(6_150));
+    }
+}
@@ -0,0 +1,392 @@
+use chrono::Utc;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        CreateFarmInputRequest, FarmActivity, FarmInput, FarmInputQuery, InputLedgerQuery, InputTransaction,
+        RecordPurchaseRequest, UpdateFarmInputRequest, FARM_INPUT_COLUMNS, INPUT_CATEGORIES,
+        INPUT_TRANSACTION_COLUMNS, INPUT_TRANSACTION_KINDS,
+    },
+    services::{
+        farm_service,
+        unit_service::{self, UnitRegistry},
+    },
+    utils::{clean_text, currency_code, one_of},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Activity statuses that mean the inputs have actually been used.
+const CONSUMING_STATUSES: &[&str] = &["in_progress", "completed"];
+
+/// Slack for floating point error when comparing quantities.
+const QUANTITY_EPSILON: f64 = 1e-9;
+
+fn clean_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::ValidationError("Input name is required".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_reorder_level(reorder_level: Option<f64>) -> AppResult<()> {
+    match reorder_level {
+        Some(level) if !(level >= 0.0 && level.is_finite()) => {
+            Err(AppError::ValidationError("reorder_level must be >= 0".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn map_duplicate_name(err: sqlx::Error, name: Option<&str>) -> AppError {
+    match &err {
+        sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("23505") => AppError::ValidationError(
+            format!("An input named '{}' already exists on this farm", name.unwrap_or_default()),
+        ),
+        _ => err.into(),
+    }
+}
+
+pub async fn list_inputs(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    query: &FarmInputQuery,
+) -> AppResult<Vec<FarmInput>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    if let Some(category) = &query.category {
+        one_of("category", category, INPUT_CATEGORIES)?;
+    }
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "SELECT * FROM (SELECT {} FROM farm_inputs) i WHERE farm_id = ",
+        FARM_INPUT_COLUMNS
+    ));
+    qb.push_bind(farm_id);
+    if let Some(category) = &query.category {
+        qb.push(" AND category = ").push_bind(category.clone());
+    }
+    if let Some(low_stock) = query.low_stock {
+        qb.push(" AND low_stock = ").push_bind(low_stock);
+    }
+    qb.push(" ORDER BY lower(name)");
+
+    Ok(qb.build_query_as::<FarmInput>().fetch_all(&db.pool).await?)
+}
+
+pub async fn get_input(db: &Database, farmer_id: Uuid, farm_id: Uuid, input_id: Uuid) -> AppResult<FarmInput> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    sqlx::query_as::<_, FarmInput>(&format!(
+        "SELECT {} FROM farm_inputs WHERE id = $1 AND farm_id = $2",
+        FARM_INPUT_COLUMNS
+    ))
+    .bind(input_id)
+    .bind(farm_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Input not found".to_string()))
+}
+
+pub async fn create_input(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    request: CreateFarmInputRequest,
+) -> AppResult<FarmInput> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let name = clean_name(&request.name)?;
+    let category = request.category.unwrap_or_else(|| "other".to_string());
+    one_of("category", &category, INPUT_CATEGORIES)?;
+    let unit = unit_service::registry(db).await?.canonical(&request.unit)?;
+    let currency_code = currency_code(request.currency_code)?;
+    validate_reorder_level(request.reorder_level)?;
+
+    sqlx::query_as::<_, FarmInput>(&format!(
+        r#"
+        INSERT INTO farm_inputs (farm_id, name, category, unit, currency_code, reorder_level, notes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {}
+        "#,
+        FARM_INPUT_COLUMNS
+    ))
+    .bind(farm_id)
+    .bind(&name)
+    .bind(&category)
+    .bind(&unit)
+    .bind(&currency_code)
+    .bind(request.reorder_level)
+    .bind(clean_text(request.notes))
+    .fetch_one(&db.pool)
+    .await
+    .map_err(|e| map_duplicate_name(e, Some(&name)))
+}
+
+pub async fn update_input(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    input_id: Uuid,
+    request: UpdateFarmInputRequest,
+) -> AppResult<FarmInput> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let name = request.name.as_deref().map(clean_name).transpose()?;
+    if let Some(category) = &request.category {
+        one_of("category", category, INPUT_CATEGORIES)?;
+    }
+    validate_reorder_level(request.reorder_level)?;
+
+    sqlx::query_as::<_, FarmInput>(&format!(
+        r#"
+        UPDATE farm_inputs SET
+            name = COALESCE($3, name),
+            category = COALESCE($4, category),
+            reorder_level = COALESCE($5, reorder_level),
+            notes = COALESCE($6, notes)
+        WHERE id = $1 AND farm_id = $2
+        RETURNING {}
+        "#,
+        FARM_INPUT_COLUMNS
+    ))
+    .bind(input_id)
+    .bind(farm_id)
+    .bind(&name)
+    .bind(&request.category)
+    .bind(request.reorder_level)
+    .bind(clean_text(request.notes))
+    .fetch_optional(&db.pool)
+    .await
+    .map_err(|e| map_duplicate_name(e, name.as_deref()))?
+    .ok_or_else(|| AppError::NotFound("Input not found".to_string()))
+}
+
+/// Deletes an input and its ledger.
+pub async fn delete_input(db: &Database, farmer_id: Uuid, farm_id: Uuid, input_id: Uuid) -> AppResult<()> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let deleted = sqlx::query("DELETE FROM farm_inputs WHERE id = $1 AND farm_id = $2")
+        .bind(input_id)
+        .bind(farm_id)
+        .execute(&db.pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Input not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Adds purchased stock at its cost.
+pub async fn record_purchase(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    input_id: Uuid,
+    request: RecordPurchaseRequest,
+) -> AppResult<FarmInput> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    if !(request.quantity > 0.0 && request.quantity.is_finite()) {
+        return Err(AppError::ValidationError("Quantity must be greater than 0".to_string()));
+    }
+    if request.cost_cents < 0 {
+        return Err(AppError::ValidationError("cost_cents must be >= 0".to_string()));
+    }
+
+    let mut tx = db.pool.begin().await?;
+
+    let input = sqlx::query_as::<_, FarmInput>(&format!(
+        r#"
+        UPDATE farm_inputs SET
+            quantity_on_hand = quantity_on_hand + $3,
+            stock_value_cents = stock_value_cents + $4
+        WHERE id = $1 AND farm_id = $2
+        RETURNING {}
+        "#,
+        FARM_INPUT_COLUMNS
+    ))
+    .bind(input_id)
+    .bind(farm_id)
+    .bind(request.quantity)
+    .bind(request.cost_cents)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Input not found".to_string()))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO input_transactions (input_id, farm_id, kind, quantity, cost_cents, occurred_on, supplier, notes)
+        VALUES ($1, $2, 'purchase', $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(input_id)
+    .bind(farm_id)
+    .bind(request.quantity)
+    .bind(request.cost_cents)
+    .bind(request.purchased_on.unwrap_or_else(|| Utc::now().date_naive()))
+    .bind(clean_text(request.supplier))
+    .bind(clean_text(request.notes))
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(input)
+}
+
+pub async fn list_transactions(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    query: &InputLedgerQuery,
+) -> AppResult<Vec<InputTransaction>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(AppError::ValidationError("from must be on or before to".to_string()));
+        }
+    }
+    if let Some(kind) = &query.kind {
+        one_of("kind", kind, INPUT_TRANSACTION_KINDS)?;
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM input_transactions t JOIN farm_inputs i ON i.id = t.input_id WHERE t.farm_id = ",
+        INPUT_TRANSACTION_COLUMNS
+    ));
+    qb.push_bind(farm_id);
+
+    if let Some(input_id) = query.input_id {
+        qb.push(" AND t.input_id = ").push_bind(input_id);
+    }
+    if let Some(kind) = &query.kind {
+        qb.push(" AND t.kind = ").push_bind(kind.clone());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND t.occurred_on >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND t.occurred_on <= ").push_bind(to);
+    }
+
+    qb.push(" ORDER BY t.occurred_on DESC, t.created_at DESC LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+
+    Ok(qb.build_query_as::<InputTransaction>().fetch_all(&db.pool).await?)
+}
+
+/// Draws down stock for the inputs an activity used, converted to the unit
+/// the input is stocked in and costed at the average price of the stock on
+/// hand. Inputs the farm doesn't track are ignored; planned and cancelled
+/// activities use nothing.
+pub async fn apply_usage(conn: &mut PgConnection, activity: &FarmActivity, units: &UnitRegistry) -> AppResult<()> {
+    if !CONSUMING_STATUSES.contains(&activity.status.as_str()) {
+        return Ok(());
+    }
+
+    for used in activity.inputs_used.iter().flat_map(|inputs| inputs.0.iter()) {
+        let stock: Option<(Uuid, String, f64, i64)> = sqlx::query_as(
+            r#"
+            SELECT id, unit, quantity_on_hand, stock_value_cents FROM farm_inputs
+            WHERE farm_id = $1 AND lower(name) = lower($2)
+            FOR UPDATE
+            "#,
+        )
+        .bind(activity.farm_id)
+        .bind(used.item.trim())
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some((input_id, unit, on_hand, value_cents)) = stock else {
+            continue;
+        };
+
+        let quantity = units.convert(used.quantity, &used.unit, &unit).ok_or_else(|| {
+            AppError::ValidationError(format!(
+                "{} is stocked in {}, which {} can't be converted to",
+                used.item, unit, used.unit
+            ))
+        })?;
+        if quantity > on_hand + QUANTITY_EPSILON {
+            return Err(AppError::ValidationError(format!(
+                "Only {} {} of {} in stock",
+                on_hand, unit, used.item
+            )));
+        }
+
+        let quantity = quantity.min(on_hand);
+        let cost_cents = if quantity >= on_hand {
+            value_cents
+        } else {
+            (value_cents as f64 * quantity / on_hand).round() as i64
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE farm_inputs SET
+                quantity_on_hand = GREATEST(quantity_on_hand - $2, 0),
+                stock_value_cents = stock_value_cents - $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(input_id)
+        .bind(quantity)
+        .bind(cost_cents)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO input_transactions (input_id, farm_id, activity_id, kind, quantity, cost_cents, occurred_on)
+            VALUES ($1, $2, $3, 'usage', $4, $5, $6)
+            "#,
+        )
+        .bind(input_id)
+        .bind(activity.farm_id)
+        .bind(activity.id)
+        .bind(quantity)
+        .bind(cost_cents)
+        .bind(activity.activity_date)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Puts back the stock an activity drew down and removes its usage rows,
+/// ahead of the activity being edited or deleted.
+pub async fn reverse_usage(conn: &mut PgConnection, activity_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        WITH reversed AS (
+            DELETE FROM input_transactions WHERE activity_id = $1 AND kind = 'usage'
+            RETURNING input_id, quantity, cost_cents
+        ), totals AS (
+            SELECT input_id, SUM(quantity) AS quantity, SUM(cost_cents) AS cost_cents
+            FROM reversed GROUP BY input_id
+        )
+        UPDATE farm_inputs f SET
+            quantity_on_hand = f.quantity_on_hand + totals.quantity,
+            stock_value_cents = f.stock_value_cents + totals.cost_cents
+        FROM totals
+        WHERE f.id = totals.input_id
+        "#,
+    )
+    .bind(activity_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
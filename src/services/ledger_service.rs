@@ -0,0 +1,762 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        BankAccount, CreateBankAccountRequest, CurrencyReconciliation, LedgerAccountKind, LedgerAccountTotal,
+        LedgerTransactionKind, Order, Payout, PayoutFailedRequest, PayoutPaidRequest, PayoutQuery, PayoutRequest,
+        ReconciliationCheck, WalletBalance, WalletEntry, WalletQuery, BANK_ACCOUNT_COLUMNS, ORDER_COLUMNS,
+        PAYOUT_COLUMNS, PAYOUT_STATUSES,
+    },
+    services::sms_service::SmsProvider,
+    utils::{currency::format_amount, currency_code, one_of, required_text},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// The platform's cut of each released order in basis points, unless
+/// `PLATFORM_FEE_BPS` says otherwise.
+const DEFAULT_PLATFORM_FEE_BPS: i64 = 500;
+
+/// Days after a farmer marks an order delivered before its escrow is released
+/// without the buyer confirming or disputing it, unless `ESCROW_RELEASE_DAYS`
+/// says otherwise.
+const DEFAULT_ESCROW_RELEASE_DAYS: i32 = 3;
+
+fn platform_fee_bps() -> i64 {
+    env::var("PLATFORM_FEE_BPS")
+        .ok()
+        .and_then(|bps| bps.parse().ok())
+        .filter(|bps| (0..=10_000).contains(bps))
+        .unwrap_or(DEFAULT_PLATFORM_FEE_BPS)
+}
+
+fn escrow_release_days() -> i32 {
+    env::var("ESCROW_RELEASE_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_ESCROW_RELEASE_DAYS)
+}
+
+/// The platform fee at `fee_bps` on an order amount, rounded half up.
+fn platform_fee(amount_cents: i64, fee_bps: i64) -> i64 {
+    (amount_cents * fee_bps + 5_000) / 10_000
+}
+
+fn page(limit: Option<i64>, offset: Option<i64>) -> AppResult<(i64, i64)> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    Ok((limit, offset.unwrap_or(0).max(0)))
+}
+
+/// Id of the ledger account, creating it on first use.
+async fn account(
+    conn: &mut PgConnection,
+    kind: LedgerAccountKind,
+    farmer_id: Option<Uuid>,
+    currency_code: &str,
+) -> AppResult<Uuid> {
+    if kind.is_farmer_account() != farmer_id.is_some() {
+        return Err(AppError::InternalError(format!("Ledger account {} needs the right owner", kind.as_str())));
+    }
+
+    sqlx::query(
+        "INSERT INTO ledger_accounts (kind, farmer_id, currency_code) VALUES ($1, $2, $3) \
+         ON CONFLICT (kind, COALESCE(farmer_id, '00000000-0000-0000-0000-000000000000'), currency_code) \
+         DO NOTHING",
+    )
+    .bind(kind.as_str())
+    .bind(farmer_id)
+    .bind(currency_code)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(sqlx::query_scalar(
+        "SELECT id FROM ledger_accounts \
+         WHERE kind = $1 AND farmer_id IS NOT DISTINCT FROM $2 AND currency_code = $3",
+    )
+    .bind(kind.as_str())
+    .bind(farmer_id)
+    .bind(currency_code)
+    .fetch_one(conn)
+    .await?)
+}
+
+/// A transaction's lines must sum to zero.
+fn check_balanced(kind: LedgerTransactionKind, lines: &[(LedgerAccountKind, Option<Uuid>, i64)]) -> AppResult<()> {
+    if lines.iter().map(|(_, _, amount)| amount).sum::<i64>() != 0 {
+        return Err(AppError::InternalError(format!("Unbalanced {} ledger transaction", kind.as_str())));
+    }
+    Ok(())
+}
+
+/// The records a ledger transaction is about.
+#[derive(Debug, Default)]
+struct Links {
+    order_id: Option<Uuid>,
+    payment_id: Option<Uuid>,
+    refund_id: Option<Uuid>,
+    payout_id: Option<Uuid>,
+}
+
+/// Posts a transaction. Each line is an account and a signed amount, debits
+/// positive; the lines must sum to zero. The database checks the same at
+/// commit, so an unbalanced transaction can't be stored either way.
+async fn post(
+    conn: &mut PgConnection,
+    kind: LedgerTransactionKind,
+    currency_code: &str,
+    links: Links,
+    memo: Option<&str>,
+    lines: &[(LedgerAccountKind, Option<Uuid>, i64)],
+) -> AppResult<Uuid> {
+    check_balanced(kind, lines)?;
+
+    let transaction_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO ledger_transactions (kind, currency_code, order_id, payment_id, refund_id, payout_id, memo) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(kind.as_str())
+    .bind(currency_code)
+    .bind(links.order_id)
+    .bind(links.payment_id)
+    .bind(links.refund_id)
+    .bind(links.payout_id)
+    .bind(memo)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for &(account_kind, farmer_id, amount_cents) in lines.iter().filter(|(_, _, amount)| *amount != 0) {
+        let account_id = account(conn, account_kind, farmer_id, currency_code).await?;
+        sqlx::query("INSERT INTO ledger_entries (transaction_id, account_id, amount_cents) VALUES ($1, $2, $3)")
+            .bind(transaction_id)
+            .bind(account_id)
+            .bind(amount_cents)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(transaction_id)
+}
+
+/// Takes a succeeded payment into escrow: the gateway is debited with what
+/// was collected and each covered order's farmer credited in escrow.
+pub async fn record_payment(conn: &mut PgConnection, payment_id: Uuid) -> AppResult<()> {
+    let lines: Vec<(Uuid, i64, Uuid, String)> = sqlx::query_as(
+        "SELECT po.order_id, po.amount_cents, o.farmer_id, p.currency_code FROM payment_orders po \
+         JOIN orders o ON o.id = po.order_id JOIN payments p ON p.id = po.payment_id \
+         WHERE po.payment_id = $1 ORDER BY po.order_id",
+    )
+    .bind(payment_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (order_id, amount_cents, farmer_id, currency_code) in lines {
+        post(
+            conn,
+            LedgerTransactionKind::Payment,
+            &currency_code,
+            Links { order_id: Some(order_id), payment_id: Some(payment_id), ..Links::default() },
+            None,
+            &[
+                (LedgerAccountKind::Gateway, None, amount_cents),
+                (LedgerAccountKind::Escrow, Some(farmer_id), -amount_cents),
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Whether the order's escrow has gone to the farmer.
+pub async fn is_released(conn: &mut PgConnection, order_id: Uuid) -> AppResult<bool> {
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM ledger_transactions WHERE order_id = $1 AND kind = 'release')",
+    )
+    .bind(order_id)
+    .fetch_one(conn)
+    .await?)
+}
+
+/// Moves a refund out of escrow back through the gateway, or with
+/// `reverse`, puts back one the gateway turned down.
+async fn post_refund(conn: &mut PgConnection, refund_id: Uuid, reverse: bool) -> AppResult<()> {
+    let (order_id, payment_id, amount_cents, farmer_id, currency_code): (Uuid, Uuid, i64, Uuid, String) =
+        sqlx::query_as(
+            "SELECT r.order_id, r.payment_id, r.amount_cents, o.farmer_id, p.currency_code FROM payment_refunds r \
+             JOIN orders o ON o.id = r.order_id JOIN payments p ON p.id = r.payment_id WHERE r.id = $1",
+        )
+        .bind(refund_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let (kind, sign) = if reverse { (LedgerTransactionKind::RefundFailed, -1) } else { (LedgerTransactionKind::Refund, 1) };
+    post(
+        conn,
+        kind,
+        &currency_code,
+        Links { order_id: Some(order_id), payment_id: Some(payment_id), refund_id: Some(refund_id), ..Links::default() },
+        None,
+        &[
+            (LedgerAccountKind::Escrow, Some(farmer_id), sign * amount_cents),
+            (LedgerAccountKind::Gateway, None, -sign * amount_cents),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Takes a refund out of escrow. Recorded when the refund is requested, so
+/// the money can't also be released to the farmer.
+pub async fn record_refund(conn: &mut PgConnection, refund_id: Uuid) -> AppResult<()> {
+    post_refund(conn, refund_id, false).await
+}
+
+/// Puts a refund the gateway turned down back into escrow.
+pub async fn record_refund_failed(conn: &mut PgConnection, refund_id: Uuid) -> AppResult<()> {
+    post_refund(conn, refund_id, true).await
+}
+
+/// Releases a paid order's escrow to the farmer's wallet, less the platform
+/// fee. The order should be locked. Returns whether anything was released:
+/// unpaid orders and ones already released are left alone.
+pub async fn release_order(conn: &mut PgConnection, order: &Order) -> AppResult<bool> {
+    let Some(payment_id) = order.payment_id.filter(|_| order.payment_status == "paid") else {
+        return Ok(false);
+    };
+    if is_released(conn, order.id).await? {
+        return Ok(false);
+    }
+
+    let amount_cents: i64 =
+        sqlx::query_scalar("SELECT amount_cents FROM payment_orders WHERE payment_id = $1 AND order_id = $2")
+            .bind(payment_id)
+            .bind(order.id)
+            .fetch_one(&mut *conn)
+            .await?;
+    let fee_cents = platform_fee(amount_cents, platform_fee_bps());
+
+    post(
+        conn,
+        LedgerTransactionKind::Release,
+        &order.currency_code,
+        Links { order_id: Some(order.id), payment_id: Some(payment_id), ..Links::default() },
+        None,
+        &[
+            (LedgerAccountKind::Escrow, Some(order.farmer_id), amount_cents),
+            (LedgerAccountKind::FarmerPayable, Some(order.farmer_id), -(amount_cents - fee_cents)),
+            (LedgerAccountKind::PlatformFees, None, -fee_cents),
+        ],
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Releases the escrow of paid orders the farmer marked delivered more than
+/// `ESCROW_RELEASE_DAYS` ago that the buyer never confirmed or disputed.
+/// Disputed orders are left out by their status and stay held until the
+/// buyer confirms receipt. Returns how many were released.
+pub async fn release_delivered_orders(db: &Database) -> AppResult<usize> {
+    let mut tx = db.pool.begin().await?;
+    let orders = sqlx::query_as::<_, Order>(&format!(
+        "SELECT {} FROM orders o WHERE status IN ('delivered', 'completed') AND payment_status = 'paid' \
+         AND NOT EXISTS (SELECT 1 FROM ledger_transactions t WHERE t.order_id = o.id AND t.kind = 'release') \
+         AND EXISTS (SELECT 1 FROM order_events e WHERE e.order_id = o.id AND e.to_status = 'delivered' \
+                     AND e.created_at <= NOW() - make_interval(days => $1)) \
+         FOR UPDATE SKIP LOCKED",
+        ORDER_COLUMNS
+    ))
+    .bind(escrow_release_days())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut released = 0;
+    for order in &orders {
+        if release_order(&mut tx, order).await? {
+            released += 1;
+        }
+    }
+    tx.commit().await?;
+    Ok(released)
+}
+
+/// What the farmer is owed in each currency they have dealt in.
+pub async fn wallet_balances(db: &Database, farmer_id: Uuid) -> AppResult<Vec<WalletBalance>> {
+    Ok(sqlx::query_as::<_, WalletBalance>(
+        "SELECT a.currency_code, \
+         COALESCE(-SUM(e.amount_cents) FILTER (WHERE a.kind = 'escrow'), 0)::BIGINT AS held_cents, \
+         COALESCE(-SUM(e.amount_cents) FILTER (WHERE a.kind = 'farmer_payable'), 0)::BIGINT AS available_cents, \
+         COALESCE(-SUM(e.amount_cents) FILTER (WHERE a.kind = 'payouts_in_transit'), 0)::BIGINT AS in_payout_cents \
+         FROM ledger_accounts a LEFT JOIN ledger_entries e ON e.account_id = a.id \
+         WHERE a.farmer_id = $1 GROUP BY a.currency_code ORDER BY a.currency_code",
+    )
+    .bind(farmer_id)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+/// The farmer's wallet statement, newest first.
+pub async fn wallet_entries(db: &Database, farmer_id: Uuid, query: &WalletQuery) -> AppResult<Vec<WalletEntry>> {
+    let (limit, offset) = page(query.limit, query.offset)?;
+
+    let mut qb = QueryBuilder::<Postgres>::new(
+        "SELECT e.id, e.transaction_id, t.kind AS transaction_kind, a.kind AS account_kind, a.currency_code, \
+         -e.amount_cents AS amount_cents, t.order_id, t.payout_id, t.memo, e.created_at \
+         FROM ledger_entries e JOIN ledger_accounts a ON a.id = e.account_id \
+         JOIN ledger_transactions t ON t.id = e.transaction_id WHERE a.farmer_id = ",
+    );
+    qb.push_bind(farmer_id);
+    if let Some(code) = &query.currency_code {
+        qb.push(" AND a.currency_code = ").push_bind(currency_code(Some(code.clone()))?);
+    }
+    qb.push(" ORDER BY e.created_at DESC, e.id LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+
+    Ok(qb.build_query_as::<WalletEntry>().fetch_all(&db.pool).await?)
+}
+
+pub async fn list_bank_accounts(db: &Database, farmer_id: Uuid) -> AppResult<Vec<BankAccount>> {
+    Ok(sqlx::query_as::<_, BankAccount>(&format!(
+        "SELECT {} FROM farmer_bank_accounts WHERE farmer_id = $1 AND is_active ORDER BY created_at",
+        BANK_ACCOUNT_COLUMNS
+    ))
+    .bind(farmer_id)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+pub async fn create_bank_account(
+    db: &Database,
+    farmer_id: Uuid,
+    request: CreateBankAccountRequest,
+) -> AppResult<BankAccount> {
+    let bank_name = required_text("bank_name", &request.bank_name)?;
+    let account_name = required_text("account_name", &request.account_name)?;
+    let account_number: String = request.account_number.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(6..=20).contains(&account_number.len()) || !account_number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::ValidationError("account_number must be 6 to 20 digits".to_string()));
+    }
+    let bank_code = request.bank_code.map(|code| code.trim().to_string()).filter(|code| !code.is_empty());
+    let currency_code = currency_code(request.currency_code)?;
+
+    Ok(sqlx::query_as::<_, BankAccount>(&format!(
+        "INSERT INTO farmer_bank_accounts (farmer_id, bank_name, bank_code, account_number, account_name, currency_code) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        BANK_ACCOUNT_COLUMNS
+    ))
+    .bind(farmer_id)
+    .bind(bank_name)
+    .bind(bank_code)
+    .bind(account_number)
+    .bind(account_name)
+    .bind(currency_code)
+    .fetch_one(&db.pool)
+    .await?)
+}
+
+/// Removes a bank account from the farmer's list. It is kept for the
+/// payouts already made to it.
+pub async fn remove_bank_account(db: &Database, farmer_id: Uuid, bank_account_id: Uuid) -> AppResult<()> {
+    let removed = sqlx::query(
+        "UPDATE farmer_bank_accounts SET is_active = FALSE WHERE id = $1 AND farmer_id = $2 AND is_active",
+    )
+    .bind(bank_account_id)
+    .bind(farmer_id)
+    .execute(&db.pool)
+    .await?;
+    if removed.rows_affected() == 0 {
+        return Err(AppError::NotFound("Bank account not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Sets wallet funds aside for a payout to one of the farmer's bank
+/// accounts, in its currency. The wallet account is locked while its balance
+/// is checked so concurrent requests can't overdraw it.
+pub async fn request_payout(db: &Database, farmer_id: Uuid, request: PayoutRequest) -> AppResult<Payout> {
+    if request.amount_cents <= 0 {
+        return Err(AppError::ValidationError("amount_cents must be positive".to_string()));
+    }
+
+    let mut tx = db.pool.begin().await?;
+    let currency_code: String = sqlx::query_scalar(
+        "SELECT currency_code FROM farmer_bank_accounts WHERE id = $1 AND farmer_id = $2 AND is_active",
+    )
+    .bind(request.bank_account_id)
+    .bind(farmer_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Bank account not found".to_string()))?;
+
+    let wallet_id = account(&mut tx, LedgerAccountKind::FarmerPayable, Some(farmer_id), &currency_code).await?;
+    sqlx::query("SELECT id FROM ledger_accounts WHERE id = $1 FOR UPDATE")
+        .bind(wallet_id)
+        .execute(&mut *tx)
+        .await?;
+    let available_cents: i64 =
+        sqlx::query_scalar("SELECT COALESCE(-SUM(amount_cents), 0)::BIGINT FROM ledger_entries WHERE account_id = $1")
+            .bind(wallet_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if request.amount_cents > available_cents {
+        return Err(AppError::ValidationError(format!(
+            "Only {} is available to withdraw",
+            format_amount(available_cents, &currency_code)
+        )));
+    }
+
+    let payout = sqlx::query_as::<_, Payout>(&format!(
+        "INSERT INTO payouts (farmer_id, bank_account_id, amount_cents, currency_code) \
+         VALUES ($1, $2, $3, $4) RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(farmer_id)
+    .bind(request.bank_account_id)
+    .bind(request.amount_cents)
+    .bind(&currency_code)
+    .fetch_one(&mut *tx)
+    .await?;
+    post(
+        &mut tx,
+        LedgerTransactionKind::Payout,
+        &currency_code,
+        Links { payout_id: Some(payout.id), ..Links::default() },
+        None,
+        &[
+            (LedgerAccountKind::FarmerPayable, Some(farmer_id), payout.amount_cents),
+            (LedgerAccountKind::PayoutsInTransit, Some(farmer_id), -payout.amount_cents),
+        ],
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(payout)
+}
+
+/// Payouts, newest first: the farmer's own when `farmer_id` is given,
+/// otherwise everyone's, optionally filtered by `query.farmer_id`.
+pub async fn list_payouts(db: &Database, farmer_id: Option<Uuid>, query: &PayoutQuery) -> AppResult<Vec<Payout>> {
+    let (limit, offset) = page(query.limit, query.offset)?;
+    if let Some(status) = &query.status {
+        one_of("status", status, PAYOUT_STATUSES)?;
+    }
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM payouts WHERE TRUE", PAYOUT_COLUMNS));
+    if let Some(farmer_id) = farmer_id.or(query.farmer_id) {
+        qb.push(" AND farmer_id = ").push_bind(farmer_id);
+    }
+    if let Some(status) = &query.status {
+        qb.push(" AND status = ").push_bind(status);
+    }
+    qb.push(" ORDER BY created_at DESC, id LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+
+    Ok(qb.build_query_as::<Payout>().fetch_all(&db.pool).await?)
+}
+
+/// Loads and locks a payout that is still waiting to be sent.
+async fn requested_payout(conn: &mut PgConnection, payout_id: Uuid) -> AppResult<Payout> {
+    let payout = sqlx::query_as::<_, Payout>(&format!("SELECT {} FROM payouts WHERE id = $1 FOR UPDATE", PAYOUT_COLUMNS))
+        .bind(payout_id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payout not found".to_string()))?;
+    if payout.status != "requested" {
+        return Err(AppError::ValidationError(format!("Payout is already {}", payout.status)));
+    }
+    Ok(payout)
+}
+
+/// Records that an admin has sent a payout, and texts the farmer.
+pub async fn mark_payout_paid(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    staff_id: Uuid,
+    payout_id: Uuid,
+    request: PayoutPaidRequest,
+) -> AppResult<Payout> {
+    let transfer_reference = required_text("transfer_reference", &request.transfer_reference)?;
+
+    let mut tx = db.pool.begin().await?;
+    let payout = requested_payout(&mut tx, payout_id).await?;
+    let payout = sqlx::query_as::<_, Payout>(&format!(
+        "UPDATE payouts SET status = 'paid', transfer_reference = $2, processed_by = $3, processed_at = NOW() \
+         WHERE id = $1 RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(payout.id)
+    .bind(&transfer_reference)
+    .bind(staff_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    post(
+        &mut tx,
+        LedgerTransactionKind::PayoutPaid,
+        &payout.currency_code,
+        Links { payout_id: Some(payout.id), ..Links::default() },
+        Some(&transfer_reference),
+        &[
+            (LedgerAccountKind::PayoutsInTransit, Some(payout.farmer_id), payout.amount_cents),
+            (LedgerAccountKind::Gateway, None, -payout.amount_cents),
+        ],
+    )
+    .await?;
+    tx.commit().await?;
+
+    let message = format!(
+        "Your payout of {} has been sent. Reference: {}",
+        format_amount(payout.amount_cents, &payout.currency_code),
+        transfer_reference
+    );
+    notify_farmer(db, sms, &payout, &message).await;
+    Ok(payout)
+}
+
+/// Records that a payout couldn't be sent, returning the money to the
+/// farmer's wallet, and texts the farmer.
+pub async fn mark_payout_failed(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    staff_id: Uuid,
+    payout_id: Uuid,
+    request: PayoutFailedRequest,
+) -> AppResult<Payout> {
+    let reason = required_text("reason", &request.reason)?;
+
+    let mut tx = db.pool.begin().await?;
+    let payout = requested_payout(&mut tx, payout_id).await?;
+    let payout = sqlx::query_as::<_, Payout>(&format!(
+        "UPDATE payouts SET status = 'failed', failure_reason = $2, processed_by = $3, processed_at = NOW() \
+         WHERE id = $1 RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(payout.id)
+    .bind(&reason)
+    .bind(staff_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    post(
+        &mut tx,
+        LedgerTransactionKind::PayoutFailed,
+        &payout.currency_code,
+        Links { payout_id: Some(payout.id), ..Links::default() },
+        Some(&reason),
+        &[
+            (LedgerAccountKind::PayoutsInTransit, Some(payout.farmer_id), payout.amount_cents),
+            (LedgerAccountKind::FarmerPayable, Some(payout.farmer_id), -payout.amount_cents),
+        ],
+    )
+    .await?;
+    tx.commit().await?;
+
+    let message = format!(
+        "Your payout of {} could not be sent and is back in your wallet. {}",
+        format_amount(payout.amount_cents, &payout.currency_code),
+        reason
+    );
+    notify_farmer(db, sms, &payout, &message).await;
+    Ok(payout)
+}
+
+/// Texts the farmer about their payout. Failures are logged.
+async fn notify_farmer(db: &Database, sms: &dyn SmsProvider, payout: &Payout, message: &str) {
+    let phone_number: Option<String> = match sqlx::query_scalar("SELECT phone_number FROM farmers WHERE id = $1")
+        .bind(payout.farmer_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(phone_number) => phone_number,
+        Err(e) => {
+            log::warn!("Failed to look up phone number for payout {}: {}", payout.id, e);
+            return;
+        }
+    };
+    let Some(phone_number) = phone_number else { return };
+    if let Err(e) = sms.send(&phone_number, message).await {
+        log::warn!("Failed to send payout {} SMS: {}", payout.id, e);
+    }
+}
+
+/// Sums by currency from a `(currency_code, cents)` query.
+async fn totals(db: &Database, sql: &str) -> AppResult<BTreeMap<String, i64>> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(sql).fetch_all(&db.pool).await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// The books in each currency: the balance of every kind of account, the
+/// trial balance, and the gateway, escrow and payout balances checked
+/// against the payment, refund, payout and order records they summarise.
+///
+/// The trial balance always holds, because every transaction is checked to
+/// sum to zero when it is stored. The checks catch records changed without
+/// going through the ledger.
+pub async fn reconciliation(db: &Database) -> AppResult<Vec<CurrencyReconciliation>> {
+    let balances: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT a.currency_code, a.kind, COALESCE(SUM(e.amount_cents), 0)::BIGINT FROM ledger_accounts a \
+         LEFT JOIN ledger_entries e ON e.account_id = a.id GROUP BY a.currency_code, a.kind",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let sides: Vec<(String, i64, i64)> = sqlx::query_as(
+        "SELECT a.currency_code, \
+         COALESCE(SUM(e.amount_cents) FILTER (WHERE e.amount_cents > 0), 0)::BIGINT, \
+         COALESCE(-SUM(e.amount_cents) FILTER (WHERE e.amount_cents < 0), 0)::BIGINT \
+         FROM ledger_entries e JOIN ledger_accounts a ON a.id = e.account_id GROUP BY a.currency_code",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+
+    let collected = totals(
+        db,
+        "SELECT currency_code, SUM(amount_cents)::BIGINT FROM payments \
+         WHERE status IN ('succeeded', 'partially_refunded', 'refunded') GROUP BY currency_code",
+    )
+    .await?;
+    let refunded = totals(
+        db,
+        "SELECT p.currency_code, SUM(r.amount_cents)::BIGINT FROM payment_refunds r \
+         JOIN payments p ON p.id = r.payment_id WHERE r.status <> 'failed' GROUP BY p.currency_code",
+    )
+    .await?;
+    let paid_out = totals(
+        db,
+        "SELECT currency_code, SUM(amount_cents)::BIGINT FROM payouts WHERE status = 'paid' GROUP BY currency_code",
+    )
+    .await?;
+    let in_transit = totals(
+        db,
+        "SELECT currency_code, SUM(amount_cents)::BIGINT FROM payouts WHERE status = 'requested' GROUP BY currency_code",
+    )
+    .await?;
+    let awaiting_release = totals(
+        db,
+        "SELECT o.currency_code, SUM(po.amount_cents)::BIGINT FROM orders o \
+         JOIN payment_orders po ON po.order_id = o.id AND po.payment_id = o.payment_id \
+         WHERE o.payment_status = 'paid' \
+         AND NOT EXISTS (SELECT 1 FROM ledger_transactions t WHERE t.order_id = o.id AND t.kind = 'release') \
+         GROUP BY o.currency_code",
+    )
+    .await?;
+
+    let mut currencies: Vec<&String> = balances
+        .iter()
+        .map(|(currency, _, _)| currency)
+        .chain(collected.keys())
+        .chain(in_transit.keys())
+        .collect();
+    currencies.sort();
+    currencies.dedup();
+
+    let kinds = [
+        LedgerAccountKind::Gateway,
+        LedgerAccountKind::Escrow,
+        LedgerAccountKind::FarmerPayable,
+        LedgerAccountKind::PayoutsInTransit,
+        LedgerAccountKind::PlatformFees,
+    ];
+    let amount = |map: &BTreeMap<String, i64>, currency: &str| map.get(currency).copied().unwrap_or(0);
+
+    let mut report = Vec::with_capacity(currencies.len());
+    for currency in currencies {
+        // Gateway is reported as a debit balance and the rest, which are owed
+        // or earned, as credit balances.
+        let balance = |kind: LedgerAccountKind| {
+            let sum = balances
+                .iter()
+                .find(|(c, k, _)| c == currency && k == kind.as_str())
+                .map_or(0, |(_, _, sum)| *sum);
+            if kind == LedgerAccountKind::Gateway { sum } else { -sum }
+        };
+        let accounts: Vec<LedgerAccountTotal> = kinds
+            .iter()
+            .map(|kind| LedgerAccountTotal { account_kind: kind.as_str().to_string(), balance_cents: balance(*kind) })
+            .collect();
+
+        let (total_debits_cents, total_credits_cents) = sides
+            .iter()
+            .find(|(c, _, _)| c == currency)
+            .map_or((0, 0), |(_, debits, credits)| (*debits, *credits));
+
+        let expected = [
+            (
+                LedgerAccountKind::Gateway,
+                amount(&collected, currency) - amount(&refunded, currency) - amount(&paid_out, currency),
+            ),
+            (LedgerAccountKind::Escrow, amount(&awaiting_release, currency)),
+            (LedgerAccountKind::PayoutsInTransit, amount(&in_transit, currency)),
+        ];
+        let checks: Vec<ReconciliationCheck> = expected
+            .into_iter()
+            .map(|(kind, expected_cents)| {
+                let ledger_cents = balance(kind);
+                ReconciliationCheck {
+                    account_kind: kind.as_str().to_string(),
+                    ledger_cents,
+                    expected_cents,
+                    difference_cents: ledger_cents - expected_cents,
+                }
+            })
+            .collect();
+
+        report.push(CurrencyReconciliation {
+            currency_code: currency.clone(),
+            accounts,
+            total_debits_cents,
+            total_credits_cents,
+            balanced: total_debits_cents == total_credits_cents,
+            matches_records: checks.iter().all(|check| check.difference_cents == 0),
+            checks,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_fee_rounds_half_up() {
+        assert_eq!(platform_fee(1_000, 500), 50);
+        assert_eq!(platform_fee(1_010, 500), 51);
+        assert_eq!(platform_fee(1_009, 500), 50);
+        assert_eq!(platform_fee(1, 5_000), 1);
+        assert_eq!(platform_fee(1, 4_999), 0);
+    }
+
+    #[test]
+    fn platform_fee_at_the_bounds() {
+        assert_eq!(platform_fee(12_345, 0), 0);
+        assert_eq!(platform_fee(12_345, 10_000), 12_345);
+        assert_eq!(platform_fee(0, 500), 0);
+    }
+
+    #[test]
+    fn balanced_lines_are_accepted() {
+        let farmer = Some(Uuid::new_v4());
+        let amount = 1_010;
+        let fee = platform_fee(amount, 500);
+        let lines = [
+            (LedgerAccountKind::Escrow, farmer, amount),
+            (LedgerAccountKind::FarmerPayable, farmer, -(amount - fee)),
+            (LedgerAccountKind::PlatformFees, None, -fee),
+        ];
+        assert!(check_balanced(LedgerTransactionKind::Release, &lines).is_ok());
+        assert!(check_balanced(LedgerTransactionKind::Release, &[]).is_ok());
+    }
+
+    #[test]
+    fn unbalanced_lines_are_rejected() {
+        let farmer = Some(Uuid::new_v4());
+        let lines = [(LedgerAccountKind::Gateway, None, 700), (LedgerAccountKind::Escrow, farmer, -699)];
+        let result = check_balanced(LedgerTransactionKind::Payment, &lines);
+        assert!(matches!(result, Err(AppError::InternalError(message)) if message.contains("payment")));
+    }
+}
@@ -1,2 +1,22 @@
+pub mod activity_service;
+pub mod buyer_service;
+pub mod cart_service;
+pub mod crop_cycle_service;
+pub mod exchange_rate_service;
+pub mod farm_service;
 pub mod farmer_service;
+pub mod finance_service;
+pub mod input_service;
+pub mod ledger_service;
+pub mod order_service;
+pub mod otp_service;
+pub mod payment_provider;
+pub mod payment_service;
+pub mod plot_service;
+pub mod product_service;
+pub mod rate_limit;
+pub mod session_service;
 pub mod sms_service;
+pub mod staff_service;
+pub mod token_service;
+pub mod unit_service;
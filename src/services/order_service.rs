@@ -0,0 +1,458 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        can_transition_order, Checkout, CheckoutRequest, Order, OrderActor, OrderDetail, OrderEvent, OrderItem,
+        OrderQuery, OrderStatusChange, ORDER_COLUMNS, ORDER_EVENT_COLUMNS, ORDER_ITEM_COLUMNS, ORDER_STATUSES,
+        RELEASED_ORDER_STATUSES,
+    },
+    services::{
+        cart_service, ledger_service, payment_provider::PaymentProvider, payment_service, sms_service::SmsProvider,
+    },
+    utils::{clean_text, currency::format_amount, one_of},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// How long a farmer has to accept an order, unless
+/// `ORDER_CONFIRM_TIMEOUT_HOURS` says otherwise.
+const DEFAULT_CONFIRM_TIMEOUT_HOURS: i32 = 48;
+
+/// How often pending orders are checked for timeouts.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(300);
+
+fn confirm_timeout_hours() -> i32 {
+    env::var("ORDER_CONFIRM_TIMEOUT_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(DEFAULT_CONFIRM_TIMEOUT_HOURS)
+}
+
+/// Places the buyer's cart as one order per farmer and currency. In one
+/// transaction the products are locked, their stock is reserved by taking
+/// the ordered quantities off `quantity_available`, and the cart is emptied,
+/// so two buyers can't both check out the last of a product. Each farmer is
+/// sent an SMS about their order.
+pub async fn checkout(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    buyer_id: Uuid,
+    payload: CheckoutRequest,
+) -> AppResult<Checkout> {
+    let delivery_notes = clean_text(payload.delivery_notes);
+
+    let mut tx = db.pool.begin().await?;
+    let lines = cart_service::cart_lines(&mut tx, buyer_id, true).await?;
+    if lines.is_empty() {
+        return Err(AppError::ValidationError("Your cart is empty".to_string()));
+    }
+    let problems: Vec<String> = lines
+        .iter()
+        .filter_map(|line| line.problem.as_ref().map(|problem| format!("{}: {}", line.name, problem)))
+        .collect();
+    if !problems.is_empty() {
+        return Err(AppError::ValidationError(problems.join("; ")));
+    }
+
+    let checkout_id = Uuid::new_v4();
+    let mut orders = Vec::new();
+    for ((farmer_id, currency_code), group) in cart_service::group_lines(&lines) {
+        let subtotal_cents: i64 = group.iter().map(|line| line.line_total_cents).sum();
+        let order = sqlx::query_as::<_, Order>(&format!(
+            "INSERT INTO orders \
+             (checkout_id, buyer_id, farmer_id, currency_code, subtotal_cents, delivery_notes, confirm_by) \
+             VALUES ($1, $2, $3, $4, $5, $6, NOW() + make_interval(hours => $7)) RETURNING {}",
+            ORDER_COLUMNS
+        ))
+        .bind(checkout_id)
+        .bind(buyer_id)
+        .bind(farmer_id)
+        .bind(&currency_code)
+        .bind(subtotal_cents)
+        .bind(&delivery_notes)
+        .bind(confirm_timeout_hours())
+        .fetch_one(&mut *tx)
+        .await?;
+        record_event(&mut tx, order.id, None, "pending", Some(buyer_id), OrderActor::Buyer, None).await?;
+
+        let mut items = Vec::with_capacity(group.len());
+        for line in group {
+            sqlx::query("UPDATE products SET quantity_available = quantity_available - $2 WHERE id = $1")
+                .bind(line.product_id)
+                .bind(line.quantity)
+                .execute(&mut *tx)
+                .await?;
+
+            let item = sqlx::query_as::<_, OrderItem>(&format!(
+                "INSERT INTO order_items \
+                 (order_id, product_id, product_name, unit, quantity, unit_price_cents, line_total_cents) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {}",
+                ORDER_ITEM_COLUMNS
+            ))
+            .bind(order.id)
+            .bind(line.product_id)
+            .bind(&line.name)
+            .bind(&line.unit)
+            .bind(line.quantity)
+            .bind(line.unit_price_cents)
+            .bind(line.line_total_cents)
+            .fetch_one(&mut *tx)
+            .await?;
+            items.push(item);
+        }
+        orders.push(OrderDetail { order, items });
+    }
+
+    sqlx::query("DELETE FROM cart_items WHERE buyer_id = $1")
+        .bind(buyer_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    for detail in &orders {
+        notify(db, sms, &detail.order, OrderActor::Buyer, None).await;
+    }
+    Ok(Checkout { checkout_id, orders })
+}
+
+/// Attaches their line items to `orders`.
+async fn with_items(conn: &mut PgConnection, orders: Vec<Order>) -> AppResult<Vec<OrderDetail>> {
+    let ids: Vec<Uuid> = orders.iter().map(|order| order.id).collect();
+    let mut items = sqlx::query_as::<_, OrderItem>(&format!(
+        "SELECT {} FROM order_items WHERE order_id = ANY($1) ORDER BY product_name, id",
+        ORDER_ITEM_COLUMNS
+    ))
+    .bind(&ids)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(orders
+        .into_iter()
+        .map(|order| {
+            let (mine, rest) = items.drain(..).partition(|item| item.order_id == order.id);
+            items = rest;
+            OrderDetail { order, items: mine }
+        })
+        .collect())
+}
+
+async fn list_orders_by(db: &Database, column: &str, account_id: Uuid, query: &OrderQuery) -> AppResult<Vec<OrderDetail>> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    let offset = query.offset.unwrap_or(0).max(0);
+    if let Some(status) = &query.status {
+        one_of("status", status, ORDER_STATUSES)?;
+    }
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM orders WHERE {} = ", ORDER_COLUMNS, column));
+    qb.push_bind(account_id);
+    if let Some(status) = &query.status {
+        qb.push(" AND status = ").push_bind(status);
+    }
+    if let Some(checkout_id) = query.checkout_id {
+        qb.push(" AND checkout_id = ").push_bind(checkout_id);
+    }
+    qb.push(" ORDER BY created_at DESC, id LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+
+    let mut conn = db.pool.acquire().await?;
+    let orders = qb.build_query_as::<Order>().fetch_all(&mut *conn).await?;
+    with_items(&mut conn, orders).await
+}
+
+/// Orders the buyer has placed, newest first.
+pub async fn list_orders(db: &Database, buyer_id: Uuid, query: &OrderQuery) -> AppResult<Vec<OrderDetail>> {
+    list_orders_by(db, "buyer_id", buyer_id, query).await
+}
+
+/// Orders placed with the farmer, newest first.
+pub async fn list_received_orders(db: &Database, farmer_id: Uuid, query: &OrderQuery) -> AppResult<Vec<OrderDetail>> {
+    list_orders_by(db, "farmer_id", farmer_id, query).await
+}
+
+/// Loads an order visible to the account: its buyer or the farmer it was
+/// placed with. With `lock`, the order is locked for update.
+async fn visible_order(conn: &mut PgConnection, account_id: Uuid, order_id: Uuid, lock: bool) -> AppResult<Order> {
+    sqlx::query_as::<_, Order>(&format!(
+        "SELECT {} FROM orders WHERE id = $1 AND (buyer_id = $2 OR farmer_id = $2){}",
+        ORDER_COLUMNS,
+        if lock { " FOR UPDATE" } else { "" }
+    ))
+    .bind(order_id)
+    .bind(account_id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Order not found".to_string()))
+}
+
+pub async fn get_order(db: &Database, account_id: Uuid, order_id: Uuid) -> AppResult<OrderDetail> {
+    let mut conn = db.pool.acquire().await?;
+    let order = visible_order(&mut conn, account_id, order_id, false).await?;
+    let mut details = with_items(&mut conn, vec![order]).await?;
+    Ok(details.remove(0))
+}
+
+/// Status history of an order, oldest first.
+pub async fn list_events(db: &Database, account_id: Uuid, order_id: Uuid) -> AppResult<Vec<OrderEvent>> {
+    let mut conn = db.pool.acquire().await?;
+    visible_order(&mut conn, account_id, order_id, false).await?;
+
+    Ok(sqlx::query_as::<_, OrderEvent>(&format!(
+        "SELECT {} FROM order_events WHERE order_id = $1 ORDER BY created_at, id",
+        ORDER_EVENT_COLUMNS
+    ))
+    .bind(order_id)
+    .fetch_all(&mut *conn)
+    .await?)
+}
+
+async fn record_event(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+    from_status: Option<&str>,
+    to_status: &str,
+    actor_id: Option<Uuid>,
+    actor: OrderActor,
+    note: Option<&str>,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO order_events (order_id, from_status, to_status, actor_id, actor_role, note) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(order_id)
+    .bind(from_status)
+    .bind(to_status)
+    .bind(actor_id)
+    .bind(actor.as_str())
+    .bind(note)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Moves a locked order to `to`, recording the change and putting the
+/// reserved stock back when the order is cancelled or rejected.
+async fn transition(
+    conn: &mut PgConnection,
+    order: &Order,
+    to: &str,
+    actor_id: Option<Uuid>,
+    actor: OrderActor,
+    note: Option<&str>,
+) -> AppResult<Order> {
+    let updated = sqlx::query_as::<_, Order>(&format!(
+        "UPDATE orders SET status = $2, confirm_by = NULL WHERE id = $1 RETURNING {}",
+        ORDER_COLUMNS
+    ))
+    .bind(order.id)
+    .bind(to)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if RELEASED_ORDER_STATUSES.contains(&to) {
+        sqlx::query(
+            "UPDATE products p SET quantity_available = p.quantity_available + i.quantity \
+             FROM order_items i WHERE i.order_id = $1 AND p.id = i.product_id",
+        )
+        .bind(order.id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    record_event(conn, order.id, Some(&order.status), to, actor_id, actor, note).await?;
+    Ok(updated)
+}
+
+/// Changes an order's status on behalf of its buyer or farmer, as allowed by
+/// `can_transition_order`, and texts the other party. A paid order that is
+/// cancelled or rejected is refunded; the buyer confirming delivery releases
+/// its escrow to the farmer.
+pub async fn change_status(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    payments: &dyn PaymentProvider,
+    account_id: Uuid,
+    order_id: Uuid,
+    payload: OrderStatusChange,
+) -> AppResult<OrderDetail> {
+    one_of("status", &payload.status, ORDER_STATUSES)?;
+    let note = clean_text(payload.note);
+
+    let mut tx = db.pool.begin().await?;
+    let order = visible_order(&mut tx, account_id, order_id, true).await?;
+    let actor = if order.farmer_id == account_id { OrderActor::Farmer } else { OrderActor::Buyer };
+
+    if order.status == payload.status {
+        return Err(AppError::ValidationError(format!("Order is already {}", order.status)));
+    }
+    if !can_transition_order(actor, &order.status, &payload.status) {
+        return Err(AppError::ValidationError(format!(
+            "The {} can't change an order from {} to {}",
+            actor.as_str(),
+            order.status,
+            payload.status
+        )));
+    }
+
+    // A dispute only matters while the escrow is still held.
+    if payload.status == "disputed" && ledger_service::is_released(&mut tx, order.id).await? {
+        return Err(AppError::ValidationError(
+            "The payment for this order has already been released to the farmer".to_string(),
+        ));
+    }
+
+    let order = transition(&mut tx, &order, &payload.status, Some(account_id), actor, note.as_deref()).await?;
+    if actor == OrderActor::Buyer && matches!(order.status.as_str(), "delivered" | "completed") {
+        ledger_service::release_order(&mut tx, &order).await?;
+    }
+    let mut details = with_items(&mut tx, vec![order]).await?;
+    tx.commit().await?;
+
+    let mut detail = details.remove(0);
+    refund_released(db, payments, &mut detail.order).await;
+    notify(db, sms, &detail.order, actor, note.as_deref()).await;
+    Ok(detail)
+}
+
+/// Cancels pending orders whose farmer didn't accept them by `confirm_by`,
+/// releasing their stock and refunding any payment. Returns how many were
+/// cancelled.
+pub async fn expire_pending_orders(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    payments: &dyn PaymentProvider,
+) -> AppResult<usize> {
+    let mut tx = db.pool.begin().await?;
+    let stale = sqlx::query_as::<_, Order>(&format!(
+        "SELECT {} FROM orders WHERE status = 'pending' AND confirm_by <= NOW() FOR UPDATE SKIP LOCKED",
+        ORDER_COLUMNS
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let note = "Not accepted in time";
+    let mut expired = Vec::with_capacity(stale.len());
+    for order in &stale {
+        expired.push(transition(&mut tx, order, "cancelled", None, OrderActor::System, Some(note)).await?);
+    }
+    tx.commit().await?;
+
+    for order in &mut expired {
+        refund_released(db, payments, order).await;
+        notify(db, sms, order, OrderActor::System, Some(note)).await;
+    }
+    Ok(expired.len())
+}
+
+/// Runs `expire_pending_orders`, `ledger_service::release_delivered_orders`
+/// and `payment_service::refund_missed_orders` every few minutes for the life
+/// of the server.
+pub async fn run_order_expiry(db: Database, sms: Arc<dyn SmsProvider>, payments: Arc<dyn PaymentProvider>) {
+    let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        match expire_pending_orders(&db, sms.as_ref(), payments.as_ref()).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("Cancelled {} orders that were not accepted in time", count),
+            Err(e) => log::error!("Failed to expire pending orders: {}", e),
+        }
+        match ledger_service::release_delivered_orders(&db).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("Released escrow for {} delivered orders", count),
+            Err(e) => log::error!("Failed to release escrow for delivered orders: {}", e),
+        }
+        match payment_service::refund_missed_orders(&db, payments.as_ref()).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("Refunded {} orders whose refunds were missed", count),
+            Err(e) => log::error!("Failed to refund missed orders: {}", e),
+        }
+    }
+}
+
+/// Refunds a cancelled or rejected order that had been paid. The status
+/// change stands either way; a refund the gateway turns down is logged and
+/// kept as failed in `payment_refunds`, where staff can list and retry it
+/// under `/api/admin/refunds`.
+async fn refund_released(db: &Database, payments: &dyn PaymentProvider, order: &mut Order) {
+    if !RELEASED_ORDER_STATUSES.contains(&order.status.as_str()) {
+        return;
+    }
+    match payment_service::refund_order(db, payments, order, &format!("Order {}", order.status)).await {
+        Ok(Some(_)) => order.payment_status = "refunded".to_string(),
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to refund order {}: {}", order.id, e),
+    }
+}
+
+fn order_message(order: &Order, note: Option<&str>) -> String {
+    let reference = &order.id.to_string()[..8];
+    let mut message = match order.status.as_str() {
+        "pending" => format!(
+            "New order {} for {}. Accept or reject it in the app.",
+            reference,
+            format_amount(order.subtotal_cents, &order.currency_code)
+        ),
+        "confirmed" => format!("Your order {} has been accepted.", reference),
+        "packed" => format!("Your order {} has been packed.", reference),
+        "shipped" => format!("Your order {} is on its way.", reference),
+        "ready_for_pickup" => format!("Your order {} is ready for pickup.", reference),
+        "delivered" => format!("Order {} has been delivered.", reference),
+        "disputed" => format!("The buyer reports order {} has not arrived. Payment is held until they confirm it.", reference),
+        "completed" => format!("Order {} is complete.", reference),
+        "cancelled" => format!("Order {} has been cancelled.", reference),
+        "rejected" => format!("Your order {} was rejected.", reference),
+        other => format!("Order {} is now {}.", reference, other),
+    };
+    if let Some(note) = note {
+        message.push(' ');
+        message.push_str(note);
+    }
+    message
+}
+
+/// Phone number of a farmer or buyer account.
+async fn account_phone(db: &Database, account_id: Uuid) -> AppResult<Option<String>> {
+    Ok(sqlx::query_scalar(
+        "SELECT phone_number FROM farmers WHERE id = $1 UNION ALL SELECT phone_number FROM buyers WHERE id = $1",
+    )
+    .bind(account_id)
+    .fetch_optional(&db.pool)
+    .await?)
+}
+
+/// Texts the parties to an order about its new status: the other side when
+/// the buyer or farmer made the change, both for system changes. Failures are
+/// logged rather than undoing the change.
+async fn notify(db: &Database, sms: &dyn SmsProvider, order: &Order, actor: OrderActor, note: Option<&str>) {
+    let recipients = match actor {
+        OrderActor::Buyer => vec![order.farmer_id],
+        OrderActor::Farmer => vec![order.buyer_id],
+        OrderActor::System => vec![order.buyer_id, order.farmer_id],
+    };
+    let message = order_message(order, note);
+
+    for account_id in recipients {
+        let phone_number = match account_phone(db, account_id).await {
+            Ok(phone_number) => phone_number,
+            Err(e) => {
+                log::warn!("Failed to look up phone number for order {}: {}", order.id, e);
+                continue;
+            }
+        };
+        let Some(phone_number) = phone_number else { continue };
+        if let Err(e) = sms.send(&phone_number, &message).await {
+            log::warn!("Failed to send order {} SMS: {}", order.id, e);
+        }
+    }
+}
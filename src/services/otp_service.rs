@@ -0,0 +1,240 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult, OtpError},
+    models::{OtpPurpose, PhoneVerification, VerifyPhoneRequest},
+    services::{rate_limit::{self, RateLimit}, sms_service::SmsProvider},
+    utils::{generate_otp, hash_otp, otp_matches, phone},
+};
+
+/// Wrong guesses allowed against a single code before a new one is required.
+pub const MAX_OTP_ATTEMPTS: i32 = 3;
+
+const OTP_SEND_COOLDOWN: RateLimit = RateLimit { action: "otp_send_cooldown", limit: 1, window_secs: 60 };
+const OTP_SEND_PER_PHONE: RateLimit = RateLimit { action: "otp_send_phone", limit: 5, window_secs: 3600 };
+const OTP_SEND_PER_IP: RateLimit = RateLimit { action: "otp_send_ip", limit: 20, window_secs: 3600 };
+const OTP_VERIFY_PER_PHONE: RateLimit = RateLimit { action: "otp_verify_phone", limit: 10, window_secs: 900 };
+const OTP_VERIFY_PER_IP: RateLimit = RateLimit { action: "otp_verify_ip", limit: 30, window_secs: 900 };
+
+/// Texts a fresh code to the phone. Codes are per phone number, so farmer and
+/// buyer logins share them.
+pub async fn send_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    purpose: OtpPurpose,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let phone_number = phone::normalize(phone_number)?;
+    reserve_send(db, &phone_number, client_ip).await?;
+    deliver_otp(db, sms, &phone_number, purpose, client_ip).await
+}
+
+/// Counts a code send against the phone's and the client's limits. Callers
+/// that must not act on a rejected send, such as registration, reserve it
+/// first and call `deliver_otp` afterwards.
+pub async fn reserve_send(db: &Database, phone_number: &str, client_ip: Option<&str>) -> AppResult<()> {
+    let mut hits = vec![(&OTP_SEND_COOLDOWN, phone_number), (&OTP_SEND_PER_PHONE, phone_number)];
+    if let Some(ip) = client_ip {
+        hits.push((&OTP_SEND_PER_IP, ip));
+    }
+    rate_limit::hit_all(db, &hits).await
+}
+
+/// Texts a fresh code to a normalized phone number whose send has been
+/// reserved.
+pub async fn deliver_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    purpose: OtpPurpose,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let otp_code = generate_otp();
+    let expires_at = Utc::now() + Duration::minutes(30);
+
+    sqlx::query(
+        r#"
+        DELETE FROM phone_verifications WHERE phone_number = $1 AND expires_at < NOW()
+        "#,
+    )
+    .bind(phone_number)
+    .execute(&db.pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO phone_verifications (phone_number, otp_hash, purpose, expires_at, request_ip)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(phone_number)
+    .bind(hash_otp(phone_number, &otp_code))
+    .bind(purpose.as_str())
+    .bind(expires_at)
+    .bind(client_ip)
+    .execute(&db.pool)
+    .await?;
+
+    let message = match purpose {
+        OtpPurpose::Registration => format!("Your verification code is {}", otp_code),
+        OtpPurpose::Login => format!("Your login code is {}", otp_code),
+    };
+    sms.send(phone_number, &message).await?;
+
+    Ok(())
+}
+
+/// Purpose of the last code sent to the phone, for resending without one.
+/// Defaults to login.
+pub async fn last_purpose(db: &Database, phone_number: &str) -> AppResult<OtpPurpose> {
+    let last: Option<String> = sqlx::query_scalar(
+        "SELECT purpose FROM phone_verifications WHERE phone_number = $1 ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(phone_number)
+    .fetch_optional(&db.pool)
+    .await?;
+
+    Ok(match last.as_deref() {
+        Some("registration") => OtpPurpose::Registration,
+        _ => OtpPurpose::Login,
+    })
+}
+
+/// Whether a code can still be guessed at: not used, not expired and with
+/// wrong guesses to spare.
+fn check_usable(code: &PhoneVerification, now: DateTime<Utc>) -> Result<(), OtpError> {
+    if code.verified || code.expires_at < now {
+        return Err(OtpError::Expired);
+    }
+    if code.attempts >= MAX_OTP_ATTEMPTS {
+        return Err(OtpError::TooManyAttempts);
+    }
+    Ok(())
+}
+
+/// The error for a wrong guess, once `attempts` wrong guesses were made.
+fn wrong_guess(attempts: i32) -> OtpError {
+    let attempts_remaining = MAX_OTP_ATTEMPTS - attempts;
+    if attempts_remaining <= 0 {
+        OtpError::TooManyAttempts
+    } else {
+        OtpError::Invalid { attempts_remaining }
+    }
+}
+
+/// Checks `otp_code` against the most recent code sent to the phone (for the
+/// requested purpose, if any). Only the latest code is live; every wrong guess
+/// is counted against it. Returns the normalized phone number.
+pub async fn verify_otp(db: &Database, request: &VerifyPhoneRequest, client_ip: Option<&str>) -> AppResult<String> {
+    let phone_number = phone::normalize(&request.phone_number)?;
+
+    rate_limit::hit(db, &OTP_VERIFY_PER_PHONE, &phone_number).await?;
+    if let Some(ip) = client_ip {
+        rate_limit::hit(db, &OTP_VERIFY_PER_IP, ip).await?;
+    }
+
+    let code = sqlx::query_as::<_, PhoneVerification>(
+        r#"
+        SELECT * 
+        FROM phone_verifications 
+        WHERE phone_number = $1 AND ($2::text IS NULL OR purpose = $2)
+        ORDER BY created_at DESC 
+        LIMIT 1
+        "#,
+    )
+    .bind(&phone_number)
+    .bind(request.purpose.map(|p| p.as_str()))
+    .fetch_optional(&db.pool)
+    .await?;
+
+    let Some(code) = code else {
+        return Err(AppError::Otp(OtpError::Expired));
+    };
+    let id = code.id;
+
+    check_usable(&code, Utc::now()).map_err(AppError::Otp)?;
+
+    if !otp_matches(&phone_number, &request.otp_code, &code.otp_hash) {
+        let attempts: i32 = sqlx::query_scalar(
+            "UPDATE phone_verifications SET attempts = attempts + 1 WHERE id = $1 RETURNING attempts",
+        )
+        .bind(id)
+        .fetch_one(&db.pool)
+        .await?;
+
+        return Err(AppError::Otp(wrong_guess(attempts)));
+    }
+
+    let mut tx = db.pool.begin().await?;
+
+    // Re-check under the row lock so a code can't be used twice or after the
+    // attempt budget ran out concurrently.
+    let consumed = sqlx::query(
+        "UPDATE phone_verifications SET verified = true WHERE id = $1 AND verified = false AND attempts < $2",
+    )
+    .bind(id)
+    .bind(MAX_OTP_ATTEMPTS)
+    .execute(&mut *tx)
+    .await?;
+
+    if consumed.rows_affected() == 0 {
+        return Err(AppError::Otp(OtpError::Expired));
+    }
+
+    tx.commit().await?;
+    Ok(phone_number)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn code(attempts: i32, verified: bool, expires_in: Duration) -> PhoneVerification {
+        let now = Utc::now();
+        PhoneVerification {
+            id: Uuid::new_v4(),
+            phone_number: "+2348012345678".to_string(),
+            otp_hash: String::new(),
+            purpose: "login".to_string(),
+            expires_at: now + expires_in,
+            verified,
+            attempts,
+            request_ip: None,
+            created_at: now,
+        }
+    }
+
+    #[test]
+    fn wrong_guesses_count_down_to_exhaustion() {
+        assert!(matches!(wrong_guess(1), OtpError::Invalid { attempts_remaining: 2 }));
+        assert!(matches!(wrong_guess(2), OtpError::Invalid { attempts_remaining: 1 }));
+        assert!(matches!(wrong_guess(MAX_OTP_ATTEMPTS), OtpError::TooManyAttempts));
+        assert!(matches!(wrong_guess(MAX_OTP_ATTEMPTS + 1), OtpError::TooManyAttempts));
+    }
+
+    #[test]
+    fn exhausted_codes_are_refused_before_checking_the_guess() {
+        let now = Utc::now();
+        assert!(check_usable(&code(MAX_OTP_ATTEMPTS - 1, false, Duration::minutes(5)), now).is_ok());
+        assert!(matches!(
+            check_usable(&code(MAX_OTP_ATTEMPTS, false, Duration::minutes(5)), now),
+            Err(OtpError::TooManyAttempts)
+        ));
+    }
+
+    #[test]
+    fn used_or_expired_codes_are_refused() {
+        let now = Utc::now();
+        assert!(matches!(check_usable(&code(0, true, Duration::minutes(5)), now), Err(OtpError::Expired)));
+        assert!(matches!(check_usable(&code(0, false, Duration::seconds(-1)), now), Err(OtpError::Expired)));
+        assert!(matches!(
+            check_usable(&code(MAX_OTP_ATTEMPTS, false, Duration::seconds(-1)), now),
+            Err(OtpError::Expired)
+        ));
+    }
+}
@@ -0,0 +1,518 @@
+use crate::errors::{AppError, AppResult};
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use sha2::Sha512;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// What the gateway needs to start a transaction.
+#[derive(Debug)]
+pub struct PaymentInit<'a> {
+    pub reference: &'a str,
+    pub amount_cents: i64,
+    pub currency_code: &'a str,
+    pub email: Option<&'a str>,
+    pub phone_number: &'a str,
+}
+
+#[derive(Debug)]
+pub struct InitializedPayment {
+    /// Where the buyer completes the payment.
+    pub authorization_url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// The gateway's view of a transaction.
+#[derive(Debug, Clone)]
+pub struct Charge {
+    pub reference: String,
+    pub status: ChargeStatus,
+    pub amount_cents: i64,
+    pub currency_code: String,
+    pub transaction_id: Option<String>,
+    pub failure_reason: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ProviderRefund {
+    pub refund_id: Option<String>,
+    /// Whether the money has already gone back, rather than being queued.
+    pub processed: bool,
+}
+
+/// A webhook delivery whose signature checked out.
+#[derive(Debug)]
+pub struct WebhookEvent {
+    pub event_type: String,
+    /// The transaction the event reports on, for charge events.
+    pub charge: Option<Charge>,
+}
+
+/// Payment gateway. The active implementation is picked at startup by
+/// `provider_from_env` and shared with handlers as
+/// `web::Data<dyn PaymentProvider>`. Amounts are in the currency's minor unit.
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Request header carrying the webhook signature.
+    fn signature_header(&self) -> &'static str;
+
+    /// The local mock behind this provider, if it is one.
+    fn mock(&self) -> Option<&MockPaymentProvider> {
+        None
+    }
+
+    /// Whether buyers can pay online at all.
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    async fn initialize(&self, payment: &PaymentInit<'_>) -> AppResult<InitializedPayment>;
+
+    async fn verify(&self, reference: &str) -> AppResult<Charge>;
+
+    async fn refund(&self, reference: &str, amount_cents: i64) -> AppResult<ProviderRefund>;
+
+    /// Checks a webhook body against its signature and parses it. A bad
+    /// signature is unauthorized.
+    fn parse_webhook(&self, body: &[u8], signature: &str) -> AppResult<WebhookEvent>;
+}
+
+/// Builds the provider named by `PAYMENT_PROVIDER` ("none", "paystack" or
+/// "mock"). Defaults to none, which turns online payments off, so existing
+/// deployments start without gateway credentials.
+pub fn provider_from_env() -> AppResult<Arc<dyn PaymentProvider>> {
+    let provider = env::var("PAYMENT_PROVIDER").unwrap_or_else(|_| "none".to_string());
+
+    let provider: Arc<dyn PaymentProvider> = match provider.to_lowercase().as_str() {
+        "none" => Arc::new(DisabledPaymentProvider),
+        "paystack" => Arc::new(PaystackProvider::from_env()?),
+        "mock" => Arc::new(MockPaymentProvider::from_env()?),
+        other => {
+            return Err(AppError::InternalError(format!("Unknown PAYMENT_PROVIDER: {}", other)));
+        }
+    };
+
+    log::info!("Using {} payment provider", provider.name());
+    Ok(provider)
+}
+
+fn required_env(key: &str) -> AppResult<String> {
+    env::var(key).map_err(|_| AppError::InternalError(format!("{} not set", key)))
+}
+
+/// Hex HMAC-SHA512 of the body, as Paystack signs its webhooks.
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha512>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Constant-time check of a hex HMAC-SHA512 signature.
+fn signature_matches(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature.trim()) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha512>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Transaction as Paystack reports it, in verify responses and webhooks.
+#[derive(Debug, Deserialize)]
+struct PaystackTransaction {
+    reference: String,
+    status: String,
+    amount: i64,
+    currency: String,
+    id: Option<i64>,
+    gateway_response: Option<String>,
+}
+
+impl From<PaystackTransaction> for Charge {
+    fn from(transaction: PaystackTransaction) -> Self {
+        let status = match transaction.status.as_str() {
+            "success" => ChargeStatus::Succeeded,
+            "failed" | "reversed" => ChargeStatus::Failed,
+            _ => ChargeStatus::Pending,
+        };
+        Charge {
+            reference: transaction.reference,
+            failure_reason: (status == ChargeStatus::Failed).then_some(transaction.gateway_response).flatten(),
+            status,
+            amount_cents: transaction.amount,
+            currency_code: transaction.currency,
+            transaction_id: transaction.id.map(|id| id.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PaystackEvent {
+    event: String,
+    data: serde_json::Value,
+}
+
+/// Parses a Paystack-format webhook body. Only `charge.*` events carry a
+/// charge; everything else is passed through by name.
+fn parse_paystack_event(body: &[u8]) -> AppResult<WebhookEvent> {
+    let event: PaystackEvent = serde_json::from_slice(body)
+        .map_err(|e| AppError::ValidationError(format!("Malformed webhook body: {}", e)))?;
+
+    let charge = if event.event.starts_with("charge.") {
+        let transaction: PaystackTransaction = serde_json::from_value(event.data)
+            .map_err(|e| AppError::ValidationError(format!("Malformed webhook charge: {}", e)))?;
+        Some(transaction.into())
+    } else {
+        None
+    };
+
+    Ok(WebhookEvent { event_type: event.event, charge })
+}
+
+/// Paystack's transaction API. Flutterwave and most local gateways follow the
+/// same initialize / verify / refund shape with an HMAC-signed webhook.
+pub struct PaystackProvider {
+    client: Client,
+    base_url: String,
+    secret_key: String,
+    callback_url: Option<String>,
+    email_domain: String,
+}
+
+/// Paystack wraps every response in `{status, message, data}`.
+#[derive(Debug, Deserialize)]
+struct PaystackResponse<T> {
+    status: bool,
+    message: String,
+    data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaystackAuthorization {
+    authorization_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaystackRefund {
+    id: Option<i64>,
+    status: String,
+}
+
+impl PaystackProvider {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(PaystackProvider {
+            client: Client::new(),
+            base_url: env::var("PAYSTACK_BASE_URL").unwrap_or_else(|_| "https://api.paystack.co".to_string()),
+            secret_key: required_env("PAYSTACK_SECRET_KEY")?,
+            callback_url: env::var("PAYSTACK_CALLBACK_URL").ok().filter(|url| !url.is_empty()),
+            email_domain: env::var("PAYSTACK_EMAIL_DOMAIN").unwrap_or_else(|_| "example.com".to_string()),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn read<T: DeserializeOwned>(response: reqwest::Response, action: &str) -> AppResult<T> {
+        let status = response.status();
+        let body: PaystackResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Unreadable Paystack {} response: {}", action, e)))?;
+
+        match body.data {
+            Some(data) if status.is_success() && body.status => Ok(data),
+            _ => Err(AppError::InternalError(format!("Paystack {} failed: {}", action, body.message))),
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for PaystackProvider {
+    fn name(&self) -> &'static str {
+        "paystack"
+    }
+
+    fn signature_header(&self) -> &'static str {
+        "x-paystack-signature"
+    }
+
+    async fn initialize(&self, payment: &PaymentInit<'_>) -> AppResult<InitializedPayment> {
+        // Paystack requires an email; buyers without one get a placeholder
+        // derived from their phone number.
+        let email = match payment.email {
+            Some(email) => email.to_string(),
+            None => format!("{}@{}", payment.phone_number.trim_start_matches('+'), self.email_domain),
+        };
+
+        let response = self.client
+            .post(self.url("/transaction/initialize"))
+            .bearer_auth(&self.secret_key)
+            .json(&json!({
+                "email": email,
+                "amount": payment.amount_cents,
+                "currency": payment.currency_code,
+                "reference": payment.reference,
+                "callback_url": self.callback_url,
+                "metadata": { "phone_number": payment.phone_number },
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to reach Paystack: {}", e)))?;
+
+        let authorization: PaystackAuthorization = Self::read(response, "initialize").await?;
+        Ok(InitializedPayment { authorization_url: authorization.authorization_url })
+    }
+
+    async fn verify(&self, reference: &str) -> AppResult<Charge> {
+        let response = self.client
+            .get(self.url(&format!("/transaction/verify/{}", reference)))
+            .bearer_auth(&self.secret_key)
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to reach Paystack: {}", e)))?;
+
+        let transaction: PaystackTransaction = Self::read(response, "verify").await?;
+        Ok(transaction.into())
+    }
+
+    async fn refund(&self, reference: &str, amount_cents: i64) -> AppResult<ProviderRefund> {
+        let response = self.client
+            .post(self.url("/refund"))
+            .bearer_auth(&self.secret_key)
+            .json(&json!({ "transaction": reference, "amount": amount_cents }))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to reach Paystack: {}", e)))?;
+
+        let refund: PaystackRefund = Self::read(response, "refund").await?;
+        Ok(ProviderRefund {
+            refund_id: refund.id.map(|id| id.to_string()),
+            processed: refund.status == "processed",
+        })
+    }
+
+    fn parse_webhook(&self, body: &[u8], signature: &str) -> AppResult<WebhookEvent> {
+        if !signature_matches(&self.secret_key, body, signature) {
+            return Err(AppError::Unauthorized("Invalid webhook signature".to_string()));
+        }
+        parse_paystack_event(body)
+    }
+}
+
+fn payments_disabled() -> AppError {
+    AppError::ValidationError("Online payments are not enabled".to_string())
+}
+
+/// Stand-in used when no gateway is configured. Every call is turned down.
+pub struct DisabledPaymentProvider;
+
+#[async_trait]
+impl PaymentProvider for DisabledPaymentProvider {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn signature_header(&self) -> &'static str {
+        "x-paystack-signature"
+    }
+
+    fn enabled(&self) -> bool {
+        false
+    }
+
+    async fn initialize(&self, _payment: &PaymentInit<'_>) -> AppResult<InitializedPayment> {
+        Err(payments_disabled())
+    }
+
+    async fn verify(&self, _reference: &str) -> AppResult<Charge> {
+        Err(payments_disabled())
+    }
+
+    async fn refund(&self, _reference: &str, _amount_cents: i64) -> AppResult<ProviderRefund> {
+        Err(payments_disabled())
+    }
+
+    fn parse_webhook(&self, _body: &[u8], _signature: &str) -> AppResult<WebhookEvent> {
+        Err(AppError::Unauthorized("Online payments are not enabled".to_string()))
+    }
+}
+
+/// Local gateway for development and tests: nothing leaves the machine.
+/// Transactions are kept in memory and settled through
+/// `POST /api/dev/payments/{reference}`, which produces the same signed,
+/// Paystack-format webhook a real gateway would send. The signing secret is
+/// `MOCK_PAYMENT_SECRET`, which must be set.
+pub struct MockPaymentProvider {
+    secret: String,
+    transactions: Mutex<HashMap<String, Charge>>,
+}
+
+impl MockPaymentProvider {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(MockPaymentProvider {
+            secret: required_env("MOCK_PAYMENT_SECRET")?,
+            transactions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Settles a transaction as the buyer would at the gateway and returns
+    /// the webhook body and signature the gateway would then send.
+    pub fn complete(&self, reference: &str, succeeded: bool) -> AppResult<(Vec<u8>, String)> {
+        let mut transactions = self.transactions.lock().unwrap();
+        let charge = transactions
+            .get_mut(reference)
+            .ok_or_else(|| AppError::NotFound("Unknown transaction".to_string()))?;
+        if charge.status != ChargeStatus::Pending {
+            return Err(AppError::ValidationError("Transaction is already settled".to_string()));
+        }
+
+        charge.status = if succeeded { ChargeStatus::Succeeded } else { ChargeStatus::Failed };
+        let transaction_id: u32 = rand::thread_rng().gen();
+        charge.transaction_id = Some(transaction_id.to_string());
+        charge.failure_reason = (!succeeded).then(|| "Declined".to_string());
+
+        let body = serde_json::to_vec(&json!({
+            "event": if succeeded { "charge.success" } else { "charge.failed" },
+            "data": {
+                "id": transaction_id,
+                "reference": charge.reference,
+                "status": if succeeded { "success" } else { "failed" },
+                "amount": charge.amount_cents,
+                "currency": charge.currency_code,
+                "gateway_response": charge.failure_reason.as_deref().unwrap_or("Approved"),
+            },
+        }))
+        .map_err(|e| AppError::InternalError(format!("Failed to encode webhook: {}", e)))?;
+
+        let signature = sign(&self.secret, &body);
+        Ok((body, signature))
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for MockPaymentProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn signature_header(&self) -> &'static str {
+        "x-paystack-signature"
+    }
+
+    fn mock(&self) -> Option<&MockPaymentProvider> {
+        Some(self)
+    }
+
+    async fn initialize(&self, payment: &PaymentInit<'_>) -> AppResult<InitializedPayment> {
+        let charge = Charge {
+            reference: payment.reference.to_string(),
+            status: ChargeStatus::Pending,
+            amount_cents: payment.amount_cents,
+            currency_code: payment.currency_code.to_string(),
+            transaction_id: None,
+            failure_reason: None,
+        };
+        log::info!("[payment mock] initialized {} for {} {}", payment.reference, payment.currency_code, payment.amount_cents);
+        self.transactions.lock().unwrap().insert(charge.reference.clone(), charge);
+
+        Ok(InitializedPayment { authorization_url: format!("/api/dev/payments/{}", payment.reference) })
+    }
+
+    async fn verify(&self, reference: &str) -> AppResult<Charge> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .get(reference)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("Unknown transaction".to_string()))
+    }
+
+    async fn refund(&self, reference: &str, amount_cents: i64) -> AppResult<ProviderRefund> {
+        let transactions = self.transactions.lock().unwrap();
+        let charge = transactions
+            .get(reference)
+            .ok_or_else(|| AppError::NotFound("Unknown transaction".to_string()))?;
+        if charge.status != ChargeStatus::Succeeded || amount_cents > charge.amount_cents {
+            return Err(AppError::ValidationError("Transaction can't be refunded".to_string()));
+        }
+
+        log::info!("[payment mock] refunded {} of {}", amount_cents, reference);
+        Ok(ProviderRefund { refund_id: Some(Uuid::new_v4().simple().to_string()), processed: true })
+    }
+
+    fn parse_webhook(&self, body: &[u8], signature: &str) -> AppResult<WebhookEvent> {
+        if !signature_matches(&self.secret, body, signature) {
+            return Err(AppError::Unauthorized("Invalid webhook signature".to_string()));
+        }
+        parse_paystack_event(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"event":"charge.success","data":{"id":7,"reference":"pay_1","status":"success","amount":700,"currency":"NGN"}}"#;
+
+    fn mock() -> MockPaymentProvider {
+        MockPaymentProvider { secret: "test-secret".to_string(), transactions: Mutex::new(HashMap::new()) }
+    }
+
+    #[test]
+    fn signature_round_trips() {
+        let signature = sign("test-secret", BODY);
+        assert_eq!(signature.len(), 128);
+        assert!(signature_matches("test-secret", BODY, &signature));
+        assert!(signature_matches("test-secret", BODY, &format!(" {}\n", signature.to_uppercase())));
+    }
+
+    #[test]
+    fn signature_rejects_other_secrets_and_bodies() {
+        let signature = sign("test-secret", BODY);
+        assert!(!signature_matches("other-secret", BODY, &signature));
+        assert!(!signature_matches("test-secret", &BODY[1..], &signature));
+        assert!(!signature_matches("test-secret", BODY, &signature[..126]));
+        assert!(!signature_matches("test-secret", BODY, "not hex"));
+        assert!(!signature_matches("test-secret", BODY, ""));
+    }
+
+    #[test]
+    fn webhook_with_valid_signature_parses_the_charge() {
+        let event = mock().parse_webhook(BODY, &sign("test-secret", BODY)).unwrap();
+        assert_eq!(event.event_type, "charge.success");
+        let charge = event.charge.unwrap();
+        assert_eq!(charge.reference, "pay_1");
+        assert_eq!(charge.status, ChargeStatus::Succeeded);
+        assert_eq!(charge.amount_cents, 700);
+        assert_eq!(charge.transaction_id.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn webhook_with_bad_signature_is_unauthorized() {
+        let result = mock().parse_webhook(BODY, &sign("other-secret", BODY));
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn non_charge_events_carry_no_charge() {
+        let event = parse_paystack_event(br#"{"event":"transfer.success","data":{"id":1}}"#).unwrap();
+        assert_eq!(event.event_type, "transfer.success");
+        assert!(event.charge.is_none());
+    }
+}
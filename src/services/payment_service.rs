@@ -0,0 +1,667 @@
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        CreatePaymentRequest, Order, Payment, PaymentDetail, PaymentOrder, PaymentRefund, RefundQuery, ORDER_COLUMNS,
+        PAYMENT_COLUMNS, PAYMENT_REFUND_COLUMNS, PAYMENT_REFUND_STATUSES, RELEASED_ORDER_STATUSES,
+    },
+    services::{
+        ledger_service,
+        payment_provider::{Charge, ChargeStatus, PaymentInit, PaymentProvider},
+    },
+    utils::{currency_code, one_of},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Starts a payment for the checkout's orders that still need paying, in one
+/// currency. `currency_code` picks the currency when the checkout spans
+/// several. The payment is recorded before the gateway is called, so a
+/// webhook can never arrive for a reference we don't know.
+pub async fn create_payment(
+    db: &Database,
+    provider: &dyn PaymentProvider,
+    buyer_id: Uuid,
+    payload: CreatePaymentRequest,
+) -> AppResult<PaymentDetail> {
+    if !provider.enabled() {
+        return Err(AppError::ValidationError("Online payments are not enabled".to_string()));
+    }
+
+    let orders = sqlx::query_as::<_, Order>(&format!(
+        "SELECT {} FROM orders WHERE checkout_id = $1 AND buyer_id = $2 ORDER BY created_at, id",
+        ORDER_COLUMNS
+    ))
+    .bind(payload.checkout_id)
+    .bind(buyer_id)
+    .fetch_all(&db.pool)
+    .await?;
+    if orders.is_empty() {
+        return Err(AppError::NotFound("Checkout not found".to_string()));
+    }
+
+    let payable: Vec<&Order> = orders
+        .iter()
+        .filter(|order| order.payment_status == "unpaid" && !RELEASED_ORDER_STATUSES.contains(&order.status.as_str()))
+        .collect();
+    let mut currencies: Vec<&str> = payable.iter().map(|order| order.currency_code.as_str()).collect();
+    currencies.sort_unstable();
+    currencies.dedup();
+
+    let currency = match payload.currency_code {
+        Some(code) => currency_code(Some(code))?,
+        None => match currencies.as_slice() {
+            [] => return Err(AppError::ValidationError("Nothing left to pay for this checkout".to_string())),
+            [only] => only.to_string(),
+            several => {
+                return Err(AppError::ValidationError(format!(
+                    "This checkout has orders in {}; choose a currency_code to pay",
+                    several.join(", ")
+                )));
+            }
+        },
+    };
+    let payable: Vec<&Order> = payable.into_iter().filter(|order| order.currency_code == currency).collect();
+    if payable.is_empty() {
+        return Err(AppError::ValidationError(format!("Nothing left to pay in {} for this checkout", currency)));
+    }
+    let amount_cents: i64 = payable.iter().map(|order| order.subtotal_cents).sum();
+    if amount_cents <= 0 {
+        return Err(AppError::ValidationError("These orders are free; there is nothing to pay".to_string()));
+    }
+
+    // Farmers buy too, so the payer may be in either table.
+    let (phone_number, email): (String, Option<String>) = sqlx::query_as(
+        "SELECT phone_number, email FROM buyers WHERE id = $1 \
+         UNION ALL SELECT phone_number, email FROM farmers WHERE id = $1",
+    )
+    .bind(buyer_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Account not found".to_string()))?;
+
+    let reference = format!("pay_{}", Uuid::new_v4().simple());
+    let mut tx = db.pool.begin().await?;
+    let payment = sqlx::query_as::<_, Payment>(&format!(
+        "INSERT INTO payments (checkout_id, buyer_id, provider, reference, amount_cents, currency_code) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        PAYMENT_COLUMNS
+    ))
+    .bind(payload.checkout_id)
+    .bind(buyer_id)
+    .bind(provider.name())
+    .bind(&reference)
+    .bind(amount_cents)
+    .bind(&currency)
+    .fetch_one(&mut *tx)
+    .await?;
+    for order in &payable {
+        sqlx::query("INSERT INTO payment_orders (payment_id, order_id, amount_cents) VALUES ($1, $2, $3)")
+            .bind(payment.id)
+            .bind(order.id)
+            .bind(order.subtotal_cents)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    let init = PaymentInit {
+        reference: &reference,
+        amount_cents,
+        currency_code: &currency,
+        email: email.as_deref(),
+        phone_number: &phone_number,
+    };
+    match provider.initialize(&init).await {
+        Ok(initialized) => {
+            sqlx::query("UPDATE payments SET authorization_url = $2 WHERE id = $1")
+                .bind(payment.id)
+                .bind(&initialized.authorization_url)
+                .execute(&db.pool)
+                .await?;
+        }
+        Err(e) => {
+            sqlx::query("UPDATE payments SET status = 'failed', failure_reason = $2 WHERE id = $1")
+                .bind(payment.id)
+                .bind(e.to_string())
+                .execute(&db.pool)
+                .await?;
+            return Err(e);
+        }
+    }
+
+    let mut conn = db.pool.acquire().await?;
+    payment_detail(&mut conn, payment.id).await
+}
+
+async fn payment_detail(conn: &mut PgConnection, payment_id: Uuid) -> AppResult<PaymentDetail> {
+    let payment = sqlx::query_as::<_, Payment>(&format!("SELECT {} FROM payments WHERE id = $1", PAYMENT_COLUMNS))
+        .bind(payment_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let orders = sqlx::query_as::<_, PaymentOrder>(
+        "SELECT order_id, amount_cents FROM payment_orders WHERE payment_id = $1 ORDER BY order_id",
+    )
+    .bind(payment_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let refunds = sqlx::query_as::<_, PaymentRefund>(&format!(
+        "SELECT {} FROM payment_refunds WHERE payment_id = $1 ORDER BY created_at, id",
+        PAYMENT_REFUND_COLUMNS
+    ))
+    .bind(payment_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(PaymentDetail { payment, orders, refunds })
+}
+
+/// Loads one of the buyer's payments.
+async fn own_payment(db: &Database, buyer_id: Uuid, payment_id: Uuid) -> AppResult<Payment> {
+    sqlx::query_as::<_, Payment>(&format!(
+        "SELECT {} FROM payments WHERE id = $1 AND buyer_id = $2",
+        PAYMENT_COLUMNS
+    ))
+    .bind(payment_id)
+    .bind(buyer_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))
+}
+
+pub async fn get_payment(db: &Database, buyer_id: Uuid, payment_id: Uuid) -> AppResult<PaymentDetail> {
+    let payment = own_payment(db, buyer_id, payment_id).await?;
+    let mut conn = db.pool.acquire().await?;
+    payment_detail(&mut conn, payment.id).await
+}
+
+/// The buyer's payments, newest first.
+pub async fn list_payments(db: &Database, buyer_id: Uuid) -> AppResult<Vec<Payment>> {
+    Ok(sqlx::query_as::<_, Payment>(&format!(
+        "SELECT {} FROM payments WHERE buyer_id = $1 ORDER BY created_at DESC, id",
+        PAYMENT_COLUMNS
+    ))
+    .bind(buyer_id)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+/// Asks the gateway for the payment's status and applies it, for buyers
+/// returning from the payment page before the webhook has arrived.
+pub async fn verify_payment(
+    db: &Database,
+    provider: &dyn PaymentProvider,
+    buyer_id: Uuid,
+    payment_id: Uuid,
+) -> AppResult<PaymentDetail> {
+    let payment = own_payment(db, buyer_id, payment_id).await?;
+    let charge = provider.verify(&payment.reference).await?;
+    apply_charge(db, provider, &charge).await?;
+
+    let mut conn = db.pool.acquire().await?;
+    payment_detail(&mut conn, payment.id).await
+}
+
+/// Handles a gateway webhook. The signature is checked first; each distinct
+/// delivery is recorded by a hash of its body and redeliveries are
+/// acknowledged without being processed again. Applying a charge is itself
+/// idempotent, so two copies racing past the check do no harm.
+pub async fn handle_webhook(
+    db: &Database,
+    provider: &dyn PaymentProvider,
+    body: &[u8],
+    signature: &str,
+) -> AppResult<()> {
+    let event = provider.parse_webhook(body, signature)?;
+    let event_key = webhook_event_key(body);
+
+    let seen: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM payment_webhook_events WHERE provider = $1 AND event_key = $2)",
+    )
+    .bind(provider.name())
+    .bind(&event_key)
+    .fetch_one(&db.pool)
+    .await?;
+    if seen {
+        log::info!("Ignoring redelivered {} webhook {}", provider.name(), event.event_type);
+        return Ok(());
+    }
+
+    if let Some(charge) = &event.charge {
+        match apply_charge(db, provider, charge).await {
+            // Not ours, e.g. a transaction made directly on the gateway
+            // dashboard. Acknowledge it so the gateway stops retrying.
+            Err(AppError::NotFound(_)) => log::warn!("Webhook for unknown payment reference {}", charge.reference),
+            result => {
+                result?;
+            }
+        }
+    }
+
+    let payload: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|e| AppError::ValidationError(format!("Malformed webhook body: {}", e)))?;
+    sqlx::query(
+        "INSERT INTO payment_webhook_events (provider, event_key, event_type, reference, payload) \
+         VALUES ($1, $2, $3, $4, $5) ON CONFLICT (provider, event_key) DO NOTHING",
+    )
+    .bind(provider.name())
+    .bind(&event_key)
+    .bind(&event.event_type)
+    .bind(event.charge.as_ref().map(|charge| &charge.reference))
+    .bind(payload)
+    .execute(&db.pool)
+    .await?;
+    Ok(())
+}
+
+/// Identifies a webhook delivery by the SHA-256 of its exact body, so a
+/// redelivery matches and any change to the payload doesn't.
+fn webhook_event_key(body: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(body))
+}
+
+/// Applies the gateway's view of a transaction to the payment with its
+/// reference. A successful charge for the expected amount marks the payment
+/// succeeded and its orders paid, with the money held in escrow; orders that
+/// were cancelled or paid by another payment in the meantime are refunded.
+/// Charges already applied are ignored, and a pending charge changes nothing.
+pub async fn apply_charge(db: &Database, provider: &dyn PaymentProvider, charge: &Charge) -> AppResult<Payment> {
+    let mut tx = db.pool.begin().await?;
+    let payment = sqlx::query_as::<_, Payment>(&format!(
+        "SELECT {} FROM payments WHERE reference = $1 FOR UPDATE",
+        PAYMENT_COLUMNS
+    ))
+    .bind(&charge.reference)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))?;
+
+    let payment = match charge_outcome(&payment, charge) {
+        ChargeOutcome::Unchanged => return Ok(payment),
+        ChargeOutcome::Failed(reason) => {
+            let payment = fail_payment(&mut tx, payment.id, &reason).await?;
+            tx.commit().await?;
+            return Ok(payment);
+        }
+        ChargeOutcome::AmountMismatch(reason) => {
+            log::error!("Payment {} amount mismatch: {}", payment.reference, reason);
+            let payment = fail_payment(&mut tx, payment.id, &reason).await?;
+            tx.commit().await?;
+            return Ok(payment);
+        }
+        ChargeOutcome::Succeeded => {
+            sqlx::query_as::<_, Payment>(&format!(
+                "UPDATE payments SET status = 'succeeded', provider_transaction_id = $2, failure_reason = NULL, \
+                 paid_at = NOW() WHERE id = $1 RETURNING {}",
+                PAYMENT_COLUMNS
+            ))
+            .bind(payment.id)
+            .bind(&charge.transaction_id)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
+    let paid: Vec<Uuid> = sqlx::query_scalar(
+        "UPDATE orders SET payment_status = 'paid', payment_id = $1, paid_at = NOW() \
+         WHERE id IN (SELECT order_id FROM payment_orders WHERE payment_id = $1) \
+         AND payment_status = 'unpaid' AND status <> ALL($2) RETURNING id",
+    )
+    .bind(payment.id)
+    .bind(RELEASED_ORDER_STATUSES)
+    .fetch_all(&mut *tx)
+    .await?;
+    let unpaid: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT order_id FROM payment_orders WHERE payment_id = $1 AND order_id <> ALL($2) ORDER BY order_id",
+    )
+    .bind(payment.id)
+    .bind(&paid)
+    .fetch_all(&mut *tx)
+    .await?;
+    ledger_service::record_payment(&mut tx, payment.id).await?;
+    tx.commit().await?;
+
+    for order_id in unpaid {
+        let reason = "Order was cancelled or already paid before this payment completed";
+        if let Err(e) = refund_payment_order(db, provider, payment.id, order_id, reason).await {
+            log::error!("Failed to refund order {} from payment {}: {}", order_id, payment.reference, e);
+        }
+    }
+    Ok(payment)
+}
+
+/// What a charge does to a payment.
+#[derive(Debug, PartialEq)]
+enum ChargeOutcome {
+    /// Nothing: the charge is pending, or was already applied, or the payment
+    /// has settled since.
+    Unchanged,
+    Failed(String),
+    /// The gateway took a different amount than was due; the payment fails.
+    AmountMismatch(String),
+    Succeeded,
+}
+
+/// Decides what `charge` does to `payment`. Only pending and failed payments
+/// settle, so a redelivered success or a late failure changes nothing.
+fn charge_outcome(payment: &Payment, charge: &Charge) -> ChargeOutcome {
+    let settleable = payment.status == "pending" || payment.status == "failed";
+    match charge.status {
+        ChargeStatus::Pending => ChargeOutcome::Unchanged,
+        _ if !settleable => ChargeOutcome::Unchanged,
+        ChargeStatus::Failed if payment.status == "failed" => ChargeOutcome::Unchanged,
+        ChargeStatus::Failed => {
+            ChargeOutcome::Failed(charge.failure_reason.clone().unwrap_or_else(|| "Payment failed".to_string()))
+        }
+        ChargeStatus::Succeeded
+            if charge.amount_cents != payment.amount_cents
+                || !charge.currency_code.eq_ignore_ascii_case(&payment.currency_code) =>
+        {
+            ChargeOutcome::AmountMismatch(format!(
+                "Gateway reported {} {} but {} {} was due",
+                charge.currency_code, charge.amount_cents, payment.currency_code, payment.amount_cents
+            ))
+        }
+        ChargeStatus::Succeeded => ChargeOutcome::Succeeded,
+    }
+}
+
+async fn fail_payment(conn: &mut PgConnection, payment_id: Uuid, reason: &str) -> AppResult<Payment> {
+    Ok(sqlx::query_as::<_, Payment>(&format!(
+        "UPDATE payments SET status = 'failed', failure_reason = $2 WHERE id = $1 RETURNING {}",
+        PAYMENT_COLUMNS
+    ))
+    .bind(payment_id)
+    .bind(reason)
+    .fetch_one(conn)
+    .await?)
+}
+
+/// Refunds a paid order that was cancelled or rejected. Orders that were
+/// never paid need nothing.
+pub async fn refund_order(
+    db: &Database,
+    provider: &dyn PaymentProvider,
+    order: &Order,
+    reason: &str,
+) -> AppResult<Option<PaymentRefund>> {
+    match (order.payment_status.as_str(), order.payment_id) {
+        ("paid", Some(payment_id)) => Ok(Some(refund_payment_order(db, provider, payment_id, order.id, reason).await?)),
+        _ => Ok(None),
+    }
+}
+
+/// Minutes a refund is left to the request that should make it before
+/// `refund_missed_orders` steps in.
+const REFUND_SWEEP_GRACE_MINUTES: i32 = 10;
+
+/// Refunds orders that were due a refund but never got one recorded, e.g.
+/// because the server stopped between settling a payment and refunding an
+/// order it no longer covered, which leaves the money in escrow. Refunds the
+/// gateway turned down are left to staff. Returns how many were refunded.
+pub async fn refund_missed_orders(db: &Database, provider: &dyn PaymentProvider) -> AppResult<usize> {
+    let missed: Vec<(Uuid, Uuid, String)> = sqlx::query_as(
+        "SELECT po.payment_id, po.order_id, o.status FROM payment_orders po \
+         JOIN payments p ON p.id = po.payment_id \
+         JOIN orders o ON o.id = po.order_id \
+         WHERE p.status IN ('succeeded', 'partially_refunded') \
+           AND (o.payment_id IS DISTINCT FROM p.id OR (o.payment_status = 'paid' AND o.status = ANY($1))) \
+           AND GREATEST(p.paid_at, o.updated_at) < NOW() - make_interval(mins => $2) \
+           AND NOT EXISTS (SELECT 1 FROM payment_refunds r WHERE r.payment_id = p.id AND r.order_id = o.id) \
+           AND NOT EXISTS (SELECT 1 FROM ledger_transactions t WHERE t.order_id = o.id AND t.kind = 'release')",
+    )
+    .bind(RELEASED_ORDER_STATUSES)
+    .bind(REFUND_SWEEP_GRACE_MINUTES)
+    .fetch_all(&db.pool)
+    .await?;
+
+    let mut refunded = 0;
+    for (payment_id, order_id, status) in missed {
+        let reason = if RELEASED_ORDER_STATUSES.contains(&status.as_str()) {
+            format!("Order {}", status)
+        } else {
+            "Order was cancelled or already paid before this payment completed".to_string()
+        };
+        match refund_payment_order(db, provider, payment_id, order_id, &reason).await {
+            Ok(_) => refunded += 1,
+            Err(e) => log::error!("Failed to refund order {} from payment {}: {}", order_id, payment_id, e),
+        }
+    }
+    Ok(refunded)
+}
+
+/// Refunds for staff to follow up, newest first. A failed refund is left out
+/// once a later attempt for the same order exists.
+pub async fn list_refunds(db: &Database, query: &RefundQuery) -> AppResult<Vec<PaymentRefund>> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    if let Some(status) = &query.status {
+        one_of("status", status, PAYMENT_REFUND_STATUSES)?;
+    }
+
+    let mut qb = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM payment_refunds r WHERE NOT (r.status = 'failed' AND EXISTS (\
+         SELECT 1 FROM payment_refunds later WHERE later.payment_id = r.payment_id \
+         AND later.order_id = r.order_id AND later.id <> r.id \
+         AND (later.status <> 'failed' OR later.created_at > r.created_at)))",
+        PAYMENT_REFUND_COLUMNS
+    ));
+    if let Some(status) = &query.status {
+        qb.push(" AND r.status = ").push_bind(status);
+    }
+    qb.push(" ORDER BY r.created_at DESC, r.id LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(query.offset.unwrap_or(0).max(0));
+
+    Ok(qb.build_query_as::<PaymentRefund>().fetch_all(&db.pool).await?)
+}
+
+/// Asks the gateway again for a refund it turned down. The retry is a new
+/// refund; the failed one stays on record.
+pub async fn retry_refund(db: &Database, provider: &dyn PaymentProvider, refund_id: Uuid) -> AppResult<PaymentRefund> {
+    let failed = sqlx::query_as::<_, PaymentRefund>(&format!(
+        "SELECT {} FROM payment_refunds WHERE id = $1",
+        PAYMENT_REFUND_COLUMNS
+    ))
+    .bind(refund_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Refund not found".to_string()))?;
+    if failed.status != "failed" {
+        return Err(AppError::ValidationError(format!("Refund is {}, not failed", failed.status)));
+    }
+    let order_id = failed
+        .order_id
+        .ok_or_else(|| AppError::ValidationError("The refunded order no longer exists".to_string()))?;
+
+    let reason = failed.reason.as_deref().unwrap_or("Refund retried");
+    refund_payment_order(db, provider, failed.payment_id, order_id, reason).await
+}
+
+/// Gives back what a payment collected for one order. The refund is recorded
+/// as pending, and taken out of escrow, before the gateway is asked; the
+/// partial unique index on `payment_refunds` stops the same order being
+/// refunded twice. Orders whose escrow has been released can't be refunded.
+async fn refund_payment_order(
+    db: &Database,
+    provider: &dyn PaymentProvider,
+    payment_id: Uuid,
+    order_id: Uuid,
+    reason: &str,
+) -> AppResult<PaymentRefund> {
+    let mut tx = db.pool.begin().await?;
+    let payment = sqlx::query_as::<_, Payment>(&format!(
+        "SELECT {} FROM payments WHERE id = $1 FOR UPDATE",
+        PAYMENT_COLUMNS
+    ))
+    .bind(payment_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if payment.status != "succeeded" && payment.status != "partially_refunded" {
+        return Err(AppError::ValidationError(format!("A {} payment can't be refunded", payment.status)));
+    }
+    let amount_cents: i64 =
+        sqlx::query_scalar("SELECT amount_cents FROM payment_orders WHERE payment_id = $1 AND order_id = $2")
+            .bind(payment_id)
+            .bind(order_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Order is not part of this payment".to_string()))?;
+    if ledger_service::is_released(&mut tx, order_id).await? {
+        return Err(AppError::ValidationError("The order's payment has already been released to the farmer".to_string()));
+    }
+
+    let refund = sqlx::query_as::<_, PaymentRefund>(&format!(
+        "INSERT INTO payment_refunds (payment_id, order_id, amount_cents, reason) VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (payment_id, order_id) WHERE order_id IS NOT NULL AND status <> 'failed' DO NOTHING \
+         RETURNING {}",
+        PAYMENT_REFUND_COLUMNS
+    ))
+    .bind(payment_id)
+    .bind(order_id)
+    .bind(amount_cents)
+    .bind(reason)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::ValidationError("Order has already been refunded".to_string()))?;
+    ledger_service::record_refund(&mut tx, refund.id).await?;
+    tx.commit().await?;
+
+    let result = match provider.refund(&payment.reference, amount_cents).await {
+        Ok(result) => result,
+        Err(e) => {
+            let mut tx = db.pool.begin().await?;
+            sqlx::query("UPDATE payment_refunds SET status = 'failed', failure_reason = $2 WHERE id = $1")
+                .bind(refund.id)
+                .bind(e.to_string())
+                .execute(&mut *tx)
+                .await?;
+            ledger_service::record_refund_failed(&mut tx, refund.id).await?;
+            tx.commit().await?;
+            return Err(e);
+        }
+    };
+
+    let mut tx = db.pool.begin().await?;
+    let refund = sqlx::query_as::<_, PaymentRefund>(&format!(
+        "UPDATE payment_refunds SET status = $2, provider_refund_id = $3 WHERE id = $1 RETURNING {}",
+        PAYMENT_REFUND_COLUMNS
+    ))
+    .bind(refund.id)
+    .bind(if result.processed { "processed" } else { "pending" })
+    .bind(&result.refund_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE payments SET refunded_cents = refunded_cents + $2, \
+         status = CASE WHEN refunded_cents + $2 >= amount_cents THEN 'refunded' ELSE 'partially_refunded' END \
+         WHERE id = $1",
+    )
+    .bind(payment_id)
+    .bind(amount_cents)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE orders SET payment_status = 'refunded' WHERE id = $1 AND payment_id = $2")
+        .bind(order_id)
+        .bind(payment_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(refund)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn payment(status: &str) -> Payment {
+        let now = Utc::now();
+        Payment {
+            id: Uuid::new_v4(),
+            checkout_id: Uuid::new_v4(),
+            buyer_id: Uuid::new_v4(),
+            provider: "paystack".to_string(),
+            reference: "pay_1".to_string(),
+            amount_cents: 2_500_000,
+            currency_code: "NGN".to_string(),
+            status: status.to_string(),
+            authorization_url: None,
+            provider_transaction_id: None,
+            failure_reason: None,
+            refunded_cents: 0,
+            paid_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn charge(status: ChargeStatus, amount_cents: i64, currency_code: &str) -> Charge {
+        Charge {
+            reference: "pay_1".to_string(),
+            status,
+            amount_cents,
+            currency_code: currency_code.to_string(),
+            transaction_id: Some("tx_1".to_string()),
+            failure_reason: None,
+        }
+    }
+
+    #[test]
+    fn a_success_for_the_amount_due_settles_a_pending_or_failed_payment() {
+        for status in ["pending", "failed"] {
+            let outcome = charge_outcome(&payment(status), &charge(ChargeStatus::Succeeded, 2_500_000, "ngn"));
+            assert_eq!(outcome, ChargeOutcome::Succeeded, "{}", status);
+        }
+    }
+
+    #[test]
+    fn a_second_success_for_the_same_reference_changes_nothing() {
+        let success = charge(ChargeStatus::Succeeded, 2_500_000, "NGN");
+        for status in ["succeeded", "partially_refunded", "refunded"] {
+            assert_eq!(charge_outcome(&payment(status), &success), ChargeOutcome::Unchanged, "{}", status);
+        }
+    }
+
+    #[test]
+    fn pending_charges_and_late_failures_change_nothing() {
+        for status in ["pending", "failed", "succeeded"] {
+            let outcome = charge_outcome(&payment(status), &charge(ChargeStatus::Pending, 2_500_000, "NGN"));
+            assert_eq!(outcome, ChargeOutcome::Unchanged, "{}", status);
+        }
+        for status in ["failed", "succeeded", "refunded"] {
+            let outcome = charge_outcome(&payment(status), &charge(ChargeStatus::Failed, 2_500_000, "NGN"));
+            assert_eq!(outcome, ChargeOutcome::Unchanged, "{}", status);
+        }
+    }
+
+    #[test]
+    fn a_failed_charge_fails_a_pending_payment_with_the_gateways_reason() {
+        let mut declined = charge(ChargeStatus::Failed, 2_500_000, "NGN");
+        assert_eq!(
+            charge_outcome(&payment("pending"), &declined),
+            ChargeOutcome::Failed("Payment failed".to_string())
+        );
+        declined.failure_reason = Some("Insufficient funds".to_string());
+        assert_eq!(
+            charge_outcome(&payment("pending"), &declined),
+            ChargeOutcome::Failed("Insufficient funds".to_string())
+        );
+    }
+
+    #[test]
+    fn a_success_for_another_amount_or_currency_is_a_mismatch() {
+        for (amount_cents, currency_code) in [(2_400_000, "NGN"), (2_500_000, "USD")] {
+            let success = charge(ChargeStatus::Succeeded, amount_cents, currency_code);
+            let outcome = charge_outcome(&payment("pending"), &success);
+            assert!(matches!(outcome, ChargeOutcome::AmountMismatch(_)), "{} {}", amount_cents, currency_code);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{CreateFarmPlotRequest, FarmPlot, UpdateFarmPlotRequest, FARM_PLOT_COLUMNS},
+    services::farm_service::{self, boundary_from_geojson},
+};
+
+fn clean_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::ValidationError("Plot name is required".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_area(area_hectares: Option<f64>) -> AppResult<()> {
+    match area_hectares {
+        Some(area) if !(area > 0.0 && area.is_finite()) => {
+            Err(AppError::ValidationError("area_hectares must be greater than 0".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn map_duplicate_name(err: sqlx::Error, name: Option<&str>) -> AppError {
+    match &err {
+        sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("23505") => AppError::ValidationError(
+            format!("A plot named '{}' already exists on this farm", name.unwrap_or_default()),
+        ),
+        _ => err.into(),
+    }
+}
+
+/// Validates a plot boundary and checks it lies within the farm boundary,
+/// when the farm has been mapped.
+async fn plot_geojson(db: &Database, farm_id: Uuid, boundary: Option<&serde_json::Value>) -> AppResult<Option<String>> {
+    let Some(boundary) = boundary else {
+        return Ok(None);
+    };
+    let geojson = farm_service::validate_boundary(db, boundary).await?;
+
+    let inside: bool = sqlx::query_scalar(&format!(
+        "SELECT boundary IS NULL OR ST_CoveredBy({}, boundary) FROM farms WHERE id = $1",
+        boundary_from_geojson("$2")
+    ))
+    .bind(farm_id)
+    .bind(&geojson)
+    .fetch_one(&db.pool)
+    .await?;
+
+    if !inside {
+        return Err(AppError::ValidationError("Plot boundary must lie within the farm boundary".to_string()));
+    }
+    Ok(Some(geojson))
+}
+
+pub async fn list_plots(db: &Database, farmer_id: Uuid, farm_id: Uuid) -> AppResult<Vec<FarmPlot>> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let plots = sqlx::query_as::<_, FarmPlot>(&format!(
+        "SELECT {} FROM farm_plots WHERE farm_id = $1 ORDER BY lower(name)",
+        FARM_PLOT_COLUMNS
+    ))
+    .bind(farm_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(plots)
+}
+
+/// Looks a plot up by name, case-insensitively, as activities refer to it.
+pub async fn find_plot_by_name(db: &Database, farm_id: Uuid, name: &str) -> AppResult<Option<FarmPlot>> {
+    let plot = sqlx::query_as::<_, FarmPlot>(&format!(
+        "SELECT {} FROM farm_plots WHERE farm_id = $1 AND lower(name) = lower($2)",
+        FARM_PLOT_COLUMNS
+    ))
+    .bind(farm_id)
+    .bind(name.trim())
+    .fetch_optional(&db.pool)
+    .await?;
+
+    Ok(plot)
+}
+
+pub async fn create_plot(db: &Database, farmer_id: Uuid, farm_id: Uuid, request: CreateFarmPlotRequest) -> AppResult<FarmPlot> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    let name = clean_name(&request.name)?;
+    validate_area(request.area_hectares)?;
+    let geojson = plot_geojson(db, farm_id, request.boundary.as_ref()).await?;
+
+    sqlx::query_as::<_, FarmPlot>(&format!(
+        r#"
+        INSERT INTO farm_plots (farm_id, name, boundary, area_hectares, notes)
+        SELECT $1, $2, b.g, COALESCE(ST_Area(b.g::geography) / 10000.0, $4::NUMERIC), $5
+        FROM (SELECT {} AS g) b
+        RETURNING {}
+        "#,
+        boundary_from_geojson("$3"),
+        FARM_PLOT_COLUMNS
+    ))
+    .bind(farm_id)
+    .bind(&name)
+    .bind(geojson)
+    .bind(request.area_hectares)
+    .bind(&request.notes)
+    .fetch_one(&db.pool)
+    .await
+    .map_err(|e| map_duplicate_name(e, Some(&name)))
+}
+
+/// Updates a plot. A new boundary replaces the area; a manual area only
+/// applies to unmapped plots. Renaming a plot renames it on the farm's
+/// activities and crop cycles too.
+pub async fn update_plot(
+    db: &Database,
+    farmer_id: Uuid,
+    farm_id: Uuid,
+    plot_id: Uuid,
+    request: UpdateFarmPlotRequest,
+) -> AppResult<FarmPlot> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+    let name = request.name.as_deref().map(clean_name).transpose()?;
+    validate_area(request.area_hectares)?;
+    let geojson = plot_geojson(db, farm_id, request.boundary.as_ref()).await?;
+
+    let mut tx = db.pool.begin().await?;
+
+    let old_name: String = sqlx::query_scalar("SELECT name FROM farm_plots WHERE id = $1 AND farm_id = $2 FOR UPDATE")
+        .bind(plot_id)
+        .bind(farm_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Plot not found".to_string()))?;
+
+    let plot = sqlx::query_as::<_, FarmPlot>(&format!(
+        r#"
+        UPDATE farm_plots p SET
+            name = COALESCE($3, p.name),
+            boundary = COALESCE(b.g, p.boundary),
+            area_hectares = CASE
+                WHEN b.g IS NOT NULL THEN ST_Area(b.g::geography) / 10000.0
+                WHEN p.boundary IS NULL THEN COALESCE($5::NUMERIC, p.area_hectares)
+                ELSE p.area_hectares
+            END,
+            notes = COALESCE($6, p.notes)
+        FROM (SELECT {} AS g) b
+        WHERE p.id = $1 AND p.farm_id = $2
+        RETURNING {}
+        "#,
+        boundary_from_geojson("$4"),
+        FARM_PLOT_COLUMNS
+    ))
+    .bind(plot_id)
+    .bind(farm_id)
+    .bind(&name)
+    .bind(geojson)
+    .bind(request.area_hectares)
+    .bind(&request.notes)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| map_duplicate_name(e, name.as_deref()))?;
+
+    if plot.name != old_name {
+        sqlx::query("UPDATE farm_activities SET field_plot = $3 WHERE farm_id = $1 AND field_plot = $2")
+            .bind(farm_id)
+            .bind(&old_name)
+            .bind(&plot.name)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE crop_cycles SET field_plot = $3 WHERE farm_id = $1 AND field_plot = $2")
+            .bind(farm_id)
+            .bind(&old_name)
+            .bind(&plot.name)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(plot)
+}
+
+pub async fn delete_plot(db: &Database, farmer_id: Uuid, farm_id: Uuid, plot_id: Uuid) -> AppResult<()> {
+    farm_service::owned_farm(db, farmer_id, farm_id).await?;
+
+    let deleted = sqlx::query("DELETE FROM farm_plots WHERE id = $1 AND farm_id = $2")
+        .bind(plot_id)
+        .bind(farm_id)
+        .execute(&db.pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Plot not found".to_string()));
+    }
+    Ok(())
+}
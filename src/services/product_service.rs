@@ -0,0 +1,556 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        ConvertedPrice, ModerationEvent, NormalizedQuantity, Product, ProductListing, ProductPage, ProductQuery,
+        ProductSort, MODERATION_EVENT_COLUMNS, PRODUCT_COLUMNS, PRODUCT_STATUSES, PRODUCT_VISIBILITIES,
+    },
+    services::{
+        exchange_rate_service::{self, RateTable},
+        sms_service::SmsProvider,
+        unit_service::{self, UnitRegistry},
+    },
+    utils::{
+        clean_text, currency,
+        geo::{self, GeoPoint},
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Position of the last row on a page, in terms of the active sort order.
+#[derive(Debug, PartialEq)]
+enum CursorKey {
+    CreatedAt(DateTime<Utc>),
+    Price(i64),
+    Harvest(Option<NaiveDate>),
+    Distance(Option<f64>),
+}
+
+#[derive(Debug)]
+struct Cursor {
+    key: CursorKey,
+    id: Uuid,
+}
+
+fn invalid_cursor() -> AppError {
+    AppError::ValidationError("Invalid cursor".to_string())
+}
+
+/// Cursors are `sort|key|id`, hex-encoded so clients treat them as opaque.
+fn encode_cursor(sort: ProductSort, listing: &ProductListing) -> String {
+    let product = &listing.product;
+    let key = match sort {
+        ProductSort::Newest => product.created_at.to_rfc3339(),
+        ProductSort::PriceAsc | ProductSort::PriceDesc => product.price_cents.to_string(),
+        ProductSort::HarvestDate => product
+            .expected_harvest_date
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+        ProductSort::Distance => listing.distance_km.map(|d| d.to_string()).unwrap_or_default(),
+    };
+
+    format!("{}|{}|{}", sort.as_str(), key, product.id)
+        .bytes()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn decode_cursor(sort: ProductSort, cursor: &str) -> AppResult<Cursor> {
+    if !cursor.is_ascii() || !cursor.len().is_multiple_of(2) {
+        return Err(invalid_cursor());
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2).ok_or_else(invalid_cursor)?, 16).map_err(|_| invalid_cursor()))
+        .collect::<AppResult<Vec<u8>>>()?;
+    let raw = String::from_utf8(bytes).map_err(|_| invalid_cursor())?;
+
+    let mut parts = raw.splitn(3, '|');
+    let (Some(cursor_sort), Some(key), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid_cursor());
+    };
+    if cursor_sort != sort.as_str() {
+        return Err(AppError::ValidationError("Cursor does not match the requested sort".to_string()));
+    }
+
+    let key = match sort {
+        ProductSort::Newest => CursorKey::CreatedAt(
+            DateTime::parse_from_rfc3339(key).map_err(|_| invalid_cursor())?.with_timezone(&Utc),
+        ),
+        ProductSort::PriceAsc | ProductSort::PriceDesc => {
+            CursorKey::Price(key.parse().map_err(|_| invalid_cursor())?)
+        }
+        ProductSort::HarvestDate if key.is_empty() => CursorKey::Harvest(None),
+        ProductSort::HarvestDate => CursorKey::Harvest(Some(key.parse().map_err(|_| invalid_cursor())?)),
+        ProductSort::Distance if key.is_empty() => CursorKey::Distance(None),
+        ProductSort::Distance => CursorKey::Distance(Some(key.parse().map_err(|_| invalid_cursor())?)),
+    };
+
+    Ok(Cursor {
+        key,
+        id: id.parse().map_err(|_| invalid_cursor())?,
+    })
+}
+
+fn push_keyset(qb: &mut QueryBuilder<'_, Postgres>, sort: ProductSort, cursor: Cursor) {
+    match cursor.key {
+        CursorKey::CreatedAt(created_at) => {
+            qb.push(" AND (created_at, id) < (").push_bind(created_at);
+            qb.push(", ").push_bind(cursor.id).push(")");
+        }
+        CursorKey::Price(price) => {
+            let op = if sort == ProductSort::PriceDesc { "<" } else { ">" };
+            qb.push(format!(" AND (price_cents, id) {} (", op)).push_bind(price);
+            qb.push(", ").push_bind(cursor.id).push(")");
+        }
+        CursorKey::Harvest(Some(date)) => {
+            qb.push(" AND (expected_harvest_date > ").push_bind(date);
+            qb.push(" OR (expected_harvest_date = ").push_bind(date);
+            qb.push(" AND id > ").push_bind(cursor.id);
+            qb.push(") OR expected_harvest_date IS NULL)");
+        }
+        CursorKey::Harvest(None) => {
+            qb.push(" AND expected_harvest_date IS NULL AND id > ").push_bind(cursor.id);
+        }
+        CursorKey::Distance(Some(distance)) => {
+            qb.push(" AND (distance_km > ").push_bind(distance);
+            qb.push(" OR (distance_km = ").push_bind(distance);
+            qb.push(" AND id > ").push_bind(cursor.id);
+            qb.push(") OR distance_km IS NULL)");
+        }
+        CursorKey::Distance(None) => {
+            qb.push(" AND distance_km IS NULL AND id > ").push_bind(cursor.id);
+        }
+    }
+}
+
+/// Restates a listing's price and stock in `unit`, if its unit converts.
+fn normalize(units: &UnitRegistry, listing: &ProductListing, unit: &str) -> Option<NormalizedQuantity> {
+    let product = &listing.product;
+    // How many of `unit` one product unit holds.
+    let per_product_unit = units.convert(1.0, &product.unit, unit)?;
+    Some(NormalizedQuantity {
+        unit: unit.to_string(),
+        price_cents: (product.price_cents as f64 / per_product_unit).round() as i64,
+        quantity_available: product.quantity_available as f64 * per_product_unit,
+        min_order_qty: product.min_order_qty as f64 * per_product_unit,
+    })
+}
+
+/// Restates a listing's prices in `currency_code`, if there is a rate.
+fn convert_price(rates: &RateTable, listing: &ProductListing, currency_code: &str) -> Option<ConvertedPrice> {
+    let product = &listing.product;
+    let rate = rates.rate(&product.currency_code, currency_code)?;
+    let convert = |amount| exchange_rate_service::apply(amount, rate, &product.currency_code, currency_code);
+    Some(ConvertedPrice {
+        currency_code: currency_code.to_string(),
+        exchange_rate: rate,
+        price_cents: convert(product.price_cents),
+        normalized_price_cents: listing.normalized.as_ref().map(|normalized| convert(normalized.price_cents)),
+    })
+}
+
+/// Restricts to products whose farm's public grid point lies within
+/// `radius_km` of `point`.
+fn push_within(qb: &mut QueryBuilder<'_, Postgres>, point: GeoPoint, radius_km: f64) {
+    qb.push("farm_id IN (SELECT id FROM farms WHERE ST_DWithin(");
+    geo::push_public_location(qb, "location");
+    qb.push(", ");
+    geo::push_point(qb, point);
+    qb.push(", ").push_bind(radius_km * 1000.0).push("))");
+}
+
+/// Lists products matching `query`, one keyset-paginated page at a time.
+/// `viewer` is the authenticated farmer, if any; only they can list their own
+/// draft or archived products. `local_only` products are only listed to
+/// searchers within the configured local radius of the farm, or to their owner.
+pub async fn search_products(db: &Database, query: &ProductQuery, viewer: Option<Uuid>) -> AppResult<ProductPage> {
+    let status = query.status.as_deref().unwrap_or("published");
+    if !PRODUCT_STATUSES.contains(&status) {
+        return Err(AppError::ValidationError(format!(
+            "Status must be one of: {}",
+            PRODUCT_STATUSES.join(", ")
+        )));
+    }
+
+    let mut farmer_id = query.farmer_id;
+    if status != "published" {
+        let me = viewer.ok_or_else(|| AppError::Unauthorized("Log in to list unpublished products".to_string()))?;
+        if farmer_id.is_some_and(|id| id != me) {
+            return Err(AppError::ValidationError("Only your own unpublished products can be listed".to_string()));
+        }
+        farmer_id = Some(me);
+    }
+
+    if let Some(visibility) = &query.visibility {
+        if !PRODUCT_VISIBILITIES.contains(&visibility.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Visibility must be one of: {}",
+                PRODUCT_VISIBILITIES.join(", ")
+            )));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (query.min_price_cents, query.max_price_cents) {
+        if min > max {
+            return Err(AppError::ValidationError("min_price_cents must be <= max_price_cents".to_string()));
+        }
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::ValidationError(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let geo_config = geo::config();
+    let point = GeoPoint::from_query(query.lat, query.lng)?;
+    let radius_km = geo_config.radius_km(query.radius_km, point)?;
+
+    let sort = query
+        .sort
+        .unwrap_or(if point.is_some() { ProductSort::Distance } else { ProductSort::Newest });
+    if sort == ProductSort::Distance && point.is_none() {
+        return Err(AppError::ValidationError("Sorting by distance requires lat and lng".to_string()));
+    }
+
+    let units = unit_service::registry(db).await?;
+    let target_unit = query.unit.as_deref().map(|unit| units.canonical(unit)).transpose()?;
+    let target_currency = query.currency.as_deref().map(currency::resolve).transpose()?;
+
+    let cursor = query
+        .cursor
+        .as_deref()
+        .filter(|c| !c.is_empty())
+        .map(|c| decode_cursor(sort, c))
+        .transpose()?;
+
+    // Distance is computed in a derived table so filters, keyset conditions
+    // and ordering can all refer to `distance_km`. It is measured to the
+    // farm's public grid point and rounded like a farm listing's, so searches
+    // from several points can't triangulate the farm.
+    let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT {}, distance_km FROM (SELECT products.*, ", PRODUCT_COLUMNS));
+    match point {
+        Some(point) => {
+            qb.push("(SELECT ROUND((ST_Distance(");
+            geo::push_public_location(&mut qb, "f.location");
+            qb.push(", ");
+            geo::push_point(&mut qb, point);
+            qb.push(") / 1000.0)::NUMERIC)::FLOAT8 FROM farms f WHERE f.id = products.farm_id)");
+        }
+        None => {
+            qb.push("NULL::FLOAT8");
+        }
+    }
+    qb.push(" AS distance_km FROM products) products WHERE status = ");
+    qb.push_bind(status);
+
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        qb.push(" AND search_vector @@ websearch_to_tsquery('english', ").push_bind(q.to_string()).push(")");
+    }
+    if let Some(category) = &query.category {
+        qb.push(" AND category = ").push_bind(category.clone());
+    }
+    if let Some(tag) = &query.tag {
+        qb.push(" AND tags @> ARRAY[").push_bind(tag.clone()).push("]::TEXT[]");
+    }
+    if let Some(organic) = query.organic {
+        qb.push(" AND organic = ").push_bind(organic);
+    }
+    if let Some(perishable) = query.perishable {
+        qb.push(" AND perishable = ").push_bind(perishable);
+    }
+    if let Some(min) = query.min_price_cents {
+        qb.push(" AND price_cents >= ").push_bind(min);
+    }
+    if let Some(max) = query.max_price_cents {
+        qb.push(" AND price_cents <= ").push_bind(max);
+    }
+    // "both" products show up under either channel.
+    match query.visibility.as_deref() {
+        Some("both") => {
+            qb.push(" AND visibility = 'both'");
+        }
+        Some(visibility) => {
+            qb.push(" AND visibility IN ('both', ").push_bind(visibility.to_string()).push(")");
+        }
+        None => {}
+    }
+    if let Some(farm_id) = query.farm_id {
+        qb.push(" AND farm_id = ").push_bind(farm_id);
+    }
+    if let Some(farmer_id) = farmer_id {
+        qb.push(" AND farmer_id = ").push_bind(farmer_id);
+    }
+
+    if let (Some(point), Some(radius_km)) = (point, radius_km) {
+        qb.push(" AND ");
+        push_within(&mut qb, point, radius_km);
+    }
+
+    let own_listing = viewer.is_some() && farmer_id == viewer;
+    if !own_listing {
+        match point {
+            Some(point) => {
+                qb.push(" AND (visibility <> 'local_only' OR ");
+                push_within(&mut qb, point, geo_config.local_radius_km);
+                qb.push(")");
+            }
+            None => {
+                qb.push(" AND visibility <> 'local_only'");
+            }
+        }
+    }
+
+    if let Some(cursor) = cursor {
+        push_keyset(&mut qb, sort, cursor);
+    }
+
+    qb.push(match sort {
+        ProductSort::Newest => " ORDER BY created_at DESC, id DESC",
+        ProductSort::PriceAsc => " ORDER BY price_cents ASC, id ASC",
+        ProductSort::PriceDesc => " ORDER BY price_cents DESC, id DESC",
+        ProductSort::HarvestDate => " ORDER BY expected_harvest_date ASC NULLS LAST, id ASC",
+        ProductSort::Distance => " ORDER BY distance_km ASC NULLS LAST, id ASC",
+    });
+    qb.push(" LIMIT ").push_bind(limit + 1);
+
+    let mut items = qb.build_query_as::<ProductListing>().fetch_all(&db.pool).await?;
+
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|last| encode_cursor(sort, last))
+    } else {
+        None
+    };
+
+    if let Some(unit) = &target_unit {
+        for listing in &mut items {
+            listing.normalized = normalize(&units, listing, unit);
+        }
+    }
+    if let Some(currency) = target_currency {
+        let rates = exchange_rate_service::rates(db).await?;
+        for listing in &mut items {
+            listing.converted = convert_price(&rates, listing, currency.code);
+        }
+    }
+
+    Ok(ProductPage { items, next_cursor })
+}
+
+/// Takes a product off the marketplace. Carts holding it can no longer check
+/// out; orders already placed are unaffected. The farmer is told why by SMS.
+pub async fn suspend_product(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    staff_id: Uuid,
+    product_id: Uuid,
+    reason: Option<String>,
+) -> AppResult<Product> {
+    let reason = clean_text(reason)
+        .ok_or_else(|| AppError::ValidationError("A reason is required to suspend a product".to_string()))?;
+    let product = moderate(db, staff_id, product_id, "suspend", Some(&reason)).await?;
+
+    let message = format!("Your product \"{}\" was suspended: {}", product.name, reason);
+    notify_farmer(db, sms, &product, &message).await;
+    Ok(product)
+}
+
+/// Returns a suspended product to draft so the farmer can fix and republish it.
+pub async fn reinstate_product(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    staff_id: Uuid,
+    product_id: Uuid,
+    reason: Option<String>,
+) -> AppResult<Product> {
+    let reason = clean_text(reason);
+    let product = moderate(db, staff_id, product_id, "reinstate", reason.as_deref()).await?;
+
+    let message = format!("Your product \"{}\" was reinstated as a draft. Publish it again when ready.", product.name);
+    notify_farmer(db, sms, &product, &message).await;
+    Ok(product)
+}
+
+async fn moderate(
+    db: &Database,
+    staff_id: Uuid,
+    product_id: Uuid,
+    action: &str,
+    reason: Option<&str>,
+) -> AppResult<Product> {
+    let mut tx = db.pool.begin().await?;
+    let status: String = sqlx::query_scalar("SELECT status FROM products WHERE id = $1 FOR UPDATE")
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+    let to = match (action, status.as_str()) {
+        ("suspend", "suspended") => {
+            return Err(AppError::ValidationError("Product is already suspended".to_string()))
+        }
+        ("suspend", _) => "suspended",
+        ("reinstate", "suspended") => "draft",
+        _ => return Err(AppError::ValidationError("Product is not suspended".to_string())),
+    };
+
+    let product = sqlx::query_as::<_, Product>(&format!(
+        "UPDATE products SET status = $2 WHERE id = $1 RETURNING {}",
+        PRODUCT_COLUMNS
+    ))
+    .bind(product_id)
+    .bind(to)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("INSERT INTO product_moderation_events (product_id, staff_id, action, reason) VALUES ($1, $2, $3, $4)")
+        .bind(product_id)
+        .bind(staff_id)
+        .bind(action)
+        .bind(reason)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(product)
+}
+
+async fn notify_farmer(db: &Database, sms: &dyn SmsProvider, product: &Product, message: &str) {
+    let phone_number: Option<String> = match sqlx::query_scalar("SELECT phone_number FROM farmers WHERE id = $1")
+        .bind(product.farmer_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(phone_number) => phone_number,
+        Err(e) => {
+            log::warn!("Failed to look up phone number for product {}: {}", product.id, e);
+            return;
+        }
+    };
+    let Some(phone_number) = phone_number else { return };
+    if let Err(e) = sms.send(&phone_number, message).await {
+        log::warn!("Failed to send product {} moderation SMS: {}", product.id, e);
+    }
+}
+
+/// Moderation history of a product, oldest first.
+pub async fn list_moderation_events(db: &Database, product_id: Uuid) -> AppResult<Vec<ModerationEvent>> {
+    Ok(sqlx::query_as::<_, ModerationEvent>(&format!(
+        "SELECT {} FROM product_moderation_events WHERE product_id = $1 ORDER BY created_at, id",
+        MODERATION_EVENT_COLUMNS
+    ))
+    .bind(product_id)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(distance_km: Option<f64>, expected_harvest_date: Option<NaiveDate>) -> ProductListing {
+        let now = Utc::now();
+        ProductListing {
+            product: Product {
+                id: Uuid::new_v4(),
+                farmer_id: Uuid::new_v4(),
+                farm_id: None,
+                name: "Tomatoes".to_string(),
+                slug: "tomatoes".to_string(),
+                description: None,
+                category: "vegetables".to_string(),
+                unit: "crate".to_string(),
+                tags: Vec::new(),
+                price_cents: 1_250_000,
+                currency_code: "NGN".to_string(),
+                min_order_qty: 1,
+                quantity_available: 12,
+                organic: false,
+                perishable: true,
+                expected_harvest_date,
+                expiry_date: None,
+                status: "published".to_string(),
+                visibility: "both".to_string(),
+                images: Vec::new(),
+                created_at: now,
+                updated_at: now,
+            },
+            distance_km,
+            normalized: None,
+            converted: None,
+        }
+    }
+
+    fn round_trip(sort: ProductSort, listing: &ProductListing) -> CursorKey {
+        let cursor = decode_cursor(sort, &encode_cursor(sort, listing)).unwrap();
+        assert_eq!(cursor.id, listing.product.id);
+        cursor.key
+    }
+
+    #[test]
+    fn cursors_round_trip_for_every_sort() {
+        let harvest = NaiveDate::from_ymd_opt(2026, 11, 2);
+        let listing = listing(Some(14.0), harvest);
+        let product = &listing.product;
+
+        assert_eq!(round_trip(ProductSort::Newest, &listing), CursorKey::CreatedAt(product.created_at));
+        assert_eq!(round_trip(ProductSort::PriceAsc, &listing), CursorKey::Price(product.price_cents));
+        assert_eq!(round_trip(ProductSort::PriceDesc, &listing), CursorKey::Price(product.price_cents));
+        assert_eq!(round_trip(ProductSort::HarvestDate, &listing), CursorKey::Harvest(harvest));
+        assert_eq!(round_trip(ProductSort::Distance, &listing), CursorKey::Distance(Some(14.0)));
+    }
+
+    #[test]
+    fn cursors_round_trip_missing_keys() {
+        let listing = listing(None, None);
+        assert_eq!(round_trip(ProductSort::HarvestDate, &listing), CursorKey::Harvest(None));
+        assert_eq!(round_trip(ProductSort::Distance, &listing), CursorKey::Distance(None));
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let hex = |raw: &str| raw.bytes().map(|b| format!("{:02x}", b)).collect::<String>();
+        let id = Uuid::new_v4();
+        for (sort, cursor) in [
+            (ProductSort::PriceAsc, "abc".to_string()),
+            (ProductSort::PriceAsc, "zz".to_string()),
+            (ProductSort::PriceAsc, "é1".to_string()),
+            (ProductSort::PriceAsc, hex("price_asc|100")),
+            (ProductSort::PriceAsc, hex(&format!("price_asc|cheap|{}", id))),
+            (ProductSort::PriceAsc, hex("price_asc|100|not-a-uuid")),
+            (ProductSort::Newest, hex(&format!("newest|yesterday|{}", id))),
+            (ProductSort::HarvestDate, hex(&format!("harvest_date|2026-13-01|{}", id))),
+            (ProductSort::Distance, hex(&format!("distance|near|{}", id))),
+        ] {
+            let result = decode_cursor(sort, &cursor);
+            assert!(
+                matches!(&result, Err(AppError::ValidationError(message)) if message == "Invalid cursor"),
+                "{:?}: {:?}",
+                cursor,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn cursors_only_work_with_their_own_sort() {
+        let listing = listing(Some(3.0), None);
+        let cursor = encode_cursor(ProductSort::PriceAsc, &listing);
+        for sort in [ProductSort::PriceDesc, ProductSort::Newest, ProductSort::Distance] {
+            let result = decode_cursor(sort, &cursor);
+            assert!(
+                matches!(&result, Err(AppError::ValidationError(message)) if message.contains("does not match")),
+                "{:?}: {:?}",
+                sort,
+                result
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use sqlx::Row;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+};
+
+/// At most `limit` events per `window_secs` for one (action, subject) pair,
+/// e.g. OTP sends per phone number.
+pub struct RateLimit {
+    pub action: &'static str,
+    pub limit: i64,
+    pub window_secs: i64,
+}
+
+impl RateLimit {
+    /// Fails once `hits` events fall in the window ending at `now`. The caller
+    /// may retry when the oldest of them, at `oldest`, drops out of it.
+    fn check(&self, hits: i64, oldest: Option<DateTime<Utc>>, now: DateTime<Utc>) -> AppResult<()> {
+        if hits < self.limit {
+            return Ok(());
+        }
+        let retry_after_secs = match oldest {
+            Some(oldest) => {
+                let millis = (oldest + Duration::seconds(self.window_secs) - now).num_milliseconds();
+                (millis + 999).div_euclid(1000)
+            }
+            None => self.window_secs,
+        };
+        Err(AppError::RateLimited {
+            message: "Too many requests, please try again later".to_string(),
+            retry_after_secs: retry_after_secs.max(1),
+        })
+    }
+}
+
+/// Records one event for `subject`, or fails with `AppError::RateLimited` if
+/// the limit is already reached. Counts live in Postgres so every instance
+/// shares them.
+pub async fn hit(db: &Database, rule: &RateLimit, subject: &str) -> AppResult<()> {
+    hit_all(db, &[(rule, subject)]).await
+}
+
+/// Like `hit` for several limits at once: an event is recorded against every
+/// one of them, or against none if any is already reached.
+pub async fn hit_all(db: &Database, hits: &[(&RateLimit, &str)]) -> AppResult<()> {
+    let mut tx = db.pool.begin().await?;
+
+    // Serialise concurrent hits on the same bucket so two requests can't both
+    // see `limit - 1` and slip through. Locks are taken in a fixed order so
+    // overlapping calls can't deadlock.
+    let mut buckets: Vec<String> = hits.iter().map(|(rule, subject)| format!("{}:{}", rule.action, subject)).collect();
+    buckets.sort();
+    buckets.dedup();
+    for bucket in &buckets {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(bucket)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for (rule, subject) in hits {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) AS hits, MIN(created_at) AS oldest, NOW() AS now
+            FROM rate_limit_events
+            WHERE action = $1 AND subject = $2 AND created_at > NOW() - make_interval(secs => $3)
+            "#,
+        )
+        .bind(rule.action)
+        .bind(subject)
+        .bind(rule.window_secs as f64)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        rule.check(row.get("hits"), row.get("oldest"), row.get("now"))?;
+    }
+
+    for (rule, subject) in hits {
+        sqlx::query("INSERT INTO rate_limit_events (action, subject) VALUES ($1, $2)")
+            .bind(rule.action)
+            .bind(subject)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    // Opportunistic cleanup; no window is longer than a day.
+    if rand::thread_rng().gen_ratio(1, 100) {
+        sqlx::query("DELETE FROM rate_limit_events WHERE created_at < NOW() - INTERVAL '1 day'")
+            .execute(&db.pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULE: RateLimit = RateLimit { action: "test", limit: 3, window_secs: 60 };
+
+    fn retry_after(result: AppResult<()>) -> i64 {
+        match result {
+            Err(AppError::RateLimited { retry_after_secs, .. }) => retry_after_secs,
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn hits_below_the_limit_pass() {
+        let now = Utc::now();
+        assert!(RULE.check(0, None, now).is_ok());
+        assert!(RULE.check(2, Some(now - Duration::seconds(10)), now).is_ok());
+    }
+
+    #[test]
+    fn retry_after_is_when_the_oldest_hit_leaves_the_window() {
+        let now = Utc::now();
+        assert_eq!(retry_after(RULE.check(3, Some(now - Duration::seconds(10)), now)), 50);
+        assert_eq!(retry_after(RULE.check(4, Some(now), now)), 60);
+    }
+
+    #[test]
+    fn retry_after_rounds_up_to_at_least_a_second() {
+        let now = Utc::now();
+        assert_eq!(retry_after(RULE.check(3, Some(now - Duration::milliseconds(10_500)), now)), 50);
+        assert_eq!(retry_after(RULE.check(3, Some(now - Duration::milliseconds(59_999)), now)), 1);
+        assert_eq!(retry_after(RULE.check(3, Some(now - Duration::seconds(61)), now)), 1);
+    }
+
+    #[test]
+    fn a_zero_limit_waits_a_whole_window() {
+        let rule = RateLimit { limit: 0, ..RULE };
+        assert_eq!(retry_after(rule.check(0, None, Utc::now())), 60);
+    }
+}
@@ -0,0 +1,85 @@
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::ActiveSession,
+    session_store::SessionStoreKind,
+};
+
+fn ensure_server_side(store: SessionStoreKind) -> AppResult<()> {
+    if store != SessionStoreKind::Postgres {
+        return Err(AppError::ValidationError(
+            "Session management requires SESSION_STORE=postgres".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_sessions(
+    db: &Database,
+    store: SessionStoreKind,
+    account_id: Uuid,
+    current: Option<Uuid>,
+) -> AppResult<Vec<ActiveSession>> {
+    ensure_server_side(store)?;
+
+    let mut sessions = sqlx::query_as::<_, ActiveSession>(
+        r#"
+        SELECT session_id, user_agent, created_at, last_seen_at, expires_at
+        FROM sessions
+        WHERE $1 IN (farmer_id, buyer_id, staff_id) AND session_id IS NOT NULL AND expires_at > NOW()
+        ORDER BY last_seen_at DESC
+        "#,
+    )
+    .bind(account_id)
+    .fetch_all(&db.pool)
+    .await?;
+
+    for session in &mut sessions {
+        session.current = Some(session.session_id) == current;
+    }
+
+    Ok(sessions)
+}
+
+pub async fn revoke_session(
+    db: &Database,
+    store: SessionStoreKind,
+    account_id: Uuid,
+    session_id: Uuid,
+) -> AppResult<()> {
+    ensure_server_side(store)?;
+
+    let result = sqlx::query("DELETE FROM sessions WHERE $1 IN (farmer_id, buyer_id, staff_id) AND session_id = $2")
+        .bind(account_id)
+        .bind(session_id)
+        .execute(&db.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Session not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// "Log out other devices": drops every session of the account except `current`.
+pub async fn revoke_other_sessions(
+    db: &Database,
+    store: SessionStoreKind,
+    account_id: Uuid,
+    current: Option<Uuid>,
+) -> AppResult<u64> {
+    ensure_server_side(store)?;
+
+    let result = sqlx::query(
+        "DELETE FROM sessions WHERE $1 IN (farmer_id, buyer_id, staff_id) AND session_id IS DISTINCT FROM $2",
+    )
+    .bind(account_id)
+    .bind(current)
+    .execute(&db.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,54 +1,245 @@
 use crate::errors::{AppError, AppResult};
+use crate::utils::phone;
 
-
-
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::env;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
-pub async fn send_sms_twilio(phone_number: &str, message: &str) -> AppResult<()> {
-    let account_sid = env::var("TWILIO_ACCOUNT_SID")
-        .map_err(|_| AppError::InternalError("TWILIO_ACCOUNT_SID not set".to_string()))?;
-    let auth_token = env::var("TWILIO_AUTH_TOKEN")
-        .map_err(|_| AppError::InternalError("TWILIO_AUTH_TOKEN not set".to_string()))?;
-    let from_number = env::var("TWILIO_PHONE_NUMBER")
-        .map_err(|_| AppError::InternalError("TWILIO_PHONE_NUMBER not set".to_string()))?;
-
-    let client = Client::new();
-    let url = format!("https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json", account_sid);
-    let phone_number = tweak_phone_number(phone_number).unwrap();
-    let response = client
-        .post(&url)
-        .basic_auth(&account_sid, Some(&auth_token))
-        .form(&[
-            ("From", from_number.as_str()),
-            ("To", &phone_number),
-            ("Body", message),
-        ])
-        .send()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to send SMS: {}", e)))?;
-
-    if response.status().is_success() {
-        log::info!("SMS sent successfully to {}", phone_number);
-        return Ok(());
-    } else {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return  Err(AppError::InternalError(format!("SMS sending failed: {}", error_text)))
-    }
-
-    Ok(())
-}
-
-fn tweak_phone_number(phone_number: &str) -> Option<String>{
-    if phone_number.starts_with("0") && phone_number.len() == 11{
-        Some(format!("+234{}" , &phone_number[1..]))
-    }
-    else if phone_number.starts_with("+234") && phone_number.len() == 14 {
-        Some(phone_number.to_string())
-    }
-    else{
+/// Outbound SMS gateway. The active implementation is picked at startup by
+/// `provider_from_env` and shared with handlers as `web::Data<dyn SmsProvider>`.
+#[async_trait]
+pub trait SmsProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The local outbox behind this provider, if it keeps one.
+    fn outbox(&self) -> Option<&OutboxProvider> {
         None
     }
+
+    async fn send(&self, phone_number: &str, message: &str) -> AppResult<()>;
+}
+
+/// Builds the provider named by `SMS_PROVIDER` ("twilio", "termii" or "outbox").
+/// Defaults to Twilio so existing deployments keep their behaviour.
+pub fn provider_from_env() -> AppResult<Arc<dyn SmsProvider>> {
+    let provider = env::var("SMS_PROVIDER").unwrap_or_else(|_| "twilio".to_string());
+
+    let provider: Arc<dyn SmsProvider> = match provider.to_lowercase().as_str() {
+        "twilio" => Arc::new(TwilioProvider::from_env()?),
+        "termii" => Arc::new(TermiiProvider::from_env()?),
+        "outbox" => Arc::new(OutboxProvider::from_env()),
+        other => {
+            return Err(AppError::InternalError(format!("Unknown SMS_PROVIDER: {}", other)));
+        }
+    };
+
+    log::info!("Using {} SMS provider", provider.name());
+    Ok(provider)
+}
+
+fn required_env(key: &str) -> AppResult<String> {
+    env::var(key).map_err(|_| AppError::InternalError(format!("{} not set", key)))
+}
+
+pub struct TwilioProvider {
+    client: Client,
+    account_sid: String,
+    auth_token: String,
+    from_number: String,
+}
+
+impl TwilioProvider {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(TwilioProvider {
+            client: Client::new(),
+            account_sid: required_env("TWILIO_ACCOUNT_SID")?,
+            auth_token: required_env("TWILIO_AUTH_TOKEN")?,
+            from_number: required_env("TWILIO_PHONE_NUMBER")?,
+        })
+    }
+}
+
+#[async_trait]
+impl SmsProvider for TwilioProvider {
+    fn name(&self) -> &'static str {
+        "twilio"
+    }
+
+    async fn send(&self, phone_number: &str, message: &str) -> AppResult<()> {
+        let url = format!("https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json", self.account_sid);
+        let phone_number = phone::normalize(phone_number)?;
+        let response = self.client
+            .post(&url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[
+                ("From", self.from_number.as_str()),
+                ("To", &phone_number),
+                ("Body", message),
+            ])
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to send SMS: {}", e)))?;
+
+        if response.status().is_success() {
+            log::info!("SMS sent successfully to {}", phone_number);
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            Err(AppError::InternalError(format!("SMS sending failed: {}", error_text)))
+        }
+    }
+}
+
+/// Termii-style JSON gateway. Africa's Talking and similar local aggregators
+/// take the same shape (api key, sender id, recipient without the leading '+').
+pub struct TermiiProvider {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    sender_id: String,
+    channel: String,
+}
+
+impl TermiiProvider {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(TermiiProvider {
+            client: Client::new(),
+            base_url: env::var("TERMII_BASE_URL").unwrap_or_else(|_| "https://api.ng.termii.com".to_string()),
+            api_key: required_env("TERMII_API_KEY")?,
+            sender_id: required_env("TERMII_SENDER_ID")?,
+            channel: env::var("TERMII_CHANNEL").unwrap_or_else(|_| "generic".to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl SmsProvider for TermiiProvider {
+    fn name(&self) -> &'static str {
+        "termii"
+    }
+
+    async fn send(&self, phone_number: &str, message: &str) -> AppResult<()> {
+        let url = format!("{}/api/sms/send", self.base_url.trim_end_matches('/'));
+        let phone_number = phone::normalize(phone_number)?;
+        let response = self.client
+            .post(&url)
+            .json(&json!({
+                "api_key": self.api_key,
+                "to": phone_number.trim_start_matches('+'),
+                "from": self.sender_id,
+                "sms": message,
+                "type": "plain",
+                "channel": self.channel,
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to send SMS: {}", e)))?;
+
+        if response.status().is_success() {
+            log::info!("SMS sent successfully to {}", phone_number);
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            Err(AppError::InternalError(format!("SMS sending failed: {}", error_text)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    pub phone_number: String,
+    pub message: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Local sink for development and tests: nothing leaves the machine. Messages
+/// are logged to the console and kept in memory, or appended as JSON lines to
+/// `SMS_OUTBOX_PATH` when it is set.
+pub struct OutboxProvider {
+    path: Option<PathBuf>,
+    messages: Mutex<Vec<OutboxMessage>>,
+}
+
+impl OutboxProvider {
+    pub fn in_memory() -> Self {
+        OutboxProvider { path: None, messages: Mutex::new(Vec::new()) }
+    }
+
+    pub fn with_file(path: impl Into<PathBuf>) -> Self {
+        OutboxProvider { path: Some(path.into()), messages: Mutex::new(Vec::new()) }
+    }
+
+    pub fn from_env() -> Self {
+        match env::var("SMS_OUTBOX_PATH") {
+            Ok(path) if !path.is_empty() => Self::with_file(path),
+            _ => Self::in_memory(),
+        }
+    }
+
+    /// Everything sent so far, oldest first.
+    pub fn messages(&self) -> AppResult<Vec<OutboxMessage>> {
+        let Some(path) = &self.path else {
+            return Ok(self.messages.lock().unwrap().clone());
+        };
+
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::InternalError(format!("Failed to read SMS outbox: {}", e))),
+        };
+
+        BufReader::new(file)
+            .lines()
+            .map(|line| {
+                let line = line.map_err(|e| AppError::InternalError(format!("Failed to read SMS outbox: {}", e)))?;
+                serde_json::from_str(&line)
+                    .map_err(|e| AppError::InternalError(format!("Corrupt SMS outbox entry: {}", e)))
+            })
+            .collect()
+    }
 }
 
+#[async_trait]
+impl SmsProvider for OutboxProvider {
+    fn name(&self) -> &'static str {
+        "outbox"
+    }
+
+    fn outbox(&self) -> Option<&OutboxProvider> {
+        Some(self)
+    }
+
+    async fn send(&self, phone_number: &str, message: &str) -> AppResult<()> {
+        let entry = OutboxMessage {
+            phone_number: phone_number.to_string(),
+            message: message.to_string(),
+            sent_at: Utc::now(),
+        };
+
+        log::info!("[sms outbox] to {}: {}", entry.phone_number, entry.message);
+
+        match &self.path {
+            Some(path) => {
+                let line = serde_json::to_string(&entry)
+                    .map_err(|e| AppError::InternalError(format!("Failed to encode SMS: {}", e)))?;
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| AppError::InternalError(format!("Failed to open SMS outbox: {}", e)))?;
+                writeln!(file, "{}", line)
+                    .map_err(|e| AppError::InternalError(format!("Failed to write SMS outbox: {}", e)))?;
+            }
+            None => self.messages.lock().unwrap().push(entry),
+        }
+
+        Ok(())
+    }
+}
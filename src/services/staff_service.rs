@@ -0,0 +1,321 @@
+use sqlx::QueryBuilder;
+use std::env;
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{
+        AccountRole, CreateStaffRequest, Farm, OtpPurpose, Permission, Staff, StaffQuery, StaffSession,
+        UpdateStaffRequest, VerifyPhoneRequest, FARM_COLUMNS, STAFF_COLUMNS, STAFF_ROLES,
+    },
+    services::{otp_service, sms_service::SmsProvider},
+    utils::{one_of, phone, required_text},
+};
+
+/// Roles that are given farms to look after.
+const ASSIGNABLE_ROLES: &[&str] = &["extension_officer", "coop_manager"];
+
+fn staff_role(role: &str) -> AppResult<AccountRole> {
+    one_of("role", role, STAFF_ROLES)?;
+    AccountRole::staff_role(role).ok_or_else(|| AppError::InternalError(format!("Unknown staff role {}", role)))
+}
+
+/// Creates the admin named by `BOOTSTRAP_ADMIN_PHONE` when there is no admin
+/// yet, so a fresh deployment has someone to create the other staff.
+pub async fn bootstrap_admin(db: &Database) -> AppResult<()> {
+    let Some(phone_number) = env::var("BOOTSTRAP_ADMIN_PHONE").ok().filter(|v| !v.is_empty()) else {
+        return Ok(());
+    };
+    let phone_number = phone::normalize(&phone_number)?;
+
+    let created = sqlx::query(
+        "INSERT INTO staff (phone_number, first_name, last_name, role) \
+         SELECT $1, 'Platform', 'Admin', 'admin' \
+         WHERE NOT EXISTS (SELECT 1 FROM staff WHERE role = 'admin') \
+         ON CONFLICT (phone_number) DO NOTHING",
+    )
+    .bind(&phone_number)
+    .execute(&db.pool)
+    .await?;
+
+    if created.rows_affected() > 0 {
+        log::info!("Created bootstrap admin {}", phone_number);
+    }
+    Ok(())
+}
+
+pub async fn create_staff(db: &Database, created_by: Uuid, request: CreateStaffRequest) -> AppResult<Staff> {
+    let phone_number = phone::normalize(&request.phone_number)?;
+    let first_name = required_text("first_name", &request.first_name)?;
+    let last_name = required_text("last_name", &request.last_name)?;
+    staff_role(&request.role)?;
+
+    let existing: Option<Uuid> = sqlx::query_scalar("SELECT id FROM staff WHERE phone_number = $1")
+        .bind(&phone_number)
+        .fetch_optional(&db.pool)
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::ValidationError("Phone number already registered".to_string()));
+    }
+
+    Ok(sqlx::query_as::<_, Staff>(&format!(
+        "INSERT INTO staff (phone_number, first_name, last_name, role, created_by) \
+         VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+        STAFF_COLUMNS
+    ))
+    .bind(&phone_number)
+    .bind(first_name)
+    .bind(last_name)
+    .bind(&request.role)
+    .bind(created_by)
+    .fetch_one(&db.pool)
+    .await?)
+}
+
+pub async fn list_staff(db: &Database, query: &StaffQuery) -> AppResult<Vec<Staff>> {
+    let mut qb = QueryBuilder::new(format!("SELECT {} FROM staff WHERE TRUE", STAFF_COLUMNS));
+    if let Some(role) = &query.role {
+        one_of("role", role, STAFF_ROLES)?;
+        qb.push(" AND role = ").push_bind(role);
+    }
+    if let Some(is_active) = query.is_active {
+        qb.push(" AND is_active = ").push_bind(is_active);
+    }
+    qb.push(" ORDER BY created_at, id");
+
+    Ok(qb.build_query_as::<Staff>().fetch_all(&db.pool).await?)
+}
+
+pub async fn get_staff(db: &Database, staff_id: Uuid) -> AppResult<Staff> {
+    sqlx::query_as::<_, Staff>(&format!("SELECT {} FROM staff WHERE id = $1", STAFF_COLUMNS))
+        .bind(staff_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Staff member not found".to_string()))
+}
+
+/// Updates a staff member. Admins can't demote or deactivate themselves, so
+/// there is always someone left to manage staff. Deactivation ends the staff
+/// member's server-side sessions and refresh tokens. Cookie sessions and
+/// access tokens can't be revoked, but the auth layer re-reads staff accounts
+/// on every request, so they stop working straight away.
+pub async fn update_staff(db: &Database, actor_id: Uuid, staff_id: Uuid, request: UpdateStaffRequest) -> AppResult<Staff> {
+    let first_name = request.first_name.as_deref().map(|name| required_text("first_name", name)).transpose()?;
+    let last_name = request.last_name.as_deref().map(|name| required_text("last_name", name)).transpose()?;
+    if let Some(role) = &request.role {
+        staff_role(role)?;
+    }
+    let demotes = request.role.as_deref().is_some_and(|role| role != "admin");
+    if staff_id == actor_id && (demotes || request.is_active == Some(false)) {
+        return Err(AppError::ValidationError("You can't demote or deactivate yourself".to_string()));
+    }
+
+    let mut tx = db.pool.begin().await?;
+    let staff = sqlx::query_as::<_, Staff>(&format!(
+        r#"
+        UPDATE staff SET
+            first_name = COALESCE($2, first_name),
+            last_name = COALESCE($3, last_name),
+            role = COALESCE($4, role),
+            is_active = COALESCE($5, is_active)
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        STAFF_COLUMNS
+    ))
+    .bind(staff_id)
+    .bind(first_name)
+    .bind(last_name)
+    .bind(&request.role)
+    .bind(request.is_active)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Staff member not found".to_string()))?;
+
+    // A role change also ends existing logins, since sessions and access
+    // tokens carry the old role.
+    if !staff.is_active || request.role.is_some() {
+        sqlx::query("DELETE FROM sessions WHERE staff_id = $1")
+            .bind(staff_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE staff_id = $1 AND revoked_at IS NULL")
+            .bind(staff_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    if !ASSIGNABLE_ROLES.contains(&staff.role.as_str()) {
+        sqlx::query("DELETE FROM staff_farm_assignments WHERE staff_id = $1")
+            .bind(staff_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(staff)
+}
+
+/// Looks up the active staff member with `role` behind a phone number.
+async fn find_by_phone(db: &Database, phone_number: &str, role: AccountRole) -> AppResult<Staff> {
+    sqlx::query_as::<_, Staff>(&format!(
+        "SELECT {} FROM staff WHERE phone_number = $1 AND role = $2 AND is_active",
+        STAFF_COLUMNS
+    ))
+    .bind(phone_number)
+    .bind(role.as_str())
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::ValidationError(format!("No active {} account for this phone number", role.as_str())))
+}
+
+/// Sends a login code to an active staff member. Staff are registered by an
+/// admin, so there is no registration code.
+pub async fn request_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    role: AccountRole,
+    purpose: OtpPurpose,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let phone_number = phone::normalize(phone_number)?;
+    if purpose == OtpPurpose::Registration {
+        return Err(AppError::ValidationError(
+            "Staff accounts are created by an admin, log in instead".to_string(),
+        ));
+    }
+    find_by_phone(db, &phone_number, role).await?;
+
+    otp_service::send_otp(db, sms, &phone_number, purpose, client_ip).await
+}
+
+/// Sends a fresh code, reusing the purpose of the last code when none is given.
+pub async fn resend_otp(
+    db: &Database,
+    sms: &dyn SmsProvider,
+    phone_number: &str,
+    role: AccountRole,
+    purpose: Option<OtpPurpose>,
+    client_ip: Option<&str>,
+) -> AppResult<()> {
+    let phone_number = phone::normalize(phone_number)?;
+    let purpose = match purpose {
+        Some(purpose) => purpose,
+        None => otp_service::last_purpose(db, &phone_number).await?,
+    };
+
+    request_otp(db, sms, &phone_number, role, purpose, client_ip).await
+}
+
+/// Checks the code and returns the staff member to log in. Their first login
+/// verifies the phone.
+pub async fn verify_staff(
+    db: &Database,
+    role: AccountRole,
+    request: VerifyPhoneRequest,
+    client_ip: Option<&str>,
+) -> AppResult<Staff> {
+    let phone_number = otp_service::verify_otp(db, &request, client_ip).await?;
+    let staff = find_by_phone(db, &phone_number, role).await?;
+
+    Ok(sqlx::query_as::<_, Staff>(&format!(
+        "UPDATE staff SET verification_status = 'phone_verified' WHERE id = $1 RETURNING {}",
+        STAFF_COLUMNS
+    ))
+    .bind(staff.id)
+    .fetch_one(&db.pool)
+    .await?)
+}
+
+/// Rebuilds the session payload for a staff member, e.g. when refreshing a
+/// token. Deactivated staff can't log back in.
+pub async fn staff_session(db: &Database, staff_id: Uuid) -> AppResult<StaffSession> {
+    let staff = sqlx::query_as::<_, Staff>(&format!(
+        "SELECT {} FROM staff WHERE id = $1 AND is_active",
+        STAFF_COLUMNS
+    ))
+    .bind(staff_id)
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized("Staff account no longer exists or is inactive".to_string()))?;
+
+    Ok(StaffSession {
+        staff_id,
+        name: staff.first_name,
+        role: staff_role(&staff.role)?,
+    })
+}
+
+/// The owner of a farm the staff member may read: any farm for admins,
+/// otherwise only farms assigned to them.
+pub async fn readable_farm_owner(db: &Database, staff: &StaffSession, farm_id: Uuid) -> AppResult<Uuid> {
+    let row: Option<(Uuid, bool)> = sqlx::query_as(
+        "SELECT f.farmer_id, EXISTS (SELECT 1 FROM staff_farm_assignments a WHERE a.farm_id = f.id AND a.staff_id = $2) \
+         FROM farms f WHERE f.id = $1",
+    )
+    .bind(farm_id)
+    .bind(staff.staff_id)
+    .fetch_optional(&db.pool)
+    .await?;
+    let (farmer_id, assigned) = row.ok_or_else(|| AppError::NotFound("Farm not found".to_string()))?;
+
+    if staff.role.can(Permission::ReadAnyFarm) || (assigned && staff.role.can(Permission::ReadAssignedFarms)) {
+        return Ok(farmer_id);
+    }
+    Err(AppError::Forbidden("This farm is not assigned to you".to_string()))
+}
+
+/// Farms assigned to a staff member.
+pub async fn assigned_farms(db: &Database, staff_id: Uuid) -> AppResult<Vec<Farm>> {
+    Ok(sqlx::query_as::<_, Farm>(&format!(
+        "SELECT {} FROM farms WHERE id IN (SELECT farm_id FROM staff_farm_assignments WHERE staff_id = $1) \
+         ORDER BY created_at, id",
+        FARM_COLUMNS
+    ))
+    .bind(staff_id)
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+/// Assigns a farm to an extension officer or cooperative manager. Assigning
+/// the same farm twice is a no-op.
+pub async fn assign_farm(db: &Database, assigned_by: Uuid, staff_id: Uuid, farm_id: Uuid) -> AppResult<Vec<Farm>> {
+    let staff = get_staff(db, staff_id).await?;
+    if !ASSIGNABLE_ROLES.contains(&staff.role.as_str()) {
+        return Err(AppError::ValidationError(
+            "Only extension officers and cooperative managers are assigned farms".to_string(),
+        ));
+    }
+    let farm_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM farms WHERE id = $1)")
+        .bind(farm_id)
+        .fetch_one(&db.pool)
+        .await?;
+    if !farm_exists {
+        return Err(AppError::NotFound("Farm not found".to_string()));
+    }
+
+    sqlx::query(
+        "INSERT INTO staff_farm_assignments (staff_id, farm_id, assigned_by) VALUES ($1, $2, $3) \
+         ON CONFLICT (staff_id, farm_id) DO NOTHING",
+    )
+    .bind(staff_id)
+    .bind(farm_id)
+    .bind(assigned_by)
+    .execute(&db.pool)
+    .await?;
+
+    assigned_farms(db, staff_id).await
+}
+
+pub async fn unassign_farm(db: &Database, staff_id: Uuid, farm_id: Uuid) -> AppResult<()> {
+    let result = sqlx::query("DELETE FROM staff_farm_assignments WHERE staff_id = $1 AND farm_id = $2")
+        .bind(staff_id)
+        .bind(farm_id)
+        .execute(&db.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Farm is not assigned to this staff member".to_string()));
+    }
+    Ok(())
+}
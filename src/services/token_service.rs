@@ -0,0 +1,353 @@
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, Row};
+use std::env;
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{AccountRole, BuyerSession, FarmerSession, SessionAccount, StaffSession},
+    services::{buyer_service, farmer_service, staff_service},
+};
+
+const ISSUER: &str = "agro-app";
+
+/// Bearer token settings. Issuance is disabled unless `JWT_SECRET` is set.
+#[derive(Clone)]
+pub struct TokenConfig {
+    secret: Option<String>,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+}
+
+impl TokenConfig {
+    /// Reads `JWT_SECRET`, `JWT_ACCESS_TTL_MINUTES` (default 15) and
+    /// `JWT_REFRESH_TTL_DAYS` (default 30).
+    pub fn from_env() -> Self {
+        let secret = env::var("JWT_SECRET").ok().filter(|s| !s.is_empty());
+        if let Some(secret) = &secret {
+            assert!(secret.len() >= 32, "JWT_SECRET must be at least 32 bytes long");
+        }
+
+        let access_minutes = env::var("JWT_ACCESS_TTL_MINUTES")
+            .ok()
+            .map(|v| v.parse::<i64>().expect("JWT_ACCESS_TTL_MINUTES must be a number"))
+            .unwrap_or(15);
+        let refresh_days = env::var("JWT_REFRESH_TTL_DAYS")
+            .ok()
+            .map(|v| v.parse::<i64>().expect("JWT_REFRESH_TTL_DAYS must be a number"))
+            .unwrap_or(30);
+
+        TokenConfig {
+            secret,
+            access_ttl: Duration::minutes(access_minutes),
+            refresh_ttl: Duration::days(refresh_days),
+        }
+    }
+
+    fn secret(&self) -> AppResult<&[u8]> {
+        self.secret
+            .as_deref()
+            .map(str::as_bytes)
+            .ok_or_else(|| AppError::ValidationError("Token authentication is not enabled".to_string()))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccessClaims {
+    sub: Uuid,
+    #[serde(default)]
+    role: AccountRole,
+    farm_id: Option<Uuid>,
+    name: String,
+    iss: String,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+    pub refresh_token: String,
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn generate_refresh_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect()
+}
+
+fn encode_access_token(config: &TokenConfig, account: &SessionAccount) -> AppResult<String> {
+    let now = Utc::now();
+    let farm_id = match account {
+        SessionAccount::Farmer(farmer) => farmer.farm_id,
+        SessionAccount::Buyer(_) | SessionAccount::Staff(_) => None,
+    };
+    let claims = AccessClaims {
+        sub: account.id(),
+        role: account.role(),
+        farm_id,
+        name: account.name().to_string(),
+        iss: ISSUER.to_string(),
+        iat: now.timestamp(),
+        exp: (now + config.access_ttl).timestamp(),
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(config.secret()?))
+        .map_err(|e| AppError::InternalError(format!("Failed to sign access token: {}", e)))
+}
+
+/// Signs a new access token for `account` without touching refresh tokens,
+/// e.g. after a farmer switches their active farm.
+pub fn issue_access_token(config: &TokenConfig, account: &SessionAccount) -> AppResult<String> {
+    encode_access_token(config, account)
+}
+
+/// Validates a bearer access token and returns the account it was issued to.
+pub fn verify_access_token(config: &TokenConfig, token: &str) -> AppResult<SessionAccount> {
+    let mut validation = Validation::default();
+    validation.set_issuer(&[ISSUER]);
+
+    let data = decode::<AccessClaims>(token, &DecodingKey::from_secret(config.secret()?), &validation)
+        .map_err(|_| AppError::Unauthorized("Invalid or expired access token".to_string()))?;
+
+    let claims = data.claims;
+    Ok(match claims.role {
+        AccountRole::Farmer => SessionAccount::Farmer(FarmerSession {
+            farmer_id: claims.sub,
+            farm_id: claims.farm_id,
+            name: claims.name,
+        }),
+        AccountRole::Buyer => SessionAccount::Buyer(BuyerSession {
+            buyer_id: claims.sub,
+            name: claims.name,
+        }),
+        role => SessionAccount::Staff(StaffSession {
+            staff_id: claims.sub,
+            name: claims.name,
+            role,
+        }),
+    })
+}
+
+async fn store_refresh_token(
+    conn: &mut PgConnection,
+    config: &TokenConfig,
+    account: &SessionAccount,
+    family_id: Uuid,
+) -> AppResult<(Uuid, String)> {
+    let id = Uuid::new_v4();
+    let token = generate_refresh_token();
+    let (farmer_id, buyer_id, staff_id) = match account {
+        SessionAccount::Farmer(farmer) => (Some(farmer.farmer_id), None, None),
+        SessionAccount::Buyer(buyer) => (None, Some(buyer.buyer_id), None),
+        SessionAccount::Staff(staff) => (None, None, Some(staff.staff_id)),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO refresh_tokens (id, farmer_id, buyer_id, staff_id, family_id, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(id)
+    .bind(farmer_id)
+    .bind(buyer_id)
+    .bind(staff_id)
+    .bind(family_id)
+    .bind(hash_token(&token))
+    .bind(Utc::now() + config.refresh_ttl)
+    .execute(conn)
+    .await?;
+
+    Ok((id, token))
+}
+
+fn token_pair(config: &TokenConfig, access_token: String, refresh_token: String) -> TokenPair {
+    TokenPair {
+        access_token,
+        token_type: "Bearer",
+        expires_in: config.access_ttl.num_seconds(),
+        refresh_token,
+    }
+}
+
+/// Issues a fresh access token and starts a new refresh token family.
+pub async fn issue_tokens(db: &Database, config: &TokenConfig, account: &SessionAccount) -> AppResult<TokenPair> {
+    let access_token = encode_access_token(config, account)?;
+    let mut conn = db.pool.acquire().await?;
+    let (_, refresh_token) = store_refresh_token(&mut conn, config, account, Uuid::new_v4()).await?;
+
+    Ok(token_pair(config, access_token, refresh_token))
+}
+
+async fn revoke_family(conn: &mut PgConnection, family_id: Uuid) -> AppResult<()> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL")
+        .bind(family_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Exchanges a refresh token for a new pair. The presented token is retired;
+/// presenting an already-retired token revokes its whole family, since that
+/// means the token was copied. The new token is stored and the old one
+/// retired in one transaction, with the old row locked, so a concurrent
+/// refresh with the same token is treated as reuse.
+pub async fn refresh_tokens(db: &Database, config: &TokenConfig, refresh_token: &str) -> AppResult<TokenPair> {
+    config.secret()?;
+
+    let mut tx = db.pool.begin().await?;
+    let row = sqlx::query(
+        "SELECT id, farmer_id, buyer_id, staff_id, family_id, expires_at, revoked_at \
+         FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE",
+    )
+    .bind(hash_token(refresh_token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized("Invalid refresh token".to_string()))?;
+
+    let id: Uuid = row.get("id");
+    let farmer_id: Option<Uuid> = row.get("farmer_id");
+    let buyer_id: Option<Uuid> = row.get("buyer_id");
+    let staff_id: Option<Uuid> = row.get("staff_id");
+    let family_id: Uuid = row.get("family_id");
+    let expires_at: chrono::DateTime<Utc> = row.get("expires_at");
+    let revoked_at: Option<chrono::DateTime<Utc>> = row.get("revoked_at");
+
+    if revoked_at.is_some() {
+        log::warn!(
+            "Refresh token reuse detected for account {}; revoking family {}",
+            farmer_id.or(buyer_id).or(staff_id).unwrap_or_default(),
+            family_id
+        );
+        revoke_family(&mut tx, family_id).await?;
+        tx.commit().await?;
+        return Err(AppError::Unauthorized("Refresh token has been revoked".to_string()));
+    }
+
+    if expires_at < Utc::now() {
+        return Err(AppError::Unauthorized("Refresh token has expired".to_string()));
+    }
+
+    let account = match (farmer_id, buyer_id, staff_id) {
+        (Some(farmer_id), _, _) => SessionAccount::Farmer(farmer_service::farmer_session(db, farmer_id).await?),
+        (_, Some(buyer_id), _) => SessionAccount::Buyer(buyer_service::buyer_session(db, buyer_id).await?),
+        (_, _, Some(staff_id)) => SessionAccount::Staff(staff_service::staff_session(db, staff_id).await?),
+        _ => return Err(AppError::Unauthorized("Invalid refresh token".to_string())),
+    };
+    let access_token = encode_access_token(config, &account)?;
+    let (new_id, new_token) = store_refresh_token(&mut tx, config, &account, family_id).await?;
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW(), replaced_by = $2 WHERE id = $1")
+        .bind(id)
+        .bind(new_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(token_pair(config, access_token, new_token))
+}
+
+/// Revokes the refresh token and everything rotated from the same login.
+pub async fn revoke_refresh_token(db: &Database, refresh_token: &str) -> AppResult<()> {
+    let row = sqlx::query("SELECT family_id FROM refresh_tokens WHERE token_hash = $1")
+        .bind(hash_token(refresh_token))
+        .fetch_optional(&db.pool)
+        .await?;
+
+    if let Some(row) = row {
+        let mut conn = db.pool.acquire().await?;
+        revoke_family(&mut conn, row.get("family_id")).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(secret: &str) -> TokenConfig {
+        TokenConfig {
+            secret: Some(secret.to_string()),
+            access_ttl: Duration::minutes(15),
+            refresh_ttl: Duration::days(30),
+        }
+    }
+
+    fn round_trip(account: &SessionAccount) -> SessionAccount {
+        let config = config("a-test-secret-that-is-32-bytes-long");
+        let token = issue_access_token(&config, account).unwrap();
+        verify_access_token(&config, &token).unwrap()
+    }
+
+    #[test]
+    fn buyer_tokens_verify_as_buyers() {
+        let buyer_id = Uuid::new_v4();
+        let account = round_trip(&SessionAccount::Buyer(BuyerSession { buyer_id, name: "Ada".to_string() }));
+        assert!(matches!(&account, SessionAccount::Buyer(buyer) if buyer.buyer_id == buyer_id && buyer.name == "Ada"));
+    }
+
+    #[test]
+    fn farmer_tokens_keep_the_active_farm() {
+        let (farmer_id, farm_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let account = round_trip(&SessionAccount::Farmer(FarmerSession {
+            farmer_id,
+            farm_id: Some(farm_id),
+            name: "Bola".to_string(),
+        }));
+        assert!(matches!(
+            &account,
+            SessionAccount::Farmer(farmer) if farmer.farmer_id == farmer_id && farmer.farm_id == Some(farm_id)
+        ));
+    }
+
+    #[test]
+    fn tokens_without_a_role_are_farmers() {
+        #[derive(Serialize)]
+        struct LegacyClaims {
+            sub: Uuid,
+            farm_id: Option<Uuid>,
+            name: String,
+            iss: String,
+            iat: i64,
+            exp: i64,
+        }
+
+        let config = config("a-test-secret-that-is-32-bytes-long");
+        let now = Utc::now();
+        let farmer_id = Uuid::new_v4();
+        let claims = LegacyClaims {
+            sub: farmer_id,
+            farm_id: None,
+            name: "Chidi".to_string(),
+            iss: ISSUER.to_string(),
+            iat: now.timestamp(),
+            exp: (now + Duration::minutes(5)).timestamp(),
+        };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(config.secret().unwrap())).unwrap();
+
+        let account = verify_access_token(&config, &token).unwrap();
+        assert!(matches!(&account, SessionAccount::Farmer(farmer) if farmer.farmer_id == farmer_id));
+    }
+
+    #[test]
+    fn tokens_signed_with_another_secret_are_rejected() {
+        let account = SessionAccount::Buyer(BuyerSession { buyer_id: Uuid::new_v4(), name: "Ada".to_string() });
+        let token = issue_access_token(&config("a-test-secret-that-is-32-bytes-long"), &account).unwrap();
+        let result = verify_access_token(&config("another-secret-that-is-32-bytes-long"), &token);
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{
+    database::Database,
+    errors::{AppError, AppResult},
+    models::{Unit, UNIT_COLUMNS},
+};
+
+/// The unit registry, loaded for the duration of a request.
+#[derive(Debug)]
+pub struct UnitRegistry {
+    units: Vec<Unit>,
+}
+
+impl UnitRegistry {
+    /// Finds a unit by code or alias, ignoring case and surrounding space.
+    pub fn resolve(&self, name: &str) -> AppResult<&Unit> {
+        let key = name.trim().to_lowercase();
+        self.units
+            .iter()
+            .find(|unit| unit.code == key || unit.aliases.contains(&key))
+            .ok_or_else(|| AppError::ValidationError(format!("Unknown unit '{}'", name.trim())))
+    }
+
+    /// The canonical code for a unit name.
+    pub fn canonical(&self, name: &str) -> AppResult<String> {
+        self.resolve(name).map(|unit| unit.code.clone())
+    }
+
+    /// Converts `quantity` between two units, or `None` if they measure
+    /// different things or either is unknown.
+    pub fn convert(&self, quantity: f64, from: &str, to: &str) -> Option<f64> {
+        let from = self.resolve(from).ok()?;
+        let to = self.resolve(to).ok()?;
+        from.factor_to(to).map(|factor| quantity * factor)
+    }
+}
+
+pub async fn list_units(db: &Database) -> AppResult<Vec<Unit>> {
+    Ok(sqlx::query_as::<_, Unit>(&format!(
+        "SELECT {} FROM units ORDER BY dimension, to_base NULLS LAST, code",
+        UNIT_COLUMNS
+    ))
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+pub async fn registry(db: &Database) -> AppResult<UnitRegistry> {
+    Ok(UnitRegistry {
+        units: list_units(db).await?,
+    })
+}
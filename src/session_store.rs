@@ -0,0 +1,289 @@
+use actix_session::config::{PersistentSession, SessionLifecycle};
+use actix_session::storage::{
+    CookieSessionStore, LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+};
+use actix_web::cookie::{time::Duration, Key};
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::{types::Json, PgPool, Row};
+use std::collections::HashMap;
+use std::env;
+use uuid::Uuid;
+
+use crate::middleware::auth::{BUYER_SESSION_KEY, FARMER_SESSION_KEY, SESSION_ID_KEY, STAFF_SESSION_KEY, USER_AGENT_KEY};
+
+type SessionState = HashMap<String, String>;
+
+/// Where session state lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStoreKind {
+    /// Whole state is signed into the cookie. Sessions cannot be listed or revoked.
+    Cookie,
+    /// Cookie only carries an opaque key; state lives in the `sessions` table.
+    Postgres,
+}
+
+/// Session settings read from the environment at startup.
+#[derive(Clone)]
+pub struct SessionConfig {
+    pub key: Key,
+    pub store: SessionStoreKind,
+    pub ttl: Duration,
+}
+
+impl SessionConfig {
+    /// `SESSION_KEY` must be at least 64 bytes so cookies stay valid across
+    /// restarts and between instances. `SESSION_STORE` is "cookie" (default)
+    /// or "postgres"; `SESSION_TTL_DAYS` defaults to 30.
+    pub fn from_env() -> Self {
+        let key = match env::var("SESSION_KEY") {
+            Ok(secret) => Key::try_from(secret.as_bytes())
+                .expect("SESSION_KEY must be at least 64 bytes long"),
+            Err(_) => {
+                log::warn!("SESSION_KEY not set; generating a random key. Sessions will not survive a restart.");
+                Key::generate()
+            }
+        };
+
+        let store = match env::var("SESSION_STORE").unwrap_or_default().to_lowercase().as_str() {
+            "" | "cookie" => SessionStoreKind::Cookie,
+            "postgres" => SessionStoreKind::Postgres,
+            other => panic!("Unknown SESSION_STORE: {}", other),
+        };
+
+        let ttl_days = env::var("SESSION_TTL_DAYS")
+            .ok()
+            .map(|days| days.parse::<i64>().expect("SESSION_TTL_DAYS must be a number"))
+            .unwrap_or(30);
+
+        SessionConfig { key, store, ttl: Duration::days(ttl_days) }
+    }
+
+    pub fn lifecycle(&self) -> SessionLifecycle {
+        PersistentSession::default().session_ttl(self.ttl).into()
+    }
+
+    pub fn build_store(&self, pool: &PgPool) -> AppSessionStore {
+        match self.store {
+            SessionStoreKind::Cookie => AppSessionStore::Cookie(CookieSessionStore::default()),
+            SessionStoreKind::Postgres => AppSessionStore::Postgres(PgSessionStore::new(pool.clone())),
+        }
+    }
+}
+
+/// Session store selected by configuration. `SessionMiddleware` is generic over
+/// its store, so this enum lets `main` pick one at runtime.
+pub enum AppSessionStore {
+    Cookie(CookieSessionStore),
+    Postgres(PgSessionStore),
+}
+
+impl SessionStore for AppSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        match self {
+            AppSessionStore::Cookie(store) => store.load(session_key).await,
+            AppSessionStore::Postgres(store) => store.load(session_key).await,
+        }
+    }
+
+    async fn save(&self, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, SaveError> {
+        match self {
+            AppSessionStore::Cookie(store) => store.save(session_state, ttl).await,
+            AppSessionStore::Postgres(store) => store.save(session_state, ttl).await,
+        }
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        match self {
+            AppSessionStore::Cookie(store) => store.update(session_key, session_state, ttl).await,
+            AppSessionStore::Postgres(store) => store.update(session_key, session_state, ttl).await,
+        }
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        match self {
+            AppSessionStore::Cookie(store) => store.update_ttl(session_key, ttl).await,
+            AppSessionStore::Postgres(store) => store.update_ttl(session_key, ttl).await,
+        }
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        match self {
+            AppSessionStore::Cookie(store) => store.delete(session_key).await,
+            AppSessionStore::Postgres(store) => store.delete(session_key).await,
+        }
+    }
+}
+
+/// Postgres-backed store. Besides the raw state it keeps the farmer, buyer or
+/// staff id, a public session id and the user agent in columns so sessions can be
+/// listed and revoked per account.
+pub struct PgSessionStore {
+    pool: PgPool,
+}
+
+/// Columns derived from the session state on every write.
+struct SessionColumns {
+    session_id: Option<Uuid>,
+    farmer_id: Option<Uuid>,
+    buyer_id: Option<Uuid>,
+    staff_id: Option<Uuid>,
+    user_agent: Option<String>,
+}
+
+impl SessionColumns {
+    fn from_state(state: &SessionState) -> Self {
+        let session_id = state
+            .get(SESSION_ID_KEY)
+            .and_then(|raw| serde_json::from_str::<Uuid>(raw).ok());
+        let account_id = |key: &str, field: &str| {
+            state
+                .get(key)
+                .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+                .and_then(|account| account.get(field).and_then(|id| id.as_str()).map(str::to_owned))
+                .and_then(|id| Uuid::parse_str(&id).ok())
+        };
+        let farmer_id = account_id(FARMER_SESSION_KEY, "farmer_id");
+        let buyer_id = account_id(BUYER_SESSION_KEY, "buyer_id");
+        let staff_id = account_id(STAFF_SESSION_KEY, "staff_id");
+        let user_agent = state
+            .get(USER_AGENT_KEY)
+            .and_then(|raw| serde_json::from_str::<String>(raw).ok());
+
+        SessionColumns { session_id, farmer_id, buyer_id, staff_id, user_agent }
+    }
+}
+
+impl PgSessionStore {
+    pub fn new(pool: PgPool) -> Self {
+        PgSessionStore { pool }
+    }
+
+    fn generate_key() -> Result<SessionKey, anyhow::Error> {
+        let value: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(64)
+            .map(char::from)
+            .collect();
+
+        SessionKey::try_from(value).map_err(anyhow::Error::from)
+    }
+
+    fn expires_at(ttl: &Duration) -> chrono::DateTime<Utc> {
+        Utc::now() + chrono::Duration::seconds(ttl.whole_seconds())
+    }
+
+    async fn insert(&self, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, anyhow::Error> {
+        let session_key = Self::generate_key()?;
+        let columns = SessionColumns::from_state(&session_state);
+
+        sqlx::query("DELETE FROM sessions WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (session_key, session_id, farmer_id, buyer_id, staff_id, user_agent, state, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(session_key.as_ref())
+        .bind(columns.session_id)
+        .bind(columns.farmer_id)
+        .bind(columns.buyer_id)
+        .bind(columns.staff_id)
+        .bind(columns.user_agent)
+        .bind(Json(&session_state))
+        .bind(Self::expires_at(ttl))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(session_key)
+    }
+}
+
+impl SessionStore for PgSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        let row = sqlx::query("SELECT state FROM sessions WHERE session_key = $1 AND expires_at > NOW()")
+            .bind(session_key.as_ref())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| LoadError::Other(e.into()))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let state: Json<SessionState> = row
+            .try_get("state")
+            .map_err(|e| LoadError::Deserialization(e.into()))?;
+
+        Ok(Some(state.0))
+    }
+
+    async fn save(&self, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, SaveError> {
+        self.insert(session_state, ttl).await.map_err(SaveError::Other)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let columns = SessionColumns::from_state(&session_state);
+
+        let result = sqlx::query(
+            r#"
+            UPDATE sessions
+            SET session_id = $2, farmer_id = $3, buyer_id = $4, staff_id = $5, user_agent = $6, state = $7,
+                expires_at = $8, last_seen_at = NOW()
+            WHERE session_key = $1 AND expires_at > NOW()
+            "#,
+        )
+        .bind(session_key.as_ref())
+        .bind(columns.session_id)
+        .bind(columns.farmer_id)
+        .bind(columns.buyer_id)
+        .bind(columns.staff_id)
+        .bind(columns.user_agent)
+        .bind(Json(&session_state))
+        .bind(Self::expires_at(ttl))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UpdateError::Other(e.into()))?;
+
+        // The row expired or was revoked while the client still held the
+        // cookie; start a fresh, empty session rather than resurrecting it,
+        // so the client has to log in again.
+        if result.rows_affected() == 0 {
+            return self.insert(SessionState::default(), ttl).await.map_err(UpdateError::Other);
+        }
+
+        Ok(session_key)
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        sqlx::query("UPDATE sessions SET expires_at = $2, last_seen_at = NOW() WHERE session_key = $1")
+            .bind(session_key.as_ref())
+            .bind(Self::expires_at(ttl))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        sqlx::query("DELETE FROM sessions WHERE session_key = $1")
+            .bind(session_key.as_ref())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use serde::Serialize;
+
+use crate::errors::{AppError, AppResult};
+
+/// An ISO-4217 currency. Amounts are stored as integers in its minor unit,
+/// which is `10^-exponent` of the major unit (kobo for NGN, yen for JPY).
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Currency {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub exponent: u32,
+}
+
+const fn currency(code: &'static str, name: &'static str, exponent: u32) -> Currency {
+    Currency { code, name, exponent }
+}
+
+/// Active ISO-4217 currencies, excluding funds, precious metals and testing
+/// codes.
+pub const CURRENCIES: &[Currency] = &[
+    currency("AED", "UAE Dirham", 2),
+    currency("AFN", "Afghani", 2),
+    currency("ALL", "Lek", 2),
+    currency("AMD", "Armenian Dram", 2),
+    currency("ANG", "Netherlands Antillean Guilder", 2),
+    currency("AOA", "Kwanza", 2),
+    currency("ARS", "Argentine Peso", 2),
+    currency("AUD", "Australian Dollar", 2),
+    currency("AWG", "Aruban Florin", 2),
+    currency("AZN", "Azerbaijan Manat", 2),
+    currency("BAM", "Convertible Mark", 2),
+    currency("BBD", "Barbados Dollar", 2),
+    currency("BDT", "Taka", 2),
+    currency("BGN", "Bulgarian Lev", 2),
+    currency("BHD", "Bahraini Dinar", 3),
+    currency("BIF", "Burundi Franc", 0),
+    currency("BMD", "Bermudian Dollar", 2),
+    currency("BND", "Brunei Dollar", 2),
+    currency("BOB", "Boliviano", 2),
+    currency("BRL", "Brazilian Real", 2),
+    currency("BSD", "Bahamian Dollar", 2),
+    currency("BTN", "Ngultrum", 2),
+    currency("BWP", "Pula", 2),
+    currency("BYN", "Belarusian Ruble", 2),
+    currency("BZD", "Belize Dollar", 2),
+    currency("CAD", "Canadian Dollar", 2),
+    currency("CDF", "Congolese Franc", 2),
+    currency("CHF", "Swiss Franc", 2),
+    currency("CLP", "Chilean Peso", 0),
+    currency("CNY", "Yuan Renminbi", 2),
+    currency("COP", "Colombian Peso", 2),
+    currency("CRC", "Costa Rican Colon", 2),
+    currency("CUP", "Cuban Peso", 2),
+    currency("CVE", "Cabo Verde Escudo", 2),
+    currency("CZK", "Czech Koruna", 2),
+    currency("DJF", "Djibouti Franc", 0),
+    currency("DKK", "Danish Krone", 2),
+    currency("DOP", "Dominican Peso", 2),
+    currency("DZD", "Algerian Dinar", 2),
+    currency("EGP", "Egyptian Pound", 2),
+    currency("ERN", "Nakfa", 2),
+    currency("ETB", "Ethiopian Birr", 2),
+    currency("EUR", "Euro", 2),
+    currency("FJD", "Fiji Dollar", 2),
+    currency("FKP", "Falkland Islands Pound", 2),
+    currency("GBP", "Pound Sterling", 2),
+    currency("GEL", "Lari", 2),
+    currency("GHS", "Ghana Cedi", 2),
+    currency("GIP", "Gibraltar Pound", 2),
+    currency("GMD", "Dalasi", 2),
+    currency("GNF", "Guinean Franc", 0),
+    currency("GTQ", "Quetzal", 2),
+    currency("GYD", "Guyana Dollar", 2),
+    currency("HKD", "Hong Kong Dollar", 2),
+    currency("HNL", "Lempira", 2),
+    currency("HTG", "Gourde", 2),
+    currency("HUF", "Forint", 2),
+    currency("IDR", "Rupiah", 2),
+    currency("ILS", "New Israeli Sheqel", 2),
+    currency("INR", "Indian Rupee", 2),
+    currency("IQD", "Iraqi Dinar", 3),
+    currency("IRR", "Iranian Rial", 2),
+    currency("ISK", "Iceland Krona", 0),
+    currency("JMD", "Jamaican Dollar", 2),
+    currency("JOD", "Jordanian Dinar", 3),
+    currency("JPY", "Yen", 0),
+    currency("KES", "Kenyan Shilling", 2),
+    currency("KGS", "Som", 2),
+    currency("KHR", "Riel", 2),
+    currency("KMF", "Comorian Franc", 0),
+    currency("KPW", "North Korean Won", 2),
+    currency("KRW", "Won", 0),
+    currency("KWD", "Kuwaiti Dinar", 3),
+    currency("KYD", "Cayman Islands Dollar", 2),
+    currency("KZT", "Tenge", 2),
+    currency("LAK", "Lao Kip", 2),
+    currency("LBP", "Lebanese Pound", 2),
+    currency("LKR", "Sri Lanka Rupee", 2),
+    currency("LRD", "Liberian Dollar", 2),
+    currency("LSL", "Loti", 2),
+    currency("LYD", "Libyan Dinar", 3),
+    currency("MAD", "Moroccan Dirham", 2),
+    currency("MDL", "Moldovan Leu", 2),
+    currency("MGA", "Malagasy Ariary", 2),
+    currency("MKD", "Denar", 2),
+    currency("MMK", "Kyat", 2),
+    currency("MNT", "Tugrik", 2),
+    currency("MOP", "Pataca", 2),
+    currency("MRU", "Ouguiya", 2),
+    currency("MUR", "Mauritius Rupee", 2),
+    currency("MVR", "Rufiyaa", 2),
+    currency("MWK", "Malawi Kwacha", 2),
+    currency("MXN", "Mexican Peso", 2),
+    currency("MYR", "Malaysian Ringgit", 2),
+    currency("MZN", "Mozambique Metical", 2),
+    currency("NAD", "Namibia Dollar", 2),
+    currency("NGN", "Naira", 2),
+    currency("NIO", "Cordoba Oro", 2),
+    currency("NOK", "Norwegian Krone", 2),
+    currency("NPR", "Nepalese Rupee", 2),
+    currency("NZD", "New Zealand Dollar", 2),
+    currency("OMR", "Rial Omani", 3),
+    currency("PAB", "Balboa", 2),
+    currency("PEN", "Sol", 2),
+    currency("PGK", "Kina", 2),
+    currency("PHP", "Philippine Peso", 2),
+    currency("PKR", "Pakistan Rupee", 2),
+    currency("PLN", "Zloty", 2),
+    currency("PYG", "Guarani", 0),
+    currency("QAR", "Qatari Rial", 2),
+    currency("RON", "Romanian Leu", 2),
+    currency("RSD", "Serbian Dinar", 2),
+    currency("RUB", "Russian Ruble", 2),
+    currency("RWF", "Rwanda Franc", 0),
+    currency("SAR", "Saudi Riyal", 2),
+    currency("SBD", "Solomon Islands Dollar", 2),
+    currency("SCR", "Seychelles Rupee", 2),
+    currency("SDG", "Sudanese Pound", 2),
+    currency("SEK", "Swedish Krona", 2),
+    currency("SGD", "Singapore Dollar", 2),
+    currency("SHP", "Saint Helena Pound", 2),
+    currency("SLE", "Leone", 2),
+    currency("SOS", "Somali Shilling", 2),
+    currency("SRD", "Surinam Dollar", 2),
+    currency("SSP", "South Sudanese Pound", 2),
+    currency("STN", "Dobra", 2),
+    currency("SVC", "El Salvador Colon", 2),
+    currency("SYP", "Syrian Pound", 2),
+    currency("SZL", "Lilangeni", 2),
+    currency("THB", "Baht", 2),
+    currency("TJS", "Somoni", 2),
+    currency("TMT", "Turkmenistan New Manat", 2),
+    currency("TND", "Tunisian Dinar", 3),
+    currency("TOP", "Pa'anga", 2),
+    currency("TRY", "Turkish Lira", 2),
+    currency("TTD", "Trinidad and Tobago Dollar", 2),
+    currency("TWD", "New Taiwan Dollar", 2),
+    currency("TZS", "Tanzanian Shilling", 2),
+    currency("UAH", "Hryvnia", 2),
+    currency("UGX", "Uganda Shilling", 0),
+    currency("USD", "US Dollar", 2),
+    currency("UYU", "Peso Uruguayo", 2),
+    currency("UZS", "Uzbekistan Sum", 2),
+    currency("VES", "Bolivar Soberano", 2),
+    currency("VND", "Dong", 0),
+    currency("VUV", "Vatu", 0),
+    currency("WST", "Tala", 2),
+    currency("XAF", "CFA Franc BEAC", 0),
+    currency("XCD", "East Caribbean Dollar", 2),
+    currency("XOF", "CFA Franc BCEAO", 0),
+    currency("XPF", "CFP Franc", 0),
+    currency("YER", "Yemeni Rial", 2),
+    currency("ZAR", "Rand", 2),
+    currency("ZMW", "Zambian Kwacha", 2),
+    currency("ZWG", "Zimbabwe Gold", 2),
+];
+
+/// Looks up a currency by code, ignoring case and surrounding space.
+pub fn find(code: &str) -> Option<&'static Currency> {
+    let code = code.trim();
+    CURRENCIES.iter().find(|currency| currency.code.eq_ignore_ascii_case(code))
+}
+
+/// Like `find`, but rejecting unknown codes.
+pub fn resolve(code: &str) -> AppResult<&'static Currency> {
+    find(code).ok_or_else(|| AppError::ValidationError(format!("Unknown currency '{}'", code.trim())))
+}
+
+/// Digits after the decimal point in `code`'s major unit. Unknown codes are
+/// treated as having two.
+pub fn exponent(code: &str) -> u32 {
+    find(code).map_or(2, |currency| currency.exponent)
+}
+
+/// Renders a minor-unit amount for people, e.g. "NGN 1,250.50" or "JPY 300".
+pub fn format_amount(amount: i64, code: &str) -> String {
+    let exponent = exponent(code);
+    let scale = 10u64.pow(exponent);
+    let units = amount.unsigned_abs();
+
+    let whole = (units / scale).to_string();
+    let mut grouped = String::with_capacity(whole.len() + whole.len() / 3);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if amount < 0 { "-" } else { "" };
+    if exponent == 0 {
+        format!("{} {}{}", code, sign, grouped)
+    } else {
+        format!("{} {}{}.{:0width$}", code, sign, grouped, units % scale, width = exponent as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_two_decimal_currencies() {
+        assert_eq!(format_amount(125_050, "NGN"), "NGN 1,250.50");
+        assert_eq!(format_amount(5, "NGN"), "NGN 0.05");
+        assert_eq!(format_amount(0, "GHS"), "GHS 0.00");
+    }
+
+    #[test]
+    fn formats_zero_and_three_decimal_currencies() {
+        assert_eq!(format_amount(300, "JPY"), "JPY 300");
+        assert_eq!(format_amount(1_234_567, "JPY"), "JPY 1,234,567");
+        assert_eq!(format_amount(1_500, "KWD"), "KWD 1.500");
+        assert_eq!(format_amount(7, "BHD"), "BHD 0.007");
+    }
+
+    #[test]
+    fn formats_negative_amounts() {
+        assert_eq!(format_amount(-125_050, "NGN"), "NGN -1,250.50");
+        assert_eq!(format_amount(-5, "NGN"), "NGN -0.05");
+        assert_eq!(format_amount(-1_000, "JPY"), "JPY -1,000");
+        assert_eq!(format_amount(i64::MIN, "JPY"), "JPY -9,223,372,036,854,775,808");
+    }
+
+    #[test]
+    fn groups_thousands() {
+        assert_eq!(format_amount(99_900, "USD"), "USD 999.00");
+        assert_eq!(format_amount(100_000, "USD"), "USD 1,000.00");
+        assert_eq!(format_amount(12_345_678_900, "USD"), "USD 123,456,789.00");
+    }
+
+    #[test]
+    fn unknown_codes_use_two_decimals() {
+        assert_eq!(exponent("XYZ"), 2);
+        assert_eq!(format_amount(1_050, "XYZ"), "XYZ 10.50");
+    }
+}
@@ -0,0 +1,320 @@
+use std::env;
+use std::sync::OnceLock;
+
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::errors::{AppError, AppResult};
+
+/// A WGS84 coordinate supplied by a client.
+#[derive(Debug, Clone, Copy)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lng: f64) -> AppResult<Self> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+            return Err(AppError::ValidationError("Latitude must be within ±90 and longitude within ±180".to_string()));
+        }
+        Ok(GeoPoint { lat, lng })
+    }
+
+    /// Builds a point from optional `lat`/`lng` query parameters, which must
+    /// be given together.
+    pub fn from_query(lat: Option<f64>, lng: Option<f64>) -> AppResult<Option<Self>> {
+        match (lat, lng) {
+            (Some(lat), Some(lng)) => GeoPoint::new(lat, lng).map(Some),
+            (None, None) => Ok(None),
+            _ => Err(AppError::ValidationError("Latitude and longitude must be provided together".to_string())),
+        }
+    }
+}
+
+/// Decimal places of a farm's coordinates shown to the public; two is a
+/// grid of about a kilometre.
+const PUBLIC_COORDINATE_DECIMALS: i32 = 2;
+
+/// Smallest `radius_km` a client may ask for, a little over the diagonal of
+/// a grid cell, so narrowing a search can't place a farm within its cell.
+pub const MIN_SEARCH_RADIUS_KM: f64 = 2.0;
+
+/// Spacing of the public grid in degrees.
+pub fn public_grid_degrees() -> f64 {
+    10f64.powi(-PUBLIC_COORDINATE_DECIMALS)
+}
+
+/// Snaps a coordinate to the public grid.
+pub fn coarsen(degrees: f64) -> f64 {
+    let scale = 10f64.powi(PUBLIC_COORDINATE_DECIMALS);
+    (degrees * scale).round() / scale
+}
+
+/// Appends `point` as a bound geography literal.
+pub fn push_point(qb: &mut QueryBuilder<'_, Postgres>, point: GeoPoint) {
+    qb.push("ST_SetSRID(ST_MakePoint(").push_bind(point.lng);
+    qb.push(", ").push_bind(point.lat).push("), 4326)::geography");
+}
+
+/// Appends a farm's `location` column snapped to the public grid, as a
+/// geography. Distances and radius checks on what strangers search for use
+/// this, so they give away no more than a listing's coordinates.
+pub fn push_public_location(qb: &mut QueryBuilder<'_, Postgres>, column: &str) {
+    qb.push("ST_SnapToGrid(").push(column).push(", ").push_bind(public_grid_degrees()).push(")::geography");
+}
+
+fn invalid_boundary(reason: &str) -> AppError {
+    AppError::ValidationError(format!("Invalid boundary: {}", reason))
+}
+
+fn check_ring(ring: &Value) -> AppResult<()> {
+    let positions = ring.as_array().ok_or_else(|| invalid_boundary("rings must be arrays of positions"))?;
+    if positions.len() < 4 {
+        return Err(invalid_boundary("rings need at least four positions"));
+    }
+    if positions.first() != positions.last() {
+        return Err(invalid_boundary("rings must be closed"));
+    }
+    for position in positions {
+        let coords = position.as_array().filter(|c| c.len() >= 2);
+        let (lng, lat) = match coords.map(|c| (c[0].as_f64(), c[1].as_f64())) {
+            Some((Some(lng), Some(lat))) => (lng, lat),
+            _ => return Err(invalid_boundary("positions must be [longitude, latitude]")),
+        };
+        GeoPoint::new(lat, lng)?;
+    }
+    Ok(())
+}
+
+fn check_polygon(rings: &Value) -> AppResult<()> {
+    let rings = rings
+        .as_array()
+        .filter(|r| !r.is_empty())
+        .ok_or_else(|| invalid_boundary("polygons need at least one ring"))?;
+    rings.iter().try_for_each(check_ring)
+}
+
+/// Checks that `value` is a GeoJSON Polygon or MultiPolygon (bare or inside a
+/// Feature) with well-formed rings, and returns the geometry as a string for
+/// `ST_GeomFromGeoJSON`. Topology (self-intersection etc.) is left to PostGIS.
+pub fn boundary_geojson(value: &Value) -> AppResult<String> {
+    let geometry = match value.get("type").and_then(Value::as_str) {
+        Some("Feature") => value.get("geometry").ok_or_else(|| invalid_boundary("feature has no geometry"))?,
+        _ => value,
+    };
+
+    let coordinates = geometry.get("coordinates").ok_or_else(|| invalid_boundary("missing coordinates"))?;
+    match geometry.get("type").and_then(Value::as_str) {
+        Some("Polygon") => check_polygon(coordinates)?,
+        Some("MultiPolygon") => {
+            let polygons = coordinates
+                .as_array()
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| invalid_boundary("multipolygons need at least one polygon"))?;
+            polygons.iter().try_for_each(check_polygon)?;
+        }
+        _ => return Err(invalid_boundary("expected a Polygon or MultiPolygon")),
+    }
+
+    Ok(geometry.to_string())
+}
+
+/// Limits for location-based search and farm mapping.
+#[derive(Debug)]
+pub struct GeoConfig {
+    /// How far from the farm `local_only` products are offered.
+    pub local_radius_km: f64,
+    /// Largest `radius_km` a client may ask for.
+    pub max_radius_km: f64,
+    /// How far, in percent, a mapped boundary's area may differ from the
+    /// self-reported farm size before it is flagged.
+    pub size_tolerance_pct: f64,
+}
+
+impl GeoConfig {
+    /// Reads `LOCAL_RADIUS_KM` (default 50), `MAX_SEARCH_RADIUS_KM`
+    /// (default 500) and `FARM_SIZE_TOLERANCE_PCT` (default 20).
+    pub fn from_env() -> AppResult<Self> {
+        let read = |name: &str, default: f64| match env::var(name) {
+            Ok(value) => value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .ok_or_else(|| AppError::InternalError(format!("{} must be a non-negative number", name))),
+            Err(_) => Ok(default),
+        };
+
+        Ok(GeoConfig {
+            local_radius_km: read("LOCAL_RADIUS_KM", 50.0)?,
+            max_radius_km: read("MAX_SEARCH_RADIUS_KM", 500.0)?,
+            size_tolerance_pct: read("FARM_SIZE_TOLERANCE_PCT", 20.0)?,
+        })
+    }
+
+    /// Whether a mapped area and a self-reported size disagree by more than
+    /// the tolerance. Unknown on either side is not a mismatch.
+    pub fn size_mismatch(&self, mapped_hectares: Option<f64>, reported_hectares: Option<f64>) -> bool {
+        match (mapped_hectares, reported_hectares) {
+            (Some(mapped), Some(reported)) if reported > 0.0 => {
+                (mapped - reported).abs() / reported * 100.0 > self.size_tolerance_pct
+            }
+            _ => false,
+        }
+    }
+
+    /// Validates a requested search radius.
+    pub fn radius_km(&self, radius_km: Option<f64>, center: Option<GeoPoint>) -> AppResult<Option<f64>> {
+        let Some(radius_km) = radius_km else {
+            return Ok(None);
+        };
+        if center.is_none() {
+            return Err(AppError::ValidationError("radius_km requires lat and lng".to_string()));
+        }
+        if !(MIN_SEARCH_RADIUS_KM..=self.max_radius_km).contains(&radius_km) {
+            return Err(AppError::ValidationError(format!(
+                "radius_km must be between {} and {}",
+                MIN_SEARCH_RADIUS_KM, self.max_radius_km
+            )));
+        }
+        Ok(Some(radius_km))
+    }
+}
+
+static CONFIG: OnceLock<GeoConfig> = OnceLock::new();
+
+/// Loads the process-wide configuration. Call once at startup.
+pub fn init() -> AppResult<()> {
+    CONFIG
+        .set(GeoConfig::from_env()?)
+        .map_err(|_| AppError::InternalError("Geo settings are already configured".to_string()))
+}
+
+pub fn config() -> &'static GeoConfig {
+    CONFIG.get().expect("geo::init must be called at startup")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config() -> GeoConfig {
+        GeoConfig { local_radius_km: 50.0, max_radius_km: 500.0, size_tolerance_pct: 20.0 }
+    }
+
+    fn center() -> Option<GeoPoint> {
+        Some(GeoPoint { lat: 6.5, lng: 3.4 })
+    }
+
+    #[test]
+    fn coordinates_snap_to_the_public_grid() {
+        assert_eq!(coarsen(6.524_379), 6.52);
+        assert_eq!(coarsen(3.379_206), 3.38);
+        assert_eq!(coarsen(-1.286_389), -1.29);
+        assert_eq!(coarsen(36.817_223), 36.82);
+        assert_eq!(coarsen(7.0), 7.0);
+        assert!((public_grid_degrees() - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn radius_needs_a_center() {
+        assert!(matches!(config().radius_km(Some(10.0), None), Err(AppError::ValidationError(_))));
+        assert_eq!(config().radius_km(None, None).unwrap(), None);
+        assert_eq!(config().radius_km(None, center()).unwrap(), None);
+    }
+
+    #[test]
+    fn radius_is_bounded_by_the_grid_and_the_maximum() {
+        assert_eq!(config().radius_km(Some(MIN_SEARCH_RADIUS_KM), center()).unwrap(), Some(MIN_SEARCH_RADIUS_KM));
+        assert_eq!(config().radius_km(Some(500.0), center()).unwrap(), Some(500.0));
+        for radius_km in [0.0, -5.0, 0.5, MIN_SEARCH_RADIUS_KM - 0.01, 500.5, f64::NAN] {
+            assert!(
+                matches!(config().radius_km(Some(radius_km), center()), Err(AppError::ValidationError(_))),
+                "{}",
+                radius_km
+            );
+        }
+    }
+
+    #[test]
+    fn size_mismatch_beyond_the_tolerance() {
+        let config = config();
+        assert!(!config.size_mismatch(Some(12.0), Some(10.0)));
+        assert!(!config.size_mismatch(Some(8.0), Some(10.0)));
+        assert!(config.size_mismatch(Some(12.5), Some(10.0)));
+        assert!(config.size_mismatch(Some(7.9), Some(10.0)));
+    }
+
+    #[test]
+    fn unknown_sizes_are_not_a_mismatch() {
+        let config = config();
+        assert!(!config.size_mismatch(None, Some(10.0)));
+        assert!(!config.size_mismatch(Some(10.0), None));
+        assert!(!config.size_mismatch(Some(10.0), Some(0.0)));
+    }
+
+    fn square() -> Value {
+        json!([[[3.0, 6.0], [3.01, 6.0], [3.01, 6.01], [3.0, 6.01], [3.0, 6.0]]])
+    }
+
+    #[test]
+    fn accepts_polygons_and_multipolygons() {
+        let polygon = json!({ "type": "Polygon", "coordinates": square() });
+        let multipolygon = json!({ "type": "MultiPolygon", "coordinates": [square(), square()] });
+        let cases = [
+            polygon.clone(),
+            multipolygon.clone(),
+            json!({ "type": "Feature", "properties": {}, "geometry": polygon.clone() }),
+            json!({ "type": "Feature", "geometry": multipolygon.clone() }),
+        ];
+        for value in cases {
+            let geojson = boundary_geojson(&value).unwrap_or_else(|e| panic!("{}: {}", value, e));
+            let geometry = value.get("geometry").unwrap_or(&value);
+            assert_eq!(geojson, geometry.to_string());
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_boundaries() {
+        let cases = [
+            (json!({ "type": "Point", "coordinates": [3.0, 6.0] }), "expected a Polygon or MultiPolygon"),
+            (json!({ "coordinates": square() }), "expected a Polygon or MultiPolygon"),
+            (json!({ "type": "Polygon" }), "missing coordinates"),
+            (json!({ "type": "Feature" }), "feature has no geometry"),
+            (json!({ "type": "Polygon", "coordinates": [] }), "at least one ring"),
+            (json!({ "type": "MultiPolygon", "coordinates": [] }), "at least one polygon"),
+            (json!({ "type": "Polygon", "coordinates": [3.0, 6.0] }), "arrays of positions"),
+            (
+                json!({ "type": "Polygon", "coordinates": [[[3.0, 6.0], [3.01, 6.0], [3.0, 6.0]]] }),
+                "at least four positions",
+            ),
+            (
+                json!({ "type": "Polygon", "coordinates": [[[3.0, 6.0], [3.01, 6.0], [3.01, 6.01], [3.0, 6.01]]] }),
+                "closed",
+            ),
+            (
+                json!({ "type": "Polygon", "coordinates": [[[3.0, 6.0], [3.01], [3.01, 6.01], [3.0, 6.0]]] }),
+                "[longitude, latitude]",
+            ),
+            (
+                json!({ "type": "Polygon", "coordinates": [[[3.0, 6.0], ["3", 6.0], [3.01, 6.01], [3.0, 6.0]]] }),
+                "[longitude, latitude]",
+            ),
+            (
+                json!({ "type": "Polygon", "coordinates": [[[3.0, 6.0], [3.01, 96.0], [3.01, 6.01], [3.0, 6.0]]] }),
+                "Latitude must be within",
+            ),
+        ];
+        for (value, reason) in cases {
+            let result = boundary_geojson(&value);
+            assert!(
+                matches!(&result, Err(AppError::ValidationError(message)) if message.contains(reason)),
+                "{}: {:?}",
+                value,
+                result
+            );
+        }
+    }
+}
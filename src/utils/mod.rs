@@ -1,6 +1,177 @@
+pub mod currency;
+pub mod geo;
+pub mod phone;
+
+use actix_web::HttpRequest;
 use rand::Rng;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use crate::errors::{AppError, AppResult};
 
 pub fn generate_otp() -> String {
     let mut rng = rand::thread_rng();
     format!("{:06}", rng.gen_range(100000..999999))
 }
+
+static OTP_PEPPER: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Reads `OTP_PEPPER`, the key OTP hashes are made with. Like `JWT_SECRET` it
+/// must be at least 32 bytes. Call once at startup.
+pub fn init_otp_pepper() -> AppResult<()> {
+    let pepper = env::var("OTP_PEPPER").unwrap_or_default();
+    if pepper.len() < 32 {
+        return Err(AppError::InternalError("OTP_PEPPER must be set to at least 32 bytes".to_string()));
+    }
+    OTP_PEPPER
+        .set(pepper.into_bytes())
+        .map_err(|_| AppError::InternalError("OTP pepper is already configured".to_string()))
+}
+
+fn otp_pepper() -> &'static [u8] {
+    OTP_PEPPER.get().expect("init_otp_pepper must be called at startup")
+}
+
+fn otp_mac(pepper: &[u8], phone_number: &str, otp_code: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(pepper).expect("HMAC accepts any key length");
+    mac.update(phone_number.as_bytes());
+    mac.update(b":");
+    mac.update(otp_code.trim().as_bytes());
+    mac
+}
+
+/// OTPs are stored as a hex HMAC-SHA256 of the phone number and code keyed
+/// with `OTP_PEPPER`, so a database leak does not reveal live codes.
+pub fn hash_otp(phone_number: &str, otp_code: &str) -> String {
+    hex::encode(otp_mac(otp_pepper(), phone_number, otp_code).finalize().into_bytes())
+}
+
+/// Constant-time check of a code against a stored `hash_otp`.
+pub fn otp_matches(phone_number: &str, otp_code: &str, otp_hash: &str) -> bool {
+    let Ok(otp_hash) = hex::decode(otp_hash) else {
+        return false;
+    };
+    otp_mac(otp_pepper(), phone_number, otp_code).verify_slice(&otp_hash).is_ok()
+}
+
+static TRUSTED_PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+
+/// Reads `TRUSTED_PROXIES`, a comma-separated list of proxy addresses whose
+/// `X-Forwarded-For` is believed. Call once at startup.
+pub fn init_trusted_proxies() -> AppResult<()> {
+    let proxies = env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|proxy| !proxy.is_empty())
+        .map(|proxy| {
+            proxy
+                .parse::<IpAddr>()
+                .map_err(|_| AppError::InternalError(format!("Invalid address in TRUSTED_PROXIES: {}", proxy)))
+        })
+        .collect::<AppResult<Vec<IpAddr>>>()?;
+    TRUSTED_PROXIES
+        .set(proxies)
+        .map_err(|_| AppError::InternalError("Trusted proxies are already configured".to_string()))
+}
+
+/// The client's address, used to key per-IP rate limits. This is the socket
+/// peer unless the peer is a trusted proxy, in which case it is the nearest
+/// `X-Forwarded-For` hop that isn't one, so clients can't pick their own.
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    let forwarded = req.headers().get("x-forwarded-for").and_then(|value| value.to_str().ok());
+    let trusted = TRUSTED_PROXIES.get().map(Vec::as_slice).unwrap_or_default();
+    Some(forwarded_client(peer, forwarded, trusted).to_string())
+}
+
+fn forwarded_client(peer: IpAddr, forwarded: Option<&str>, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&peer) {
+        return peer;
+    }
+    let hops: Vec<IpAddr> = forwarded
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    hops.iter()
+        .rev()
+        .find(|hop| !trusted.contains(hop))
+        .or(hops.first())
+        .copied()
+        .unwrap_or(peer)
+}
+
+/// Rejects `value` unless it is one of `allowed`.
+pub fn one_of(field: &str, value: &str, allowed: &[&str]) -> AppResult<()> {
+    if !allowed.contains(&value) {
+        return Err(AppError::ValidationError(format!("{} must be one of: {}", field, allowed.join(", "))));
+    }
+    Ok(())
+}
+
+/// Trims required text, rejecting blank values.
+pub fn required_text(field: &str, value: &str) -> AppResult<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(AppError::ValidationError(format!("{} is required", field)));
+    }
+    Ok(value.to_string())
+}
+
+/// Trims optional text, treating blank as absent.
+pub fn clean_text(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Validates an ISO-4217 currency code and upper-cases it, defaulting to
+/// NGN.
+pub fn currency_code(code: Option<String>) -> AppResult<String> {
+    match code {
+        Some(code) => currency::resolve(&code).map(|currency| currency.code.to_string()),
+        None => Ok("NGN".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_untrusted_peers() {
+        let client = forwarded_client(ip("203.0.113.9"), Some("198.51.100.1"), &[ip("10.0.0.1")]);
+        assert_eq!(client, ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn trusted_proxy_yields_nearest_untrusted_hop() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let client = forwarded_client(ip("10.0.0.1"), Some("1.2.3.4, 203.0.113.9, 10.0.0.2"), &trusted);
+        assert_eq!(client, ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn trusted_proxy_without_header_yields_peer() {
+        assert_eq!(forwarded_client(ip("10.0.0.1"), None, &[ip("10.0.0.1")]), ip("10.0.0.1"));
+    }
+
+    fn otp_hash(pepper: &[u8], phone_number: &str, otp_code: &str) -> String {
+        hex::encode(otp_mac(pepper, phone_number, otp_code).finalize().into_bytes())
+    }
+
+    #[test]
+    fn otp_hash_is_keyed_by_the_pepper_and_phone() {
+        let pepper = [7u8; 32];
+        let hash = otp_hash(&pepper, "+2348031234567", "123456");
+        assert_eq!(hash, otp_hash(&pepper, "+2348031234567", " 123456 "));
+        assert_ne!(hash, otp_hash(&[8u8; 32], "+2348031234567", "123456"));
+        assert_ne!(hash, otp_hash(&pepper, "+2348031234568", "123456"));
+    }
+}
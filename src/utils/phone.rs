@@ -0,0 +1,195 @@
+use std::env;
+use std::sync::OnceLock;
+
+use crate::errors::{AppError, AppResult};
+
+/// Numbering rules for one supported country.
+#[derive(Debug)]
+pub struct Country {
+    /// ISO 3166-1 alpha-2 code, e.g. "NG".
+    pub iso: &'static str,
+    /// International dialling code without the '+'.
+    pub dial_code: &'static str,
+    /// Digits in the national significant number (after the trunk '0').
+    pub nsn_len: usize,
+    /// Allowed leading digits of the national significant number (mobile ranges).
+    pub leading_digits: &'static [char],
+}
+
+pub const SUPPORTED_COUNTRIES: &[Country] = &[
+    Country { iso: "NG", dial_code: "234", nsn_len: 10, leading_digits: &['7', '8', '9'] },
+    Country { iso: "GH", dial_code: "233", nsn_len: 9, leading_digits: &['2', '5'] },
+    Country { iso: "KE", dial_code: "254", nsn_len: 9, leading_digits: &['1', '7'] },
+    Country { iso: "UG", dial_code: "256", nsn_len: 9, leading_digits: &['7'] },
+    Country { iso: "TZ", dial_code: "255", nsn_len: 9, leading_digits: &['6', '7'] },
+];
+
+/// Which countries the deployment accepts, and which one a national-format
+/// number (leading '0') belongs to.
+#[derive(Debug)]
+pub struct PhoneConfig {
+    pub default_country: &'static Country,
+    pub countries: Vec<&'static Country>,
+}
+
+fn find_country(iso: &str) -> Option<&'static Country> {
+    SUPPORTED_COUNTRIES.iter().find(|c| c.iso.eq_ignore_ascii_case(iso.trim()))
+}
+
+impl PhoneConfig {
+    /// Reads `PHONE_COUNTRIES` (comma-separated ISO codes, default "NG,GH,KE")
+    /// and `PHONE_DEFAULT_COUNTRY` (default: the first listed country).
+    pub fn from_env() -> AppResult<Self> {
+        let countries = env::var("PHONE_COUNTRIES")
+            .unwrap_or_else(|_| "NG,GH,KE".to_string())
+            .split(',')
+            .filter(|iso| !iso.trim().is_empty())
+            .map(|iso| {
+                find_country(iso).ok_or_else(|| {
+                    AppError::InternalError(format!("Unsupported country in PHONE_COUNTRIES: {}", iso))
+                })
+            })
+            .collect::<AppResult<Vec<&'static Country>>>()?;
+        if countries.is_empty() {
+            return Err(AppError::InternalError("PHONE_COUNTRIES must list at least one country".to_string()));
+        }
+
+        let default_country = match env::var("PHONE_DEFAULT_COUNTRY") {
+            Ok(iso) => find_country(&iso)
+                .filter(|c| countries.iter().any(|allowed| allowed.iso == c.iso))
+                .ok_or_else(|| {
+                    AppError::InternalError(format!("PHONE_DEFAULT_COUNTRY must be one of PHONE_COUNTRIES: {}", iso))
+                })?,
+            Err(_) => countries[0],
+        };
+
+        Ok(PhoneConfig { default_country, countries })
+    }
+
+    /// Canonicalises `raw` to E.164 (`+<dial code><number>`). Accepts
+    /// international numbers with `+` or `00`, international numbers without
+    /// a prefix, and national numbers with a trunk '0' for the default country.
+    pub fn normalize(&self, raw: &str) -> AppResult<String> {
+        let trimmed = raw.trim();
+        let international = trimmed.starts_with('+') || trimmed.starts_with("00");
+
+        let digits: String = trimmed
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '(' | ')' | '.' | '+'))
+            .collect();
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid(raw));
+        }
+
+        let digits = if trimmed.starts_with("00") { &digits[2..] } else { digits.as_str() };
+
+        if international {
+            return self.match_international(digits).ok_or_else(|| invalid(raw));
+        }
+
+        if let Some(national) = digits.strip_prefix('0') {
+            return self.build(self.default_country, national).ok_or_else(|| invalid(raw));
+        }
+
+        self.match_international(digits)
+            .or_else(|| self.build(self.default_country, digits))
+            .ok_or_else(|| invalid(raw))
+    }
+
+    fn match_international(&self, digits: &str) -> Option<String> {
+        self.countries.iter().find_map(|country| {
+            let nsn = digits.strip_prefix(country.dial_code)?;
+            // Tolerate the common "+234 0803..." mistake.
+            let nsn = if nsn.len() == country.nsn_len + 1 { nsn.strip_prefix('0')? } else { nsn };
+            self.build(country, nsn)
+        })
+    }
+
+    fn build(&self, country: &Country, nsn: &str) -> Option<String> {
+        let first = nsn.chars().next()?;
+        if nsn.len() != country.nsn_len || !country.leading_digits.contains(&first) {
+            return None;
+        }
+        Some(format!("+{}{}", country.dial_code, nsn))
+    }
+}
+
+fn invalid(raw: &str) -> AppError {
+    AppError::ValidationError(format!("Invalid or unsupported phone number: {}", raw))
+}
+
+static CONFIG: OnceLock<PhoneConfig> = OnceLock::new();
+
+/// Loads the process-wide configuration. Call once at startup.
+pub fn init() -> AppResult<()> {
+    CONFIG
+        .set(PhoneConfig::from_env()?)
+        .map_err(|_| AppError::InternalError("Phone numbers are already configured".to_string()))
+}
+
+pub fn config() -> &'static PhoneConfig {
+    CONFIG.get().expect("phone::init must be called at startup")
+}
+
+/// Normalises with the process-wide configuration.
+pub fn normalize(raw: &str) -> AppResult<String> {
+    config().normalize(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(countries: &[&str]) -> PhoneConfig {
+        let countries: Vec<&'static Country> = countries.iter().map(|iso| find_country(iso).unwrap()).collect();
+        PhoneConfig { default_country: countries[0], countries }
+    }
+
+    #[test]
+    fn national_numbers_use_the_default_country() {
+        let config = config(&["NG", "GH", "KE"]);
+        assert_eq!(config.normalize("0803 123 4567").unwrap(), "+2348031234567");
+        assert_eq!(config.normalize("(0803) 123-4567").unwrap(), "+2348031234567");
+        assert_eq!(config.normalize("8031234567").unwrap(), "+2348031234567");
+    }
+
+    #[test]
+    fn international_prefixes_are_accepted() {
+        let config = config(&["NG", "GH", "KE"]);
+        assert_eq!(config.normalize("+233 24 123 4567").unwrap(), "+233241234567");
+        assert_eq!(config.normalize("00254712345678").unwrap(), "+254712345678");
+        assert_eq!(config.normalize("254712345678").unwrap(), "+254712345678");
+        assert_eq!(config.normalize("  +234.803.123.4567 ").unwrap(), "+2348031234567");
+    }
+
+    #[test]
+    fn trunk_zero_after_the_dial_code_is_dropped() {
+        let config = config(&["NG", "GH"]);
+        assert_eq!(config.normalize("+234 0803 123 4567").unwrap(), "+2348031234567");
+        assert_eq!(config.normalize("+233 024 123 4567").unwrap(), "+233241234567");
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        let config = config(&["NG", "GH", "KE"]);
+        for raw in ["", "   ", "0803123456", "080312345678", "0603 123 4567", "+1 415 555 0100", "0803abc4567"] {
+            assert!(matches!(config.normalize(raw), Err(AppError::ValidationError(_))), "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn rejects_countries_the_deployment_does_not_accept() {
+        let config = config(&["NG"]);
+        assert!(config.normalize("+254712345678").is_err());
+        assert!(config.normalize("+256712345678").is_err());
+        assert_eq!(config.normalize("+2348031234567").unwrap(), "+2348031234567");
+    }
+
+    #[test]
+    fn default_country_decides_national_numbers() {
+        let config = config(&["KE", "NG"]);
+        assert_eq!(config.normalize("0712 345 678").unwrap(), "+254712345678");
+        assert!(config.normalize("0803 123 4567").is_err());
+    }
+}